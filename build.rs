@@ -0,0 +1,488 @@
+//! Generate the block state and item registries from the vanilla data reports.
+//!
+//! `data/reports/blocks.json` is the `blocks.json` report generated by the vanilla 1.15.2 server
+//! (`java -cp server.jar net.minecraft.data.Main --reports`), and `data/block_data.json` holds the
+//! block attributes the report doesn't contain, by block type. Its `states` entries override the
+//! light attributes of the states with a property value, e.g. `"lit=true"`.
+//! `data/reports/registries.json` is the `registries.json` report trimmed to the item registry,
+//! completed by `data/item_data.json`.
+//! `data/recipes.json` gathers the vanilla recipe files (`data/minecraft/recipes/*.json`) by
+//! recipe name, with the item tags replaced by the lists of their items.
+
+use serde_json::{Map, Value};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const BLOCKS_REPORT: &str = "data/reports/blocks.json";
+const BLOCK_DATA: &str = "data/block_data.json";
+const REGISTRIES_REPORT: &str = "data/reports/registries.json";
+const ITEM_DATA: &str = "data/item_data.json";
+const RECIPES: &str = "data/recipes.json";
+
+struct Kind {
+    name: String,
+    first_state: u64,
+    default_state: u64,
+    properties: Vec<(String, Vec<String>)>,
+    data: Map<String, Value>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", BLOCKS_REPORT);
+    println!("cargo:rerun-if-changed={}", BLOCK_DATA);
+    println!("cargo:rerun-if-changed={}", REGISTRIES_REPORT);
+    println!("cargo:rerun-if-changed={}", ITEM_DATA);
+    println!("cargo:rerun-if-changed={}", RECIPES);
+
+    let report = read_json(BLOCKS_REPORT);
+    let data = read_json(BLOCK_DATA);
+
+    let mut kinds = report
+        .iter()
+        .map(|(name, block)| parse_kind(name, block, &data))
+        .collect::<Vec<_>>();
+    // The report is an object: its order isn't kept by serde_json, sort the kinds by state id.
+    kinds.sort_by_key(|kind| kind.first_state);
+
+    let destination = Path::new(&env::var("OUT_DIR").unwrap()).join("blocks.rs");
+    fs::write(destination, generate_blocks(&kinds)).unwrap();
+
+    let registries = read_json(REGISTRIES_REPORT);
+    let data = read_json(ITEM_DATA);
+    let mut items = registries["minecraft:item"]["entries"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, item)| {
+            let stack_size = data[name]["max_stack_size"].as_u64().unwrap();
+            (
+                name.clone(),
+                item["protocol_id"].as_u64().unwrap(),
+                stack_size,
+            )
+        })
+        .collect::<Vec<_>>();
+    items.sort_by_key(|(_, id, _)| *id);
+    for (index, (name, id, _)) in items.iter().enumerate() {
+        assert_eq!(index as u64, *id, "{}: item ids must be contiguous", name);
+    }
+
+    let destination = Path::new(&env::var("OUT_DIR").unwrap()).join("items.rs");
+    fs::write(destination, generate_items(&items)).unwrap();
+
+    let recipes = read_json(RECIPES);
+    let destination = Path::new(&env::var("OUT_DIR").unwrap()).join("recipes.rs");
+    fs::write(destination, generate_recipes(&recipes)).unwrap();
+}
+
+fn read_json(path: &str) -> Map<String, Value> {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    match serde_json::from_str(&content) {
+        Ok(Value::Object(map)) => map,
+        _ => panic!("{}: expected a JSON object", path),
+    }
+}
+
+fn parse_kind(name: &str, block: &Value, data: &Map<String, Value>) -> Kind {
+    let properties = block["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(property, values)| {
+                    let values = values
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|value| value.as_str().unwrap().to_string())
+                        .collect();
+                    (property.clone(), values)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let states = block["states"].as_array().unwrap();
+    let first_state = states
+        .iter()
+        .map(|s| s["id"].as_u64().unwrap())
+        .min()
+        .unwrap();
+    let default_state = states
+        .iter()
+        .find(|s| s["default"].as_bool() == Some(true))
+        .map(|s| s["id"].as_u64().unwrap())
+        .unwrap_or(first_state);
+
+    let mut kind = Kind {
+        name: name.to_string(),
+        first_state,
+        default_state,
+        properties,
+        data: data[name].as_object().cloned().unwrap_or_default(),
+    };
+
+    // serde_json sorts the properties by name, reorder them the way vanilla numbers states.
+    kind.properties = ordered_properties(&kind, states);
+    kind
+}
+
+/// States are numbered with the last property changing the fastest. Find the order of the
+/// properties from the properties of the second state, then the third...
+fn ordered_properties(kind: &Kind, states: &[Value]) -> Vec<(String, Vec<String>)> {
+    let mut remaining = kind.properties.clone();
+    let mut ordered = Vec::with_capacity(remaining.len());
+    let mut stride = 1;
+    while !remaining.is_empty() {
+        let state = states
+            .iter()
+            .find(|s| s["id"].as_u64().unwrap() == kind.first_state + stride)
+            .unwrap();
+        let position = remaining
+            .iter()
+            .position(|(name, values)| state["properties"][name] != values[0].as_str())
+            .unwrap();
+        let property = remaining.remove(position);
+        stride *= property.1.len() as u64;
+        ordered.insert(0, property);
+    }
+    ordered
+}
+
+fn camel_case(name: &str) -> String {
+    name.trim_start_matches("minecraft:")
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .unwrap()
+                .to_uppercase()
+                .chain(chars)
+                .collect::<String>()
+        })
+        .collect()
+}
+
+fn data_u8(kind: &Kind, key: &str) -> u64 {
+    kind.data.get(key).and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// The light attributes of the states having a given property value, e.g. `lit=true`, which
+/// replace the ones of the block type.
+fn light_states(kind: &Kind) -> String {
+    let states = match kind.data.get("states").and_then(Value::as_object) {
+        Some(states) => states,
+        None => return String::new(),
+    };
+    states
+        .iter()
+        .map(|(state, data)| {
+            let (property, value) = state.split_at(state.find('=').unwrap());
+            let attribute = |key| {
+                data.get(key)
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| data_u8(kind, key))
+            };
+            format!(
+                "({:?}, {:?}, {}, {})",
+                property,
+                &value[1..],
+                attribute("light_filter"),
+                attribute("light_emission")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn data_tool(kind: &Kind, key: &str) -> String {
+    match kind.data.get(key).and_then(Value::as_str) {
+        Some(tool) => format!("Some(ToolKind::{})", camel_case(tool)),
+        None => "None".to_string(),
+    }
+}
+
+fn harvest_tool(kind: &Kind) -> String {
+    let tool = kind.data.get("harvest_tool").and_then(Value::as_str);
+    let level = kind.data.get("harvest_level").and_then(Value::as_u64);
+    match (tool, level) {
+        (Some(tool), Some(level)) => format!("Some((ToolKind::{}, {}))", camel_case(tool), level),
+        _ => "None".to_string(),
+    }
+}
+
+/// The id of the block entity holding the data of the block, for the supported ones.
+fn block_entity(kind: &Kind) -> Option<&'static str> {
+    let name = kind.name.trim_start_matches("minecraft:");
+    match name {
+        "chest" => Some("minecraft:chest"),
+        "trapped_chest" => Some("minecraft:trapped_chest"),
+        "piston_head" => None,
+        _ if name.ends_with("_sign") => Some("minecraft:sign"),
+        _ if name.ends_with("_banner") => Some("minecraft:banner"),
+        _ if name.ends_with("_skull") || name.ends_with("_head") => Some("minecraft:skull"),
+        _ => None,
+    }
+}
+
+fn drop(kind: &Kind) -> String {
+    let item = match kind.data.get("drop").and_then(Value::as_str) {
+        Some(item) => item,
+        None => return "None".to_string(),
+    };
+    let count = kind
+        .data
+        .get("drop_count")
+        .and_then(Value::as_array)
+        .map(|count| (count[0].as_u64().unwrap(), count[1].as_u64().unwrap()))
+        .unwrap_or((1, 1));
+    let chance = kind
+        .data
+        .get("drop_chance")
+        .and_then(Value::as_f64)
+        .unwrap_or(1.);
+    format!(
+        "Some(Drop {{ item: Item::{}, count: {:?}, chance: {:?} }})",
+        camel_case(item),
+        count,
+        chance as f32
+    )
+}
+
+fn generate_blocks(kinds: &[Kind]) -> String {
+    let mut code = String::new();
+    let last = kinds.last().unwrap();
+    let last_state = last.first_state
+        + last
+            .properties
+            .iter()
+            .map(|(_, values)| values.len() as u64)
+            .product::<u64>()
+        - 1;
+
+    writeln!(code, "const LAST_STATE: u16 = {};", last_state).unwrap();
+
+    writeln!(
+        code,
+        "/// All the block types of the game, a type has one or multiple states."
+    )
+    .unwrap();
+    writeln!(code, "#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]").unwrap();
+    writeln!(code, "pub enum BlockKind {{").unwrap();
+    for kind in kinds {
+        writeln!(code, "    {},", camel_case(&kind.name)).unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+
+    writeln!(code, "static KINDS: [KindData; {}] = [", kinds.len()).unwrap();
+    for kind in kinds {
+        let properties = kind
+            .properties
+            .iter()
+            .map(|(name, values)| format!("({:?}, &{:?})", name, values))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            code,
+            "    KindData {{ kind: BlockKind::{}, name: {:?}, first_state: {}, default_state: {}, \
+             properties: &[{}], light_filter: {}, light_emission: {}, hardness: {:?}, tool: {}, \
+             light_states: &[{}], harvest_tool: {}, drop: {}, block_entity: {:?} }},",
+            camel_case(&kind.name),
+            kind.name,
+            kind.first_state,
+            kind.default_state,
+            properties,
+            data_u8(kind, "light_filter"),
+            data_u8(kind, "light_emission"),
+            kind.data["hardness"].as_f64().unwrap() as f32,
+            data_tool(kind, "tool"),
+            light_states(kind),
+            harvest_tool(kind),
+            drop(kind),
+            block_entity(kind),
+        )
+        .unwrap();
+    }
+    writeln!(code, "];").unwrap();
+
+    writeln!(code, "impl BlockKind {{").unwrap();
+    writeln!(
+        code,
+        "    /// Find a block type from its name, e.g. `minecraft:stone`."
+    )
+    .unwrap();
+    writeln!(code, "    pub fn from_name(name: &str) -> Option<Self> {{").unwrap();
+    writeln!(code, "        Some(match name {{").unwrap();
+    for kind in kinds {
+        writeln!(
+            code,
+            "            {:?} => BlockKind::{},",
+            kind.name,
+            camel_case(&kind.name)
+        )
+        .unwrap();
+    }
+    writeln!(code, "            _ => return None,").unwrap();
+    writeln!(code, "        }})").unwrap();
+    writeln!(code, "    }}").unwrap();
+    writeln!(code, "}}").unwrap();
+
+    writeln!(code, "#[allow(non_upper_case_globals)]").unwrap();
+    writeln!(code, "impl Block {{").unwrap();
+    for kind in kinds {
+        writeln!(code, "    /// Default state of `{}`.", kind.name).unwrap();
+        writeln!(
+            code,
+            "    pub const {}: Block = Block({});",
+            camel_case(&kind.name),
+            kind.default_state
+        )
+        .unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+
+    code
+}
+
+fn generate_items(items: &[(String, u64, u64)]) -> String {
+    let mut code = String::new();
+
+    writeln!(
+        code,
+        "/// All the items of the game, numbered by protocol id."
+    )
+    .unwrap();
+    writeln!(code, "#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]").unwrap();
+    writeln!(code, "pub enum Item {{").unwrap();
+    for (name, id, _) in items {
+        writeln!(code, "    {} = {},", camel_case(name), id).unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+
+    writeln!(code, "static ITEMS: [ItemData; {}] = [", items.len()).unwrap();
+    for (name, _, stack_size) in items {
+        writeln!(
+            code,
+            "    ItemData {{ item: Item::{}, name: {:?}, max_stack_size: {} }},",
+            camel_case(name),
+            name,
+            stack_size,
+        )
+        .unwrap();
+    }
+    writeln!(code, "];").unwrap();
+
+    writeln!(code, "impl Item {{").unwrap();
+    writeln!(
+        code,
+        "    /// Find an item from its name, e.g. `minecraft:stone`."
+    )
+    .unwrap();
+    writeln!(code, "    pub fn from_name(name: &str) -> Option<Self> {{").unwrap();
+    writeln!(code, "        Some(match name {{").unwrap();
+    for (name, _, _) in items {
+        writeln!(
+            code,
+            "            {:?} => Item::{},",
+            name,
+            camel_case(name)
+        )
+        .unwrap();
+    }
+    writeln!(code, "            _ => return None,").unwrap();
+    writeln!(code, "        }})").unwrap();
+    writeln!(code, "    }}").unwrap();
+    writeln!(code, "}}").unwrap();
+
+    code
+}
+
+fn item(name: &str) -> String {
+    format!("Item::{}", camel_case(name))
+}
+
+/// An ingredient is either an item or a list of items: `{"item": "minecraft:stick"}`.
+fn ingredient(name: &str, ingredient: &Value) -> String {
+    let items = match ingredient {
+        Value::Array(items) => items.iter().collect(),
+        ingredient => vec![ingredient],
+    };
+    let items = items
+        .iter()
+        .map(|ingredient| match ingredient["item"].as_str() {
+            Some(name) => item(name),
+            None => panic!("{}: only item ingredients are supported", name),
+        })
+        .collect::<Vec<_>>();
+    format!("&[{}]", items.join(", "))
+}
+
+fn generate_recipes(recipes: &Map<String, Value>) -> String {
+    let mut code = String::new();
+    writeln!(code, "static RECIPES: [Recipe; {}] = [", recipes.len()).unwrap();
+    for (name, recipe) in recipes {
+        let kind = match recipe["type"].as_str().unwrap() {
+            "minecraft:crafting_shaped" => {
+                let pattern = recipe["pattern"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|row| row.as_str().unwrap())
+                    .collect::<Vec<_>>();
+                let ingredients = pattern
+                    .iter()
+                    .flat_map(|row| row.chars())
+                    .map(|key| match key {
+                        ' ' => "&[]".to_string(),
+                        key => ingredient(name, &recipe["key"][key.to_string()]),
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "RecipeKind::Shaped {{ width: {}, height: {}, ingredients: &[{}] }}",
+                    pattern[0].len(),
+                    pattern.len(),
+                    ingredients.join(", ")
+                )
+            }
+            "minecraft:crafting_shapeless" => {
+                let ingredients = recipe["ingredients"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|value| ingredient(name, value))
+                    .collect::<Vec<_>>();
+                format!("RecipeKind::Shapeless(&[{}])", ingredients.join(", "))
+            }
+            "minecraft:smelting" => format!(
+                "RecipeKind::Smelting {{ ingredient: {}, experience: {:?}, cooking_time: {} }}",
+                ingredient(name, &recipe["ingredient"]),
+                recipe["experience"].as_f64().unwrap_or(0.) as f32,
+                recipe["cookingtime"].as_u64().unwrap_or(200),
+            ),
+            kind => panic!("{}: unsupported recipe type {}", name, kind),
+        };
+        // Crafting results are objects with a count, smelting results only an item name.
+        let (result, count) = match &recipe["result"] {
+            Value::String(result) => (result.as_str(), 1),
+            result => (
+                result["item"].as_str().unwrap(),
+                result["count"].as_u64().unwrap_or(1),
+            ),
+        };
+        writeln!(
+            code,
+            "    Recipe {{ name: {:?}, group: {:?}, kind: {}, result: {}, count: {} }},",
+            name,
+            recipe["group"].as_str().unwrap_or(""),
+            kind,
+            item(result),
+            count,
+        )
+        .unwrap();
+    }
+    writeln!(code, "];").unwrap();
+    code
+}
@@ -11,6 +11,7 @@ fn main() -> ! {
         .with_description("Rusty Minecraft Server".into())
         .with_icon_from_path("./examples/assets/server-icon.png")
         .unwrap()
+        .with_pregeneration_radius(8)
         .build_leak(FlatChunkGenerator::new());
 
     let world = smol::block_on(world);
@@ -9,6 +9,7 @@ use minecrust::packets::play::entity_position::{
 };
 use minecrust::packets::play::join_game::JoinGame;
 use minecrust::packets::play::keep_alive::KeepAlive;
+use minecrust::packets::play::light::UpdateLight;
 use minecrust::packets::play::player_digging::PlayerDigging;
 use minecrust::packets::play::player_info::PlayerInfo;
 use minecrust::packets::play::player_position::{
@@ -16,6 +17,9 @@ use minecrust::packets::play::player_position::{
     OutViewPosition,
 };
 use minecrust::packets::play::spawn_player::SpawnPlayer;
+use minecrust::packets::play::spawn_position::SpawnPosition;
+use minecrust::packets::play::time_update::TimeUpdate;
+use minecrust::packets::play::world_border::WorldBorder;
 use minecrust::types::{Receive, Send, Size, TAsyncRead, TAsyncWrite, VarInt};
 use piper::Arc;
 use serde::export::Formatter;
@@ -118,6 +122,8 @@ where
             *KeepAlive::PACKET_ID,
             0x22,
             *Chunk::PACKET_ID,
+            0x25,
+            *UpdateLight::PACKET_ID,
             0x26,
             *JoinGame::PACKET_ID,
             0x29,
@@ -135,9 +141,12 @@ where
             0x3C,
             *OutEntityHeadLook::PACKET_ID,
             // 0x1E, /* Unload chunk */
-            // 0x3E, /* World border */
-            // 0x25, /* Update light */
-            // 0x4F, /* Time update */
+            0x3E,
+            *WorldBorder::PACKET_ID,
+            0x4E,
+            *SpawnPosition::PACKET_ID,
+            0x4F,
+            *TimeUpdate::PACKET_ID,
             0x41,
             *OutViewPosition::PACKET_ID,
         ];
@@ -0,0 +1,144 @@
+use crate::packets::play::WorldBorder;
+use std::time::{Duration, Instant};
+
+/// The world border, a square centered on `center` whose diameter can move progressively.
+#[derive(Debug, Clone)]
+pub struct Border {
+    center: (f64, f64),
+    old_diameter: f64,
+    new_diameter: f64,
+    lerp_start: Instant,
+    lerp_time: Duration,
+    warning_time: i32,
+    warning_blocks: i32,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Self {
+            center: (0., 0.),
+            old_diameter: Self::MAX_DIAMETER,
+            new_diameter: Self::MAX_DIAMETER,
+            lerp_start: Instant::now(),
+            lerp_time: Duration::default(),
+            warning_time: 15,
+            warning_blocks: 5,
+        }
+    }
+}
+
+impl Border {
+    /// The default diameter, the border is then out of reach.
+    pub const MAX_DIAMETER: f64 = 60_000_000.;
+
+    pub fn center(&self) -> (f64, f64) {
+        self.center
+    }
+
+    /// The current diameter, between the old and the new one while the border is moving.
+    pub fn diameter(&self) -> f64 {
+        let elapsed = self.lerp_start.elapsed();
+        if elapsed >= self.lerp_time {
+            return self.new_diameter;
+        }
+        let progress = elapsed.as_secs_f64() / self.lerp_time.as_secs_f64();
+        self.old_diameter + (self.new_diameter - self.old_diameter) * progress
+    }
+
+    /// The diameter the border is moving to.
+    pub fn target_diameter(&self) -> f64 {
+        self.new_diameter
+    }
+
+    fn remaining(&self) -> Duration {
+        self.lerp_time
+            .checked_sub(self.lerp_start.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub fn warning_time(&self) -> i32 {
+        self.warning_time
+    }
+
+    pub fn warning_blocks(&self) -> i32 {
+        self.warning_blocks
+    }
+
+    /// Whether a position is inside the border.
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        let radius = self.diameter() / 2.;
+        (x - self.center.0).abs() <= radius && (z - self.center.1).abs() <= radius
+    }
+
+    /// The closest position inside the border.
+    pub fn clamp(&self, x: f64, z: f64) -> (f64, f64) {
+        let radius = self.diameter() / 2.;
+        let clamp = |value: f64, center: f64| value.clamp(center - radius, center + radius);
+        (clamp(x, self.center.0), clamp(z, self.center.1))
+    }
+
+    pub fn set_center(&mut self, x: f64, z: f64) -> WorldBorder {
+        self.center = (x, z);
+        WorldBorder::set_center(x, z)
+    }
+
+    /// Move the border to `diameter` over `time`, or right away if `time` is zero.
+    pub fn set_diameter(&mut self, diameter: f64, time: Duration) -> WorldBorder {
+        let diameter = diameter.clamp(1., Self::MAX_DIAMETER);
+        let old = self.diameter();
+        self.old_diameter = old;
+        self.new_diameter = diameter;
+        self.lerp_start = Instant::now();
+        self.lerp_time = time;
+        if time == Duration::default() {
+            WorldBorder::set_size(diameter)
+        } else {
+            WorldBorder::lerp_size(old, diameter, time.as_millis() as i64)
+        }
+    }
+
+    pub fn set_warning_time(&mut self, seconds: i32) -> WorldBorder {
+        self.warning_time = seconds;
+        WorldBorder::set_warning_time(seconds)
+    }
+
+    pub fn set_warning_blocks(&mut self, blocks: i32) -> WorldBorder {
+        self.warning_blocks = blocks;
+        WorldBorder::set_warning_blocks(blocks)
+    }
+
+    /// The packet sending the whole border to a player who just joined.
+    pub fn packet(&self) -> WorldBorder {
+        WorldBorder::initialize(
+            self.center,
+            self.diameter(),
+            self.new_diameter,
+            self.remaining().as_millis() as i64,
+            self.warning_time,
+            self.warning_blocks,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_border() {
+        let mut border = Border::default();
+        assert!(border.contains(1_000_000., -1_000_000.));
+
+        border.set_center(10., 0.);
+        border.set_diameter(20., Duration::default());
+        assert_eq!(border.diameter(), 20.);
+        assert!(border.contains(20., -10.));
+        assert!(!border.contains(-0.5, 0.));
+        assert_eq!(border.clamp(30., 5.), (20., 5.));
+
+        border.set_diameter(100., Duration::from_secs(3600));
+        let diameter = border.diameter();
+        assert!((20. ..21.).contains(&diameter));
+        assert_eq!(border.target_diameter(), 100.);
+    }
+}
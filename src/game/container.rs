@@ -0,0 +1,752 @@
+use crate::game::inventory::Inventory;
+use crate::packets::play::{ChestItems, ClickMode, ClickWindow, ItemStack, Recipe, WindowType};
+use crate::types::{chat::Chat, BlockPosition};
+use anyhow::{bail, ensure, Result};
+
+/// The slots of a window opened above the player inventory, e.g. a chest.
+pub trait Container: std::marker::Send + Sync {
+    fn window_type(&self) -> WindowType;
+    fn title(&self) -> Chat;
+    fn size(&self) -> u16;
+    fn get(&self, index: u16) -> Option<ItemStack>;
+    fn set(&mut self, index: u16, item: Option<ItemStack>);
+
+    /// Whether items can only be taken from the slot, e.g. a crafting result.
+    fn is_output(&self, _index: u16) -> bool {
+        false
+    }
+
+    /// Called when the item of an output slot is taken.
+    fn take_output(&mut self, _index: u16) {}
+
+    /// Whether shift-clicking an item of the player inventory moves it into the container.
+    fn accepts_shift_click(&self) -> bool {
+        true
+    }
+
+    /// The block holding the items, shared by all the players who opened it.
+    fn position(&self) -> Option<&BlockPosition> {
+        None
+    }
+
+    /// Empty the slots which don't keep their items once the window is closed.
+    fn close(&mut self) -> Vec<ItemStack> {
+        Vec::new()
+    }
+}
+
+pub struct Chest {
+    position: BlockPosition,
+    items: ChestItems,
+}
+
+impl Chest {
+    pub fn new(position: BlockPosition, items: ChestItems) -> Self {
+        Self { position, items }
+    }
+}
+
+impl Container for Chest {
+    fn window_type(&self) -> WindowType {
+        WindowType::Generic9x3
+    }
+
+    fn title(&self) -> Chat {
+        Chat::new("Chest")
+    }
+
+    fn size(&self) -> u16 {
+        ChestItems::SIZE
+    }
+
+    fn get(&self, index: u16) -> Option<ItemStack> {
+        self.items.get(index)
+    }
+
+    fn set(&mut self, index: u16, item: Option<ItemStack>) {
+        self.items.set(index, item);
+    }
+
+    fn position(&self) -> Option<&BlockPosition> {
+        Some(&self.position)
+    }
+}
+
+/// The 3x3 crafting grid of a crafting table, given back to the player on close.
+pub struct CraftingTable {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl CraftingTable {
+    pub const RESULT: u16 = 0;
+    pub const GRID: u16 = 1;
+    pub const SIZE: u16 = 10;
+
+    pub fn new() -> Self {
+        Self {
+            slots: vec![None; Self::SIZE as usize],
+        }
+    }
+}
+
+impl Default for CraftingTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Container for CraftingTable {
+    fn window_type(&self) -> WindowType {
+        WindowType::Crafting
+    }
+
+    fn title(&self) -> Chat {
+        Chat::new("Crafting")
+    }
+
+    fn size(&self) -> u16 {
+        Self::SIZE
+    }
+
+    fn get(&self, index: u16) -> Option<ItemStack> {
+        self.slots[index as usize].clone()
+    }
+
+    fn set(&mut self, index: u16, item: Option<ItemStack>) {
+        self.slots[index as usize] = item;
+        if index != Self::RESULT {
+            let result = Recipe::crafted(&self.slots[Self::GRID as usize..], 3);
+            self.slots[Self::RESULT as usize] = result.map(Recipe::result);
+        }
+    }
+
+    fn is_output(&self, index: u16) -> bool {
+        index == Self::RESULT
+    }
+
+    fn take_output(&mut self, _index: u16) {
+        for index in Self::GRID..Self::SIZE {
+            let item = self.get(index).and_then(ItemStack::used_in_recipe);
+            self.set(index, item);
+        }
+    }
+
+    fn accepts_shift_click(&self) -> bool {
+        false
+    }
+
+    fn close(&mut self) -> Vec<ItemStack> {
+        self.slots[Self::RESULT as usize] = None;
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+}
+
+/// A drag started by the player, with the slots the cursor went over.
+#[derive(Debug)]
+pub struct Drag {
+    /// 0 to split the items evenly, 1 for one item per slot, 2 for full stacks in creative.
+    button: u8,
+    slots: Vec<u16>,
+}
+
+/// The slots of a window, indexed like the client does: the container slots if any, then the
+/// player inventory.
+pub struct View<'a> {
+    container: Option<&'a mut dyn Container>,
+    inventory: &'a mut Inventory,
+}
+
+enum Location {
+    Container(u16),
+    Inventory(u16),
+}
+
+impl<'a> View<'a> {
+    /// Slots of the main inventory and the hotbar, shown under the containers.
+    const PLAYER_SLOTS: u16 = Inventory::OFF_HAND - Inventory::MAIN;
+
+    pub fn new(container: Option<&'a mut dyn Container>, inventory: &'a mut Inventory) -> Self {
+        Self {
+            container,
+            inventory,
+        }
+    }
+
+    pub fn len(&self) -> u16 {
+        match &self.container {
+            Some(container) => container.size() + Self::PLAYER_SLOTS,
+            None => Inventory::SIZE,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn locate(&self, index: u16) -> Location {
+        match &self.container {
+            Some(container) if index < container.size() => Location::Container(index),
+            Some(container) => Location::Inventory(Inventory::MAIN + index - container.size()),
+            None => Location::Inventory(index),
+        }
+    }
+
+    pub fn get(&self, index: u16) -> Option<ItemStack> {
+        match self.locate(index) {
+            Location::Container(index) => self.container.as_ref().unwrap().get(index),
+            Location::Inventory(index) => self.inventory.get(index).cloned(),
+        }
+    }
+
+    pub fn set(&mut self, index: u16, item: Option<ItemStack>) {
+        let item = item.filter(|item| item.count() > 0);
+        match self.locate(index) {
+            Location::Container(index) => self.container.as_mut().unwrap().set(index, item),
+            Location::Inventory(index) => self.inventory.set(index, item),
+        }
+    }
+
+    /// The content of all the slots, as sent in a Window Items packet.
+    pub fn items(&self) -> Vec<Option<ItemStack>> {
+        (0..self.len()).map(|index| self.get(index)).collect()
+    }
+
+    fn is_output(&self, index: u16) -> bool {
+        match self.locate(index) {
+            Location::Container(index) => self.container.as_ref().unwrap().is_output(index),
+            Location::Inventory(index) => index == Inventory::CRAFTING_RESULT,
+        }
+    }
+
+    fn take_output(&mut self, index: u16) {
+        match self.locate(index) {
+            Location::Container(index) => self.container.as_mut().unwrap().take_output(index),
+            Location::Inventory(_) => self.inventory.consume_crafting(),
+        }
+    }
+
+    /// The window index of a hotbar slot, from 0 to 8.
+    fn hotbar(&self, slot: u8) -> u16 {
+        let hotbar = match &self.container {
+            Some(container) => container.size() + 27,
+            None => Inventory::HOTBAR,
+        };
+        hotbar + slot as u16
+    }
+
+    /// The slots a shift-clicked item is moved to, in the order they are filled.
+    fn shift_targets(&self, index: u16) -> Vec<u16> {
+        let hotbar = self.hotbar(0);
+        let (player, end) = (hotbar - 27, hotbar + 9);
+        match &self.container {
+            Some(container) if index < container.size() => (player..end).rev().collect(),
+            Some(container) if container.accepts_shift_click() => (0..container.size())
+                .filter(|&index| !container.is_output(index))
+                .collect(),
+            _ if (player..hotbar).contains(&index) => (hotbar..end).collect(),
+            _ if (hotbar..end).contains(&index) => (player..hotbar).collect(),
+            _ => (player..end).collect(),
+        }
+    }
+
+    /// Move items to the existing stacks of the slots then to their empty slots, return what
+    /// didn't fit.
+    fn spread(&mut self, mut item: ItemStack, targets: &[u16]) -> Option<ItemStack> {
+        let max = item.item().max_stack_size() as i8;
+        for &index in targets {
+            if let Some(mut stack) = self.get(index) {
+                if stack.stacks_with(&item) && stack.count() < max {
+                    let moved = (max - stack.count()).min(item.count());
+                    stack.set_count(stack.count() + moved);
+                    item.set_count(item.count() - moved);
+                    self.set(index, Some(stack));
+                }
+            }
+            if item.count() == 0 {
+                return None;
+            }
+        }
+        for &index in targets {
+            if self.get(index).is_none() {
+                let mut stack = item.clone();
+                stack.set_count(max.min(item.count()));
+                item.set_count(item.count() - stack.count());
+                self.set(index, Some(stack));
+            }
+            if item.count() == 0 {
+                return None;
+            }
+        }
+        Some(item)
+    }
+
+    /// Apply a click of the player, return the items thrown out of the window.
+    pub fn click(
+        &mut self,
+        click: &ClickWindow,
+        drag: &mut Option<Drag>,
+        creative: bool,
+    ) -> Result<Vec<ItemStack>> {
+        if !matches!(click.mode, ClickMode::Drag(_)) {
+            *drag = None;
+        }
+        if click.slot == ClickWindow::OUTSIDE {
+            return Ok(match click.mode {
+                ClickMode::Click(button) => self.throw_cursor(button == 0),
+                ClickMode::Drag(button) => {
+                    self.drag(button, None, drag, creative);
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            });
+        }
+        // The client sends -1 for clicks on the border of the window.
+        if click.slot == -1 {
+            return Ok(Vec::new());
+        }
+        ensure!(
+            click.slot >= 0 && (click.slot as u16) < self.len(),
+            "invalid slot {}",
+            click.slot
+        );
+        let index = click.slot as u16;
+
+        match click.mode {
+            ClickMode::Click(button) => self.pick(index, button == 0),
+            ClickMode::ShiftClick(_) => self.shift_click(index),
+            ClickMode::NumberKey(slot) => self.swap(index, self.hotbar(slot)),
+            ClickMode::MiddleClick => {
+                if let (true, None, Some(mut item)) = (creative, self.cursor(), self.get(index)) {
+                    item.set_count(item.item().max_stack_size() as i8);
+                    self.inventory.set_cursor(Some(item));
+                }
+            }
+            ClickMode::Drop(button) => return Ok(self.throw_slot(index, button == 1)),
+            ClickMode::Drag(button) => self.drag(button, Some(index), drag, creative),
+            ClickMode::DoubleClick => self.collect(),
+        }
+        Ok(Vec::new())
+    }
+
+    fn cursor(&self) -> Option<ItemStack> {
+        self.inventory.cursor().cloned()
+    }
+
+    /// Left or right click on a slot.
+    fn pick(&mut self, index: u16, left: bool) {
+        let (slot, cursor) = (self.get(index), self.cursor());
+        if self.is_output(index) {
+            if let Some(output) = slot {
+                let cursor = match cursor {
+                    None => output,
+                    Some(mut cursor) => {
+                        let count = cursor.count() as i16 + output.count() as i16;
+                        if !cursor.stacks_with(&output)
+                            || count > output.item().max_stack_size() as i16
+                        {
+                            return;
+                        }
+                        cursor.set_count(count as i8);
+                        cursor
+                    }
+                };
+                self.inventory.set_cursor(Some(cursor));
+                self.set(index, None);
+                self.take_output(index);
+            }
+            return;
+        }
+
+        match (slot, cursor) {
+            (None, None) => {}
+            (Some(mut slot), None) => {
+                let taken = if left {
+                    slot.count()
+                } else {
+                    (slot.count() + 1) / 2
+                };
+                let mut cursor = slot.clone();
+                cursor.set_count(taken);
+                slot.set_count(slot.count() - taken);
+                self.inventory.set_cursor(Some(cursor));
+                self.set(index, Some(slot));
+            }
+            (None, Some(mut cursor)) => {
+                let mut slot = cursor.clone();
+                let placed = if left { cursor.count() } else { 1 };
+                slot.set_count(placed);
+                cursor.set_count(cursor.count() - placed);
+                self.set(index, Some(slot));
+                self.inventory.set_cursor(Some(cursor));
+            }
+            (Some(mut slot), Some(mut cursor)) if slot.stacks_with(&cursor) => {
+                let space = slot.item().max_stack_size() as i8 - slot.count();
+                let placed = if left { cursor.count() } else { 1 }.min(space.max(0));
+                slot.set_count(slot.count() + placed);
+                cursor.set_count(cursor.count() - placed);
+                self.set(index, Some(slot));
+                self.inventory.set_cursor(Some(cursor));
+            }
+            (Some(slot), Some(cursor)) => {
+                self.set(index, Some(cursor));
+                self.inventory.set_cursor(Some(slot));
+            }
+        }
+    }
+
+    fn shift_click(&mut self, index: u16) {
+        let targets = self.shift_targets(index);
+        if !self.is_output(index) {
+            if let Some(item) = self.get(index) {
+                let leftover = self.spread(item, &targets);
+                self.set(index, leftover);
+            }
+            return;
+        }
+
+        // Take the output as long as it fits entirely, e.g. to craft as much as possible.
+        let item = self.get(index).map(|output| output.item());
+        for _ in 0..64 {
+            let output = match self.get(index) {
+                Some(output) if Some(output.item()) == item => output,
+                _ => return,
+            };
+            let before = targets.iter().map(|&i| self.get(i)).collect::<Vec<_>>();
+            if self.spread(output, &targets).is_some() {
+                for (&target, item) in targets.iter().zip(before) {
+                    self.set(target, item);
+                }
+                return;
+            }
+            self.set(index, None);
+            self.take_output(index);
+        }
+    }
+
+    /// Swap a slot with a hotbar slot, output slots can only be moved to an empty slot.
+    fn swap(&mut self, index: u16, hotbar: u16) {
+        if index == hotbar {
+            return;
+        }
+        if self.is_output(index) {
+            if let (Some(output), None) = (self.get(index), self.get(hotbar)) {
+                self.set(hotbar, Some(output));
+                self.set(index, None);
+                self.take_output(index);
+            }
+            return;
+        }
+        let item = self.get(index);
+        self.set(index, self.get(hotbar));
+        self.set(hotbar, item);
+    }
+
+    fn throw_cursor(&mut self, all: bool) -> Vec<ItemStack> {
+        let mut cursor = match self.cursor() {
+            Some(cursor) => cursor,
+            None => return Vec::new(),
+        };
+        let mut thrown = cursor.clone();
+        if !all {
+            thrown.set_count(1);
+        }
+        cursor.set_count(cursor.count() - thrown.count());
+        self.inventory.set_cursor(Some(cursor));
+        vec![thrown]
+    }
+
+    fn throw_slot(&mut self, index: u16, stack: bool) -> Vec<ItemStack> {
+        let mut slot = match self.get(index) {
+            Some(slot) if self.cursor().is_none() => slot,
+            _ => return Vec::new(),
+        };
+        if self.is_output(index) {
+            self.set(index, None);
+            self.take_output(index);
+            return vec![slot];
+        }
+        let mut thrown = slot.clone();
+        if !stack {
+            thrown.set_count(1);
+        }
+        slot.set_count(slot.count() - thrown.count());
+        self.set(index, Some(slot));
+        vec![thrown]
+    }
+
+    /// Start a drag, add a slot to it, or spread the cursor over its slots.
+    fn drag(&mut self, button: u8, index: Option<u16>, drag: &mut Option<Drag>, creative: bool) {
+        let (kind, stage) = (button / 4, button % 4);
+        let cursor = match self.cursor() {
+            Some(cursor) if kind != 2 || creative => cursor,
+            _ => {
+                *drag = None;
+                return;
+            }
+        };
+        match (stage, index, drag.as_mut()) {
+            (0, None, _) => {
+                *drag = Some(Drag {
+                    button: kind,
+                    slots: Vec::new(),
+                })
+            }
+            (1, Some(index), Some(current)) if current.button == kind => {
+                let accepted = !self.is_output(index)
+                    && !matches!(self.get(index), Some(slot) if !slot.stacks_with(&cursor))
+                    && !current.slots.contains(&index)
+                    && (kind == 2 || current.slots.len() < cursor.count() as usize);
+                if accepted {
+                    current.slots.push(index);
+                }
+            }
+            (2, None, Some(current)) if current.button == kind => {
+                let current = drag.take().unwrap();
+                match current.slots.as_slice() {
+                    [] => {}
+                    [index] if kind != 2 => self.pick(*index, kind == 0),
+                    slots => self.spread_cursor(cursor, slots, kind),
+                }
+            }
+            _ => *drag = None,
+        }
+    }
+
+    fn spread_cursor(&mut self, mut cursor: ItemStack, slots: &[u16], kind: u8) {
+        let max = cursor.item().max_stack_size() as i8;
+        let each = match kind {
+            0 => cursor.count() / slots.len() as i8,
+            1 => 1,
+            _ => max,
+        };
+        for &index in slots {
+            let current = self.get(index).map_or(0, |slot| slot.count());
+            let added = each.min(max - current).max(0);
+            let mut slot = cursor.clone();
+            slot.set_count(current + added);
+            self.set(index, Some(slot));
+            if kind != 2 {
+                cursor.set_count(cursor.count() - added);
+            }
+        }
+        self.inventory.set_cursor(Some(cursor));
+    }
+
+    /// Gather the items stacking with the cursor, from the smallest stacks.
+    fn collect(&mut self) {
+        let mut cursor = match self.cursor() {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let max = cursor.item().max_stack_size() as i8;
+        for full in &[false, true] {
+            for index in 0..self.len() {
+                if cursor.count() >= max || self.is_output(index) {
+                    continue;
+                }
+                if let Some(mut slot) = self.get(index) {
+                    if slot.stacks_with(&cursor) && (slot.count() == max) == *full {
+                        let moved = (max - cursor.count()).min(slot.count());
+                        cursor.set_count(cursor.count() + moved);
+                        slot.set_count(slot.count() - moved);
+                        self.set(index, Some(slot));
+                    }
+                }
+            }
+        }
+        self.inventory.set_cursor(Some(cursor));
+    }
+}
+
+/// The container window opened by a player, if any.
+#[derive(Default)]
+pub struct Windows {
+    open: Option<(u8, Box<dyn Container>)>,
+    drag: Option<Drag>,
+    last_id: u8,
+}
+
+impl Windows {
+    /// Open a container and return the id of its window, from 1 to 100. The previous window
+    /// is replaced and returned.
+    pub fn open(&mut self, container: Box<dyn Container>) -> (u8, Option<Box<dyn Container>>) {
+        self.last_id = self.last_id % 100 + 1;
+        self.drag = None;
+        let previous = self.open.replace((self.last_id, container));
+        (self.last_id, previous.map(|(_, container)| container))
+    }
+
+    /// Close the window with this id, 0 for the player inventory.
+    pub fn close(&mut self, id: u8) -> Option<Box<dyn Container>> {
+        self.drag = None;
+        match &self.open {
+            Some((open, _)) if *open == id => self.open.take().map(|(_, container)| container),
+            _ => None,
+        }
+    }
+
+    /// The id of the opened window, 0 for the player inventory.
+    pub fn id(&self) -> u8 {
+        self.open.as_ref().map_or(0, |(id, _)| *id)
+    }
+
+    pub fn container(&self) -> Option<&dyn Container> {
+        self.open.as_ref().map(|(_, container)| &**container)
+    }
+
+    /// The slots of the window with this id, and the state of the current drag.
+    pub fn view<'a>(
+        &'a mut self,
+        id: u8,
+        inventory: &'a mut Inventory,
+    ) -> Result<(View<'a>, &'a mut Option<Drag>)> {
+        let container: Option<&mut dyn Container> = match (&mut self.open, id) {
+            (_, 0) => None,
+            (Some((open, container)), id) if *open == id => Some(&mut **container),
+            _ => bail!("window {} isn't opened", id),
+        };
+        Ok((View::new(container, inventory), &mut self.drag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Item;
+
+    fn click(slot: i16, mode: ClickMode, item: Option<ItemStack>) -> ClickWindow {
+        ClickWindow {
+            window: 1,
+            slot,
+            action: 1,
+            mode,
+            item,
+        }
+    }
+
+    #[test]
+    fn test_pick() -> Result<()> {
+        let mut inventory = Inventory::new();
+        let mut chest = Chest::new(BlockPosition::new(0, 0, 0), ChestItems::new());
+        chest.set(0, Some(ItemStack::new(Item::Stone, 5)));
+        let mut drag = None;
+        let mut view = View::new(Some(&mut chest), &mut inventory);
+        assert_eq!(view.len(), 63);
+
+        // Take half of the stack, then put one item back.
+        view.click(&click(0, ClickMode::Click(1), None), &mut drag, false)?;
+        assert_eq!(view.get(0).unwrap().count(), 2);
+        assert_eq!(view.cursor().unwrap().count(), 3);
+        view.click(&click(27, ClickMode::Click(1), None), &mut drag, false)?;
+        assert_eq!(view.get(27).unwrap().count(), 1);
+        view.click(&click(0, ClickMode::Click(0), None), &mut drag, false)?;
+        assert_eq!(view.get(0).unwrap().count(), 4);
+        assert_eq!(view.cursor(), None);
+
+        assert!(view
+            .click(&click(63, ClickMode::Click(0), None), &mut drag, false)
+            .is_err());
+        assert_eq!(inventory.get(Inventory::MAIN).unwrap().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shift_click() -> Result<()> {
+        let mut inventory = Inventory::new();
+        inventory.set(Inventory::MAIN, Some(ItemStack::new(Item::Stone, 10)));
+        let mut chest = Chest::new(BlockPosition::new(0, 0, 0), ChestItems::new());
+        chest.set(4, Some(ItemStack::new(Item::Stone, 60)));
+        let mut drag = None;
+        let mut view = View::new(Some(&mut chest), &mut inventory);
+
+        view.click(&click(27, ClickMode::ShiftClick(0), None), &mut drag, false)?;
+        assert_eq!(view.get(4).unwrap().count(), 64);
+        assert_eq!(view.get(0).unwrap().count(), 6);
+        assert_eq!(view.get(27), None);
+
+        // From the chest to the last slot of the hotbar.
+        view.click(&click(0, ClickMode::ShiftClick(0), None), &mut drag, false)?;
+        assert_eq!(view.get(62).unwrap().count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_drag() -> Result<()> {
+        let mut inventory = Inventory::new();
+        inventory.set_cursor(Some(ItemStack::new(Item::Stone, 7)));
+        let mut drag = None;
+        let mut view = View::new(None, &mut inventory);
+
+        for (slot, button) in &[(ClickWindow::OUTSIDE, 0), (9, 1), (10, 1), (0, 1), (2, 2)] {
+            let slot = if *button == 2 {
+                ClickWindow::OUTSIDE
+            } else {
+                *slot
+            };
+            view.click(
+                &click(slot, ClickMode::Drag(*button), None),
+                &mut drag,
+                false,
+            )?;
+        }
+        assert_eq!(view.get(9).unwrap().count(), 3);
+        assert_eq!(view.get(10).unwrap().count(), 3);
+        assert_eq!(view.get(0), None);
+        assert_eq!(view.cursor().unwrap().count(), 1);
+        assert!(drag.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_crafting_table() -> Result<()> {
+        let mut inventory = Inventory::new();
+        let mut table = CraftingTable::new();
+        table.set(
+            CraftingTable::GRID,
+            Some(ItemStack::new(Item::OakPlanks, 2)),
+        );
+        let mut drag = None;
+        let mut view = View::new(Some(&mut table), &mut inventory);
+
+        // Items can't be put in the result slot.
+        view.click(&click(1, ClickMode::Click(0), None), &mut drag, false)?;
+        view.click(&click(0, ClickMode::Click(0), None), &mut drag, false)?;
+        assert_eq!(view.get(0), None);
+
+        // Items are thrown when dropped outside of the window.
+        let thrown = view.click(
+            &click(ClickWindow::OUTSIDE, ClickMode::Click(1), None),
+            &mut drag,
+            false,
+        )?;
+        assert_eq!(thrown, vec![ItemStack::new(Item::OakPlanks, 1)]);
+        view.click(&click(1, ClickMode::Click(0), None), &mut drag, false)?;
+        assert_eq!(table.close(), vec![ItemStack::new(Item::OakPlanks, 1)]);
+        assert_eq!(table.get(CraftingTable::GRID), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_craft() -> Result<()> {
+        let mut inventory = Inventory::new();
+        let mut table = CraftingTable::new();
+        table.set(1, Some(ItemStack::new(Item::OakPlanks, 3)));
+        table.set(4, Some(ItemStack::new(Item::OakPlanks, 2)));
+        assert_eq!(table.get(0), Some(ItemStack::new(Item::Stick, 4)));
+        let mut drag = None;
+        let mut view = View::new(Some(&mut table), &mut inventory);
+
+        view.click(&click(0, ClickMode::Click(0), None), &mut drag, false)?;
+        assert_eq!(view.cursor(), Some(ItemStack::new(Item::Stick, 4)));
+        assert_eq!(view.get(4).unwrap().count(), 1);
+
+        // Craft until the planks of a slot run out.
+        view.click(&click(0, ClickMode::ShiftClick(0), None), &mut drag, false)?;
+        assert_eq!(view.get(45), Some(ItemStack::new(Item::Stick, 4)));
+        assert_eq!(view.get(1).unwrap().count(), 1);
+        assert_eq!(view.get(4), None);
+        // A single plank makes a button.
+        assert_eq!(view.get(0).unwrap().item(), Item::OakButton);
+        Ok(())
+    }
+}
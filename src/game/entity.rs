@@ -0,0 +1,184 @@
+use crate::packets::play::{
+    EntityMetadata, EntityType, ItemStack, LivingType, SpawnEntity, SpawnLivingEntity,
+};
+use crate::types::{EntityPosition, VarInt};
+use uuid::Uuid;
+
+/// The packet spawning an entity on the client, which depends on its type.
+#[derive(Debug)]
+pub enum Spawn {
+    Object(SpawnEntity),
+    Living(SpawnLivingEntity),
+}
+
+/// An entity of the world other than a player, spawned for the players who loaded its chunk.
+pub trait Entity: std::marker::Send + Sync {
+    fn id(&self) -> VarInt;
+    fn position(&self) -> &EntityPosition;
+    fn spawn(&self) -> Spawn;
+
+    /// The distance in chunks under which players see the entity, if they see that far.
+    fn tracking_range(&self) -> i32 {
+        8
+    }
+
+    /// The metadata sent after spawning the entity, if it isn't the default one.
+    fn metadata(&self) -> Option<EntityMetadata> {
+        None
+    }
+
+    /// Update the entity once per tick, returns false once it should be removed.
+    fn tick(&mut self) -> bool {
+        true
+    }
+}
+
+/// A random version 4 UUID, for entities which aren't players.
+pub fn random_uuid() -> Uuid {
+    uuid::Builder::from_bytes(rand::random())
+        .set_variant(uuid::Variant::RFC4122)
+        .set_version(uuid::Version::Random)
+        .build()
+}
+
+/// A dropped stack of items, its motion is only simulated by the clients.
+pub struct ItemEntity {
+    id: VarInt,
+    uuid: Uuid,
+    position: EntityPosition,
+    velocity: (f64, f64, f64),
+    item: ItemStack,
+    age: u32,
+}
+
+impl ItemEntity {
+    /// Five minutes, the time before an item despawns.
+    pub const LIFETIME: u32 = 6000;
+
+    /// `velocity` is in blocks per tick.
+    pub fn new(
+        id: VarInt,
+        (x, y, z): (f64, f64, f64),
+        velocity: (f64, f64, f64),
+        item: ItemStack,
+    ) -> Self {
+        Self {
+            id,
+            uuid: random_uuid(),
+            position: EntityPosition::new(x, y, z, 0, 0),
+            velocity,
+            item,
+            age: 0,
+        }
+    }
+
+    pub fn item(&self) -> &ItemStack {
+        &self.item
+    }
+}
+
+impl Entity for ItemEntity {
+    fn id(&self) -> VarInt {
+        self.id
+    }
+
+    fn position(&self) -> &EntityPosition {
+        &self.position
+    }
+
+    fn spawn(&self) -> Spawn {
+        let position = (self.position.x, self.position.y, self.position.z);
+        // Players who start tracking the item later see it fall from its spawn position.
+        let velocity = if self.age == 0 {
+            self.velocity
+        } else {
+            (0., 0., 0.)
+        };
+        Spawn::Object(SpawnEntity::new(
+            self.id,
+            self.uuid,
+            EntityType::Item,
+            position,
+            velocity,
+        ))
+    }
+
+    fn tracking_range(&self) -> i32 {
+        6
+    }
+
+    fn metadata(&self) -> Option<EntityMetadata> {
+        Some(EntityMetadata::item(self.id, self.item.clone()))
+    }
+
+    fn tick(&mut self) -> bool {
+        self.age += 1;
+        self.age < Self::LIFETIME
+    }
+}
+
+/// A living entity without behavior, e.g. an armor stand or a mob standing still.
+pub struct LivingEntity {
+    id: VarInt,
+    uuid: Uuid,
+    kind: LivingType,
+    position: EntityPosition,
+}
+
+impl LivingEntity {
+    pub fn new(id: VarInt, kind: LivingType, position: EntityPosition) -> Self {
+        Self {
+            id,
+            uuid: random_uuid(),
+            kind,
+            position,
+        }
+    }
+
+    pub fn kind(&self) -> LivingType {
+        self.kind
+    }
+}
+
+impl Entity for LivingEntity {
+    fn id(&self) -> VarInt {
+        self.id
+    }
+
+    fn position(&self) -> &EntityPosition {
+        &self.position
+    }
+
+    fn spawn(&self) -> Spawn {
+        Spawn::Living(SpawnLivingEntity::new(
+            self.id,
+            self.uuid,
+            self.kind,
+            &self.position,
+        ))
+    }
+
+    fn tracking_range(&self) -> i32 {
+        match self.kind {
+            LivingType::ArmorStand => 10,
+            _ => 8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Item;
+
+    #[test]
+    fn test_item_lifetime() {
+        let item = ItemStack::new(Item::Stone, 1);
+        let mut entity = ItemEntity::new(VarInt(1), (0.5, 64., 0.5), (0., 0.2, 0.), item);
+        assert!(matches!(entity.spawn(), Spawn::Object(_)));
+        for _ in 1..ItemEntity::LIFETIME {
+            assert!(entity.tick());
+        }
+        assert!(!entity.tick());
+    }
+}
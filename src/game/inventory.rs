@@ -0,0 +1,248 @@
+use crate::packets::play::{Hand, Item, ItemStack, Recipe};
+use std::collections::BTreeSet;
+
+/// The inventory of a player, indexed like the slots of the player inventory window.
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    selected: u8,
+    cursor: Option<ItemStack>,
+    changes: BTreeSet<u16>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArmorSlot {
+    Head,
+    Chest,
+    Legs,
+    Feet,
+}
+
+impl Inventory {
+    pub const SIZE: u16 = 46;
+    pub const CRAFTING_RESULT: u16 = 0;
+    pub const CRAFTING: u16 = 1;
+    pub const ARMOR: u16 = 5;
+    pub const MAIN: u16 = 9;
+    pub const HOTBAR: u16 = 36;
+    pub const OFF_HAND: u16 = 45;
+
+    pub fn new() -> Self {
+        Self {
+            slots: vec![None; Self::SIZE as usize],
+            selected: 0,
+            cursor: None,
+            changes: BTreeSet::new(),
+        }
+    }
+
+    pub fn get(&self, index: u16) -> Option<&ItemStack> {
+        self.slots.get(index as usize)?.as_ref()
+    }
+
+    /// Set the content of a slot, stacks of 0 items empty the slot.
+    pub fn set(&mut self, index: u16, item: Option<ItemStack>) {
+        if let Some(slot) = self.slots.get_mut(index as usize) {
+            *slot = item.filter(|item| item.count() > 0);
+            self.changes.insert(index);
+        }
+        if (Self::CRAFTING..Self::ARMOR).contains(&index) {
+            let grid = &self.slots[Self::CRAFTING as usize..Self::ARMOR as usize];
+            let result = Recipe::crafted(grid, 2).map(Recipe::result);
+            self.slots[Self::CRAFTING_RESULT as usize] = result;
+            self.changes.insert(Self::CRAFTING_RESULT);
+        }
+    }
+
+    /// Use the items of the crafting grid once, after its result was taken.
+    pub fn consume_crafting(&mut self) {
+        for index in Self::CRAFTING..Self::ARMOR {
+            if let Some(item) = self.get(index).cloned() {
+                self.set(index, item.used_in_recipe());
+            }
+        }
+    }
+
+    /// All the slots, empty slots included.
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn armor(&self, slot: ArmorSlot) -> Option<&ItemStack> {
+        self.get(Self::ARMOR + slot as u16)
+    }
+
+    /// The index of the selected hotbar slot, from 0 to 8.
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    pub fn set_selected(&mut self, selected: u8) {
+        self.selected = selected.min(8);
+    }
+
+    /// The index of the slot held in the given hand.
+    pub fn held_index(&self, hand: Hand) -> u16 {
+        match hand {
+            Hand::Main => Self::HOTBAR + self.selected as u16,
+            Hand::Off => Self::OFF_HAND,
+        }
+    }
+
+    pub fn held(&self, hand: Hand) -> Option<&ItemStack> {
+        self.get(self.held_index(hand))
+    }
+
+    /// The item held by the mouse cursor while a window is open.
+    pub fn cursor(&self) -> Option<&ItemStack> {
+        self.cursor.as_ref()
+    }
+
+    pub fn set_cursor(&mut self, item: Option<ItemStack>) {
+        self.cursor = item.filter(|item| item.count() > 0);
+    }
+
+    /// The storage slots, in the order they are filled: hotbar then main inventory.
+    fn storage() -> impl Iterator<Item = u16> {
+        (Self::HOTBAR..Self::OFF_HAND).chain(Self::MAIN..Self::HOTBAR)
+    }
+
+    /// Add items to the existing stacks then to the empty slots, return what didn't fit.
+    pub fn give(&mut self, mut item: ItemStack) -> Option<ItemStack> {
+        let max = item.item().max_stack_size() as i8;
+        for index in Self::storage() {
+            if let Some(stack) = &self.slots[index as usize] {
+                if stack.stacks_with(&item) && stack.count() < max {
+                    let moved = (max - stack.count()).min(item.count());
+                    let mut stack = stack.clone();
+                    stack.set_count(stack.count() + moved);
+                    item.set_count(item.count() - moved);
+                    self.set(index, Some(stack));
+                }
+            }
+            if item.count() == 0 {
+                return None;
+            }
+        }
+
+        for index in Self::storage() {
+            if self.slots[index as usize].is_none() {
+                let mut stack = item.clone();
+                let moved = max.min(item.count());
+                stack.set_count(moved);
+                item.set_count(item.count() - moved);
+                self.set(index, Some(stack));
+            }
+            if item.count() == 0 {
+                return None;
+            }
+        }
+        Some(item)
+    }
+
+    /// Remove up to `count` items of a type from the storage slots, return how many were
+    /// removed.
+    pub fn take(&mut self, item: Item, count: u32) -> u32 {
+        let mut taken = 0;
+        for index in Self::storage().chain(std::iter::once(Self::OFF_HAND)) {
+            if taken == count {
+                break;
+            }
+            if let Some(stack) = &self.slots[index as usize] {
+                if stack.item() == item {
+                    let mut stack = stack.clone();
+                    let removed = (stack.count() as u32).min(count - taken);
+                    stack.set_count(stack.count() - removed as i8);
+                    taken += removed;
+                    self.set(index, Some(stack));
+                }
+            }
+        }
+        taken
+    }
+
+    /// The number of items of a type in the inventory.
+    pub fn count(&self, item: Item) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item() == item)
+            .map(|stack| stack.count() as u32)
+            .sum()
+    }
+
+    /// The indexes of the slots changed since the last call.
+    pub fn take_changes(&mut self) -> Vec<u16> {
+        std::mem::take(&mut self.changes).into_iter().collect()
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_give() {
+        let mut inventory = Inventory::new();
+        assert_eq!(inventory.give(ItemStack::new(Item::Stone, 10)), None);
+        assert_eq!(inventory.give(ItemStack::new(Item::Stone, 60)), None);
+        assert_eq!(inventory.get(Inventory::HOTBAR).unwrap().count(), 64);
+        assert_eq!(inventory.get(Inventory::HOTBAR + 1).unwrap().count(), 6);
+        assert_eq!(inventory.count(Item::Stone), 70);
+        assert_eq!(
+            inventory.take_changes(),
+            vec![Inventory::HOTBAR, Inventory::HOTBAR + 1]
+        );
+        assert!(inventory.take_changes().is_empty());
+
+        for _ in 0..33 {
+            assert_eq!(inventory.give(ItemStack::new(Item::Dirt, 64)), None);
+        }
+        let leftover = inventory.give(ItemStack::new(Item::Dirt, 100)).unwrap();
+        assert_eq!(leftover.count(), 36);
+        assert!(inventory.get(Inventory::MAIN).is_some());
+        assert!(inventory.get(Inventory::OFF_HAND).is_none());
+    }
+
+    #[test]
+    fn test_take() {
+        let mut inventory = Inventory::new();
+        inventory.give(ItemStack::new(Item::Stone, 10));
+        inventory.set(Inventory::MAIN, Some(ItemStack::new(Item::Stone, 5)));
+        assert_eq!(inventory.take(Item::Stone, 12), 12);
+        assert_eq!(inventory.get(Inventory::HOTBAR), None);
+        assert_eq!(inventory.count(Item::Stone), 3);
+        assert_eq!(inventory.take(Item::Stone, 12), 3);
+        assert_eq!(inventory.take(Item::Dirt, 1), 0);
+    }
+
+    #[test]
+    fn test_crafting() {
+        let mut inventory = Inventory::new();
+        inventory.set(Inventory::CRAFTING, Some(ItemStack::new(Item::OakLog, 2)));
+        let planks = ItemStack::new(Item::OakPlanks, 4);
+        assert_eq!(inventory.get(Inventory::CRAFTING_RESULT), Some(&planks));
+
+        inventory.consume_crafting();
+        assert_eq!(inventory.get(Inventory::CRAFTING_RESULT), Some(&planks));
+        inventory.consume_crafting();
+        assert_eq!(inventory.get(Inventory::CRAFTING), None);
+        assert_eq!(inventory.get(Inventory::CRAFTING_RESULT), None);
+    }
+
+    #[test]
+    fn test_held() {
+        let mut inventory = Inventory::new();
+        inventory.set(Inventory::HOTBAR + 2, Some(ItemStack::new(Item::Stone, 1)));
+        assert_eq!(inventory.held(Hand::Main), None);
+        inventory.set_selected(2);
+        assert_eq!(inventory.held(Hand::Main).unwrap().item(), Item::Stone);
+        assert_eq!(inventory.held(Hand::Off), None);
+    }
+}
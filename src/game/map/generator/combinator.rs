@@ -0,0 +1,568 @@
+use super::decorator::{DecoratedGenerator, Populator};
+use super::ChunkGenerator;
+use crate::game::map::structure::Structure;
+use crate::packets::play::{Block, Chunk};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// A type erased generator, so generators of different types can be combined.
+pub struct BoxedGenerator(Box<dyn ChunkGenerator + Sync + Send>);
+
+impl BoxedGenerator {
+    pub fn new(generator: impl ChunkGenerator + Sync + Send + 'static) -> Self {
+        Self(Box::new(generator))
+    }
+}
+
+impl ChunkGenerator for BoxedGenerator {
+    fn chunk(&self, x: i32, z: i32) -> Chunk {
+        self.0.chunk(x, z)
+    }
+
+    fn seed(&self) -> i64 {
+        self.0.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.0.height(x, z)
+    }
+}
+
+/// Only keep the blocks of an area, everything outside of it is void.
+pub struct Bounded<G> {
+    generator: G,
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+impl<G: ChunkGenerator> Bounded<G> {
+    /// Keep the columns between `min` and `max` (inclusive), in world (x, z) coordinates.
+    pub fn new(generator: G, min: (i32, i32), max: (i32, i32)) -> Self {
+        assert!(min.0 <= max.0 && min.1 <= max.1);
+        Self {
+            generator,
+            min,
+            max,
+        }
+    }
+
+    fn contains(&self, x: i32, z: i32) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&z)
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for Bounded<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let (x, z) = (c_x * 16, c_z * 16);
+        if x + 15 < self.min.0 || x > self.max.0 || z + 15 < self.min.1 || z > self.max.1 {
+            return Chunk::new(c_x, c_z);
+        }
+
+        let mut chunk = self.generator.chunk(c_x, c_z);
+        for l_z in 0..16_u8 {
+            for l_x in 0..16_u8 {
+                if self.contains(x + l_x as i32, z + l_z as i32) {
+                    continue;
+                }
+                for y in (0..=chunk.height(l_x, l_z)).rev() {
+                    chunk.set_block(l_x, y, l_z, Block::Air);
+                }
+            }
+        }
+        chunk
+    }
+
+    fn seed(&self) -> i64 {
+        self.generator.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        if self.contains(x, z) {
+            self.generator.height(x, z)
+        } else {
+            0
+        }
+    }
+}
+
+/// Stack generators on top of each other: each layer replaces the blocks of a range of heights
+/// by the blocks of its own generator.
+/// ```
+/// use minecrust::game::map::generator::combinator::Layered;
+/// use minecrust::game::map::generator::{ChunkGenerator, FlatChunkGenerator, NoiseChunkGenerator};
+/// use minecrust::packets::play::block::Block;
+///
+/// // The top of the mountains floating above a flat world.
+/// let generator = Layered::new(FlatChunkGenerator::new())
+///     .with_layer(100, 255, NoiseChunkGenerator::new(42));
+/// assert_eq!(generator.chunk(0, 0).get_block(0, 3, 0), Block::GrassBlock);
+/// ```
+pub struct Layered<G> {
+    base: G,
+    layers: Vec<(u16, u16, BoxedGenerator)>,
+}
+
+impl<G: ChunkGenerator> Layered<G> {
+    pub fn new(base: G) -> Self {
+        Self {
+            base,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Add a layer in place, copying the blocks of `generator` from `min_y` to `max_y`
+    /// (inclusive). Later layers override earlier ones
+    pub fn add_layer(
+        &mut self,
+        min_y: u16,
+        max_y: u16,
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) {
+        assert!(min_y <= max_y && max_y < 256);
+        self.layers
+            .push((min_y, max_y, BoxedGenerator::new(generator)));
+    }
+
+    /// Add a layer, copying the blocks of `generator` from `min_y` to `max_y` (inclusive).
+    /// Later layers override earlier ones
+    pub fn with_layer(
+        mut self,
+        min_y: u16,
+        max_y: u16,
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) -> Self {
+        self.add_layer(min_y, max_y, generator);
+        self
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for Layered<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let mut chunk = self.base.chunk(c_x, c_z);
+        for (min_y, max_y, generator) in &self.layers {
+            let layer = generator.chunk(c_x, c_z);
+            for y in *min_y..=*max_y {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        chunk.set_block(x, y, z, layer.get_block(x, y, z));
+                    }
+                }
+            }
+        }
+        chunk
+    }
+
+    fn seed(&self) -> i64 {
+        self.base.seed()
+    }
+
+    /// Assumes the columns don't have holes: a layer without blocks lowers the ground to the
+    /// bottom of its range.
+    fn height(&self, x: i32, z: i32) -> u16 {
+        let mut height = self.base.height(x, z);
+        for (min_y, max_y, generator) in &self.layers {
+            if height > *max_y {
+                continue;
+            }
+            let layer = generator.height(x, z);
+            if layer >= *min_y {
+                height = layer.min(*max_y);
+            } else if height >= *min_y {
+                height = min_y.saturating_sub(1);
+            }
+        }
+        height
+    }
+}
+
+/// Use different generators for different regions of the world, e.g. one per mini-game arena.
+///
+/// Regions are rectangles of chunks, the first region containing a chunk generates it and
+/// chunks outside of every region are generated by the default generator.
+pub struct RegionDispatch<G> {
+    default: G,
+    regions: Vec<Region>,
+}
+
+struct Region {
+    min: (i32, i32),
+    max: (i32, i32),
+    generator: BoxedGenerator,
+}
+
+impl<G: ChunkGenerator> RegionDispatch<G> {
+    pub fn new(default: G) -> Self {
+        Self {
+            default,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Add a region in place, from chunk `min` to chunk `max` (inclusive)
+    pub fn add_region(
+        &mut self,
+        min: (i32, i32),
+        max: (i32, i32),
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) {
+        assert!(min.0 <= max.0 && min.1 <= max.1);
+        self.regions.push(Region {
+            min,
+            max,
+            generator: BoxedGenerator::new(generator),
+        });
+    }
+
+    /// Add a region, from chunk `min` to chunk `max` (inclusive)
+    pub fn with_region(
+        mut self,
+        min: (i32, i32),
+        max: (i32, i32),
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) -> Self {
+        self.add_region(min, max, generator);
+        self
+    }
+
+    fn generator(&self, c_x: i32, c_z: i32) -> &dyn ChunkGenerator {
+        self.regions
+            .iter()
+            .find(|Region { min, max, .. }| {
+                (min.0..=max.0).contains(&c_x) && (min.1..=max.1).contains(&c_z)
+            })
+            .map_or(&self.default as &dyn ChunkGenerator, |region| {
+                &region.generator as &dyn ChunkGenerator
+            })
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for RegionDispatch<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        self.generator(c_x, c_z).chunk(c_x, c_z)
+    }
+
+    fn seed(&self) -> i64 {
+        self.default.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.generator(x.div_euclid(16), z.div_euclid(16))
+            .height(x, z)
+    }
+}
+
+/// Paste a structure (e.g. a schematic) on top of the chunks of another generator.
+pub struct Overlay<G> {
+    generator: G,
+    structure: Structure,
+    position: (i32, u16, i32),
+}
+
+impl<G: ChunkGenerator> Overlay<G> {
+    /// Paste `structure` with its lowest corner at the `(x, y, z)` world coordinates.
+    pub fn new(generator: G, structure: Structure, position: (i32, u16, i32)) -> Self {
+        Self {
+            generator,
+            structure,
+            position,
+        }
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for Overlay<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let mut chunk = self.generator.chunk(c_x, c_z);
+        let (x, y, z) = self.position;
+        self.structure.paste_into(&mut chunk, x, y, z);
+        chunk
+    }
+
+    fn seed(&self) -> i64 {
+        self.generator.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        let height = self.generator.height(x, z);
+        let (s_x, s_z) = (x - self.position.0, z - self.position.2);
+        if !(0..self.structure.width() as i32).contains(&s_x)
+            || !(0..self.structure.length() as i32).contains(&s_z)
+        {
+            return height;
+        }
+
+        (0..self.structure.height())
+            .rev()
+            .find(|y| {
+                matches!(
+                    self.structure.get_block(s_x as u16, *y, s_z as u16),
+                    Some(block) if block != Block::Air
+                )
+            })
+            .map_or(height, |y| height.max(self.position.1 + y))
+    }
+}
+
+/// Keep the last generated chunks in memory, for generators which are expensive to run or
+/// which are queried multiple times for the same chunk (e.g. `height` by populators).
+pub struct Cached<G> {
+    generator: G,
+    capacity: usize,
+    cache: Mutex<ChunkCache>,
+}
+
+#[derive(Default)]
+struct ChunkCache {
+    chunks: HashMap<(i32, i32), Chunk>,
+    /// Positions of the cached chunks, from the oldest to the newest.
+    order: VecDeque<(i32, i32)>,
+}
+
+impl<G: ChunkGenerator> Cached<G> {
+    /// Keep up to `capacity` chunks, the oldest ones are dropped first.
+    pub fn new(generator: G, capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            generator,
+            capacity,
+            cache: Mutex::new(ChunkCache::default()),
+        }
+    }
+
+    fn with_chunk<T>(&self, c_x: i32, c_z: i32, f: impl FnOnce(&Chunk) -> T) -> T {
+        if let Some(chunk) = self.cache.lock().unwrap().chunks.get(&(c_x, c_z)) {
+            return f(chunk);
+        }
+
+        // Don't hold the lock while generating, other threads may use the cache meanwhile.
+        let chunk = self.generator.chunk(c_x, c_z);
+        let result = f(&chunk);
+
+        let ChunkCache { chunks, order } = &mut *self.cache.lock().unwrap();
+        if chunks.insert((c_x, c_z), chunk).is_none() {
+            order.push_back((c_x, c_z));
+        }
+        while order.len() > self.capacity {
+            let oldest = order.pop_front().unwrap();
+            chunks.remove(&oldest);
+        }
+        result
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for Cached<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        self.with_chunk(c_x, c_z, |chunk| chunk.clone(c_x, c_z))
+    }
+
+    fn seed(&self) -> i64 {
+        self.generator.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.with_chunk(x.div_euclid(16), z.div_euclid(16), |chunk| {
+            chunk.height(x.rem_euclid(16) as u8, z.rem_euclid(16) as u8)
+        })
+    }
+}
+
+/// Compose generators, each call wraps the generator built so far.
+/// ```
+/// use minecrust::game::map::generator::combinator::GeneratorBuilder;
+/// use minecrust::game::map::generator::decorator::Trees;
+/// use minecrust::game::map::generator::{ChunkGenerator, FlatChunkGenerator};
+/// use minecrust::packets::play::block::Block;
+///
+/// // A 64x64 flat arena with a few trees, surrounded by void.
+/// let generator = GeneratorBuilder::new(FlatChunkGenerator::new())
+///     .decorate(Trees::new(1))
+///     .bounded((-32, -32), (31, 31))
+///     .build();
+/// assert_eq!(generator.chunk(0, 0).get_block(0, 0, 0), Block::Bedrock);
+/// assert_eq!(generator.chunk(2, 0).get_block(0, 0, 0), Block::Air);
+/// ```
+pub struct GeneratorBuilder {
+    generator: BoxedGenerator,
+}
+
+impl GeneratorBuilder {
+    pub fn new(generator: impl ChunkGenerator + Sync + Send + 'static) -> Self {
+        Self {
+            generator: BoxedGenerator::new(generator),
+        }
+    }
+
+    /// See `Bounded`
+    pub fn bounded(self, min: (i32, i32), max: (i32, i32)) -> Self {
+        Self::new(Bounded::new(self.generator, min, max))
+    }
+
+    /// See `Layered`
+    pub fn layer(
+        self,
+        min_y: u16,
+        max_y: u16,
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) -> Self {
+        Self::new(Layered::new(self.generator).with_layer(min_y, max_y, generator))
+    }
+
+    /// See `RegionDispatch`
+    pub fn region(
+        self,
+        min: (i32, i32),
+        max: (i32, i32),
+        generator: impl ChunkGenerator + Sync + Send + 'static,
+    ) -> Self {
+        Self::new(RegionDispatch::new(self.generator).with_region(min, max, generator))
+    }
+
+    /// See `Overlay`
+    pub fn overlay(self, structure: Structure, position: (i32, u16, i32)) -> Self {
+        Self::new(Overlay::new(self.generator, structure, position))
+    }
+
+    /// See `DecoratedGenerator`
+    pub fn decorate(self, populator: impl Populator + Sync + Send + 'static) -> Self {
+        Self::new(DecoratedGenerator::new(self.generator).with_populator(populator))
+    }
+
+    /// See `Cached`
+    pub fn cached(self, capacity: usize) -> Self {
+        Self::new(Cached::new(self.generator, capacity))
+    }
+
+    pub fn build(self) -> BoxedGenerator {
+        self.generator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::generator::FlatChunkGenerator;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Filled(Block);
+
+    impl ChunkGenerator for Filled {
+        fn chunk(&self, x: i32, z: i32) -> Chunk {
+            let mut chunk = Chunk::new(x, z);
+            for z in 0..16 {
+                for x in 0..16 {
+                    chunk.set_block(x, 0, z, self.0);
+                }
+            }
+            chunk
+        }
+
+        fn height(&self, _x: i32, _z: i32) -> u16 {
+            0
+        }
+    }
+
+    struct Counting(Arc<AtomicUsize>);
+
+    impl ChunkGenerator for Counting {
+        fn chunk(&self, x: i32, z: i32) -> Chunk {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            FlatChunkGenerator::new().chunk(x, z)
+        }
+
+        fn height(&self, _x: i32, _z: i32) -> u16 {
+            3
+        }
+    }
+
+    #[test]
+    fn test_bounded() {
+        let generator = Bounded::new(FlatChunkGenerator::new(), (-4, 0), (3, 20));
+        let chunk = generator.chunk(-1, 0);
+        assert_eq!(chunk.get_block(11, 3, 0), Block::Air);
+        assert_eq!(chunk.get_block(12, 3, 0), Block::GrassBlock);
+        assert_eq!(chunk.get_block(12, 0, 0), Block::Bedrock);
+        assert_eq!(chunk.height(11, 0), 0);
+
+        let chunk = generator.chunk(0, 1);
+        assert_eq!(chunk.get_block(3, 0, 4), Block::Bedrock);
+        assert_eq!(chunk.get_block(3, 0, 5), Block::Air);
+        assert_eq!(chunk.get_block(4, 0, 4), Block::Air);
+
+        assert_eq!(generator.chunk(5, 5).get_block(0, 0, 0), Block::Air);
+        assert_eq!(generator.height(0, 0), 3);
+        assert_eq!(generator.height(4, 0), 0);
+    }
+
+    #[test]
+    fn test_layered() {
+        let generator = Layered::new(FlatChunkGenerator::new())
+            .with_layer(0, 0, Filled(Block::Stone))
+            .with_layer(2, 3, Filled(Block::Glass));
+        let chunk = generator.chunk(0, 0);
+        assert_eq!(chunk.get_block(5, 0, 5), Block::Stone);
+        assert_eq!(chunk.get_block(5, 1, 5), Block::Dirt);
+        assert_eq!(chunk.get_block(5, 2, 5), Block::Air);
+        assert_eq!(chunk.get_block(5, 3, 5), Block::Air);
+        assert_eq!(chunk.height(5, 5), 1);
+        assert_eq!(generator.height(5, 5), 1);
+    }
+
+    #[test]
+    fn test_region_dispatch() {
+        let generator = RegionDispatch::new(Filled(Block::Stone))
+            .with_region((0, 0), (1, 1), Filled(Block::Glass))
+            .with_region((1, 1), (2, 2), Filled(Block::Dirt));
+        assert_eq!(generator.chunk(-1, 0).get_block(0, 0, 0), Block::Stone);
+        assert_eq!(generator.chunk(0, 0).get_block(0, 0, 0), Block::Glass);
+        assert_eq!(generator.chunk(1, 1).get_block(0, 0, 0), Block::Glass);
+        assert_eq!(generator.chunk(2, 1).get_block(0, 0, 0), Block::Dirt);
+        assert_eq!(generator.chunk(3, 1).get_block(0, 0, 0), Block::Stone);
+    }
+
+    #[test]
+    fn test_overlay() {
+        let mut tower = Structure::new(1, 10, 1);
+        tower.fill(Block::Stone);
+        let generator = Overlay::new(FlatChunkGenerator::new(), tower, (-1, 4, 16));
+        assert_eq!(generator.chunk(-1, 1).get_block(15, 13, 0), Block::Stone);
+        assert_eq!(generator.chunk(-1, 1).get_block(15, 14, 0), Block::Air);
+        assert_eq!(generator.height(-1, 16), 13);
+        assert_eq!(generator.height(0, 16), 3);
+    }
+
+    #[test]
+    fn test_cached() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let generator = Cached::new(Counting(Arc::clone(&count)), 2);
+        for x in 0..16 {
+            assert_eq!(generator.height(x, 0), 3);
+        }
+        assert_eq!(generator.chunk(0, 0).get_block(0, 3, 0), Block::GrassBlock);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        generator.chunk(1, 0);
+        generator.chunk(2, 0);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+        generator.chunk(0, 0);
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+        assert_eq!(generator.chunk(5, 5).x, 5);
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut house = Structure::new(2, 2, 2);
+        house.fill(Block::OakPlanks);
+        let generator = GeneratorBuilder::new(FlatChunkGenerator::new())
+            .region((2, 2), (2, 2), Filled(Block::Sand))
+            .overlay(house, (0, 4, 0))
+            .bounded((0, 0), (47, 47))
+            .cached(16)
+            .build();
+
+        assert_eq!(generator.chunk(0, 0).get_block(1, 5, 1), Block::OakPlanks);
+        assert_eq!(generator.height(1, 1), 5);
+        assert_eq!(generator.chunk(2, 2).get_block(0, 0, 0), Block::Sand);
+        assert_eq!(generator.chunk(-1, 0).get_block(15, 0, 0), Block::Air);
+        assert_eq!(generator.chunk(3, 0).get_block(0, 0, 0), Block::Air);
+    }
+}
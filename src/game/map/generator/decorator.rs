@@ -0,0 +1,442 @@
+use super::noise::Random;
+use super::ChunkGenerator;
+use crate::game::map::structure::Structure;
+use crate::packets::play::{Block, Chunk};
+
+/// A decoration pass adding features (trees, ores, structures...) on top of generated terrain.
+///
+/// `populate` is called once per chunk with a random generator only depending on the seed and
+/// the chunk coordinates: features are picked around `Population::origin` and may cross the
+/// border of the chunk, by up to 16 blocks.
+/// ```
+/// use minecrust::game::map::generator::decorator::{Population, Populator};
+/// use minecrust::packets::play::block::Block;
+///
+/// /// A glowstone block floating above the ground of each chunk.
+/// struct Lamps;
+///
+/// impl Populator for Lamps {
+///     fn populate(&self, population: &mut Population) {
+///         let (x, z) = population.origin();
+///         let (x, z) = (x + population.random().next_bounded(16) as i32, z + 8);
+///         let y = population.height(x, z) + 3;
+///         population.set_block(x, y, z, Block::Glowstone);
+///     }
+/// }
+/// ```
+pub trait Populator {
+    fn populate(&self, population: &mut Population);
+}
+
+/// The features of one chunk being applied to another one (itself or one of its neighbours).
+///
+/// Blocks outside of the chunk being generated are ignored, the neighbour chunk will place them
+/// when it's generated. Decisions should only depend on the random generator and on the base
+/// generator (e.g. `height`), never on blocks of the chunk, so both chunks agree.
+pub struct Population<'a> {
+    generator: &'a dyn ChunkGenerator,
+    chunk: &'a mut Chunk,
+    origin: (i32, i32),
+    random: Random,
+}
+
+impl<'a> Population<'a> {
+    /// World coordinates of the lowest corner of the chunk features are generated for.
+    pub fn origin(&self) -> (i32, i32) {
+        self.origin
+    }
+
+    pub fn random(&mut self) -> &mut Random {
+        &mut self.random
+    }
+
+    /// The ground height of the column of the undecorated terrain.
+    pub fn height(&self, x: i32, z: i32) -> u16 {
+        self.generator.height(x, z)
+    }
+
+    fn local(&self, x: i32, y: u16, z: i32) -> Option<(u8, u16, u8)> {
+        let (l_x, l_z) = (x - self.chunk.x * 16, z - self.chunk.z * 16);
+        if (0..16).contains(&l_x) && (0..16).contains(&l_z) && y < 256 {
+            Some((l_x as u8, y, l_z as u8))
+        } else {
+            None
+        }
+    }
+
+    /// The block at the given world coordinates, `None` if it's outside of the chunk being
+    /// generated.
+    pub fn get_block(&self, x: i32, y: u16, z: i32) -> Option<Block> {
+        self.local(x, y, z)
+            .map(|(x, y, z)| self.chunk.get_block(x, y, z))
+    }
+
+    pub fn set_block(&mut self, x: i32, y: u16, z: i32, block: Block) {
+        if let Some((x, y, z)) = self.local(x, y, z) {
+            self.chunk.set_block(x, y, z, block);
+        }
+    }
+
+    /// Set a block only if the current one matches `predicate`, e.g. ores only replace stone.
+    pub fn replace(
+        &mut self,
+        x: i32,
+        y: u16,
+        z: i32,
+        block: Block,
+        predicate: impl Fn(Block) -> bool,
+    ) {
+        if let Some((x, y, z)) = self.local(x, y, z) {
+            if predicate(self.chunk.get_block(x, y, z)) {
+                self.chunk.set_block(x, y, z, block);
+            }
+        }
+    }
+
+    /// Place a structure with its lowest corner at the given world coordinates.
+    pub fn place(&mut self, structure: &Structure, x: i32, y: u16, z: i32) {
+        structure.paste_into(self.chunk, x, y, z);
+    }
+}
+
+/// Apply populators to the chunks of another generator.
+///
+/// Each chunk receives the features of itself and of its 8 neighbours, so features crossing
+/// chunk borders are complete whatever the order chunks are generated in.
+/// ```
+/// use minecrust::game::map::generator::decorator::{DecoratedGenerator, Ores, Trees};
+/// use minecrust::game::map::generator::{ChunkGenerator, NoiseChunkGenerator};
+///
+/// let generator = DecoratedGenerator::new(NoiseChunkGenerator::new(42))
+///     .with_populator(Ores::coal())
+///     .with_populator(Trees::new(4).with_min_height(NoiseChunkGenerator::WATER_LEVEL + 1));
+/// let chunk = generator.chunk(0, 0);
+/// ```
+pub struct DecoratedGenerator<G> {
+    generator: G,
+    populators: Vec<Box<dyn Populator + Sync + Send>>,
+}
+
+impl<G: ChunkGenerator> DecoratedGenerator<G> {
+    const SALT: u64 = 0x706F_7075_6C61_7465;
+
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            populators: Vec::new(),
+        }
+    }
+
+    /// Add a populator in place, populators are applied in the order they are added
+    pub fn add_populator(&mut self, populator: impl Populator + Sync + Send + 'static) {
+        self.populators.push(Box::new(populator));
+    }
+
+    /// Add a populator, populators are applied in the order they are added
+    pub fn with_populator(mut self, populator: impl Populator + Sync + Send + 'static) -> Self {
+        self.add_populator(populator);
+        self
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for DecoratedGenerator<G> {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let mut chunk = self.generator.chunk(c_x, c_z);
+        let seed = self.generator.seed();
+
+        // Always visit the neighbours in the same order, so overlapping features end up the
+        // same in every chunk.
+        for (index, populator) in self.populators.iter().enumerate() {
+            for s_z in c_z - 1..=c_z + 1 {
+                for s_x in c_x - 1..=c_x + 1 {
+                    let mut population = Population {
+                        generator: &self.generator,
+                        chunk: &mut chunk,
+                        origin: (s_x * 16, s_z * 16),
+                        random: Random::at(seed, s_x, index as i32, s_z, Self::SALT),
+                    };
+                    populator.populate(&mut population);
+                }
+            }
+        }
+
+        chunk
+    }
+
+    fn seed(&self) -> i64 {
+        self.generator.seed()
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.generator.height(x, z)
+    }
+}
+
+/// Ore veins replacing stone.
+#[derive(Debug, Clone)]
+pub struct Ores {
+    block: Block,
+    veins: u32,
+    size: u32,
+    min_y: u16,
+    max_y: u16,
+}
+
+impl Ores {
+    /// `veins` veins per chunk of up to `size` blocks, starting between `min_y` and `max_y`.
+    pub fn new(block: Block, veins: u32, size: u32, min_y: u16, max_y: u16) -> Self {
+        assert!(size <= 16 && min_y < max_y);
+        Self {
+            block,
+            veins,
+            size,
+            min_y,
+            max_y,
+        }
+    }
+
+    pub fn coal() -> Self {
+        Self::new(Block::CoalOre, 20, 16, 5, 128)
+    }
+
+    pub fn iron() -> Self {
+        Self::new(Block::IronOre, 20, 8, 5, 64)
+    }
+
+    pub fn gold() -> Self {
+        Self::new(Block::GoldOre, 2, 8, 5, 32)
+    }
+
+    pub fn redstone() -> Self {
+        Self::new(Block::RedstoneOre, 8, 7, 5, 16)
+    }
+
+    pub fn diamond() -> Self {
+        Self::new(Block::DiamondOre, 1, 7, 5, 16)
+    }
+}
+
+impl Populator for Ores {
+    fn populate(&self, population: &mut Population) {
+        let (o_x, o_z) = population.origin();
+        for _ in 0..self.veins {
+            let random = population.random();
+            let mut x = o_x + random.next_bounded(16) as i32;
+            let mut y = self.min_y + random.next_bounded((self.max_y - self.min_y) as u32) as u16;
+            let mut z = o_z + random.next_bounded(16) as i32;
+
+            // A random walk, one block at a time.
+            for _ in 0..self.size {
+                population.replace(x, y, z, self.block, |b| b == Block::Stone);
+                match population.random().next_bounded(6) {
+                    0 => x -= 1,
+                    1 => x += 1,
+                    2 => y = y.saturating_sub(1),
+                    3 => y += 1,
+                    4 => z -= 1,
+                    _ => z += 1,
+                }
+            }
+        }
+    }
+}
+
+/// Oak trees planted on the ground.
+#[derive(Debug, Clone)]
+pub struct Trees {
+    count: u32,
+    min_height: u16,
+}
+
+impl Trees {
+    /// Up to `count` trees per chunk.
+    pub fn new(count: u32) -> Self {
+        Self {
+            count,
+            min_height: 1,
+        }
+    }
+
+    /// Only plant trees on columns at least this high, e.g. to avoid planting them underwater.
+    pub fn with_min_height(mut self, min_height: u16) -> Self {
+        self.min_height = min_height;
+        self
+    }
+
+    fn plant(population: &mut Population, x: i32, y: u16, z: i32, trunk: u16) {
+        let top = y + trunk;
+        let is_air = |b| b == Block::Air;
+
+        for l_y in top - 3..=top {
+            let radius: i32 = if l_y + 2 > top { 1 } else { 2 };
+            for d_z in -radius..=radius {
+                for d_x in -radius..=radius {
+                    let corner = d_x.abs() == radius && d_z.abs() == radius;
+                    // Trim the corners of the top layer and a few of the others.
+                    if corner && (l_y == top || population.random().next_bounded(2) == 0) {
+                        continue;
+                    }
+                    population.replace(x + d_x, l_y, z + d_z, Block::OakLeaves, is_air);
+                }
+            }
+        }
+
+        for l_y in y..top {
+            population.set_block(x, l_y, z, Block::OakLog);
+        }
+        population.set_block(x, y - 1, z, Block::Dirt);
+    }
+}
+
+impl Populator for Trees {
+    fn populate(&self, population: &mut Population) {
+        let (o_x, o_z) = population.origin();
+        for _ in 0..self.count {
+            let random = population.random();
+            let x = o_x + random.next_bounded(16) as i32;
+            let z = o_z + random.next_bounded(16) as i32;
+            let trunk = 4 + random.next_bounded(3) as u16;
+
+            let ground = population.height(x, z);
+            if ground >= self.min_height && ground + trunk + 2 < 256 {
+                Self::plant(population, x, ground + 1, z, trunk);
+            }
+        }
+    }
+}
+
+/// A structure placed on the ground of some chunks.
+#[derive(Debug, Clone)]
+pub struct Structures {
+    structure: Structure,
+    rarity: u32,
+    min_height: u16,
+}
+
+impl Structures {
+    /// Place the structure in one chunk out of `rarity` on average. The structure can't be
+    /// bigger than 16 blocks on the x and z axes.
+    pub fn new(structure: Structure, rarity: u32) -> Self {
+        assert!(structure.width() <= 16 && structure.length() <= 16 && rarity > 0);
+        Self {
+            structure,
+            rarity,
+            min_height: 1,
+        }
+    }
+
+    /// Only place the structure on columns at least this high.
+    pub fn with_min_height(mut self, min_height: u16) -> Self {
+        self.min_height = min_height;
+        self
+    }
+}
+
+impl Populator for Structures {
+    fn populate(&self, population: &mut Population) {
+        let (o_x, o_z) = population.origin();
+        let random = population.random();
+        if random.next_bounded(self.rarity) != 0 {
+            return;
+        }
+        let x = o_x + random.next_bounded(16) as i32;
+        let z = o_z + random.next_bounded(16) as i32;
+
+        let ground = population.height(x, z);
+        if ground >= self.min_height && ground as u32 + 1 + self.structure.height() as u32 <= 256 {
+            population.place(&self.structure, x, ground + 1, z);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::generator::FlatChunkGenerator;
+
+    /// A line of glass starting on the last block of chunk (0, 0) and going 3 blocks east.
+    struct Line;
+
+    impl Populator for Line {
+        fn populate(&self, population: &mut Population) {
+            if population.origin() == (0, 0) {
+                for x in 15..18 {
+                    population.set_block(x, 10, 0, Block::Glass);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cross_chunks() {
+        let generator = DecoratedGenerator::new(FlatChunkGenerator::new()).with_populator(Line);
+        let chunk = generator.chunk(0, 0);
+        assert_eq!(chunk.get_block(15, 10, 0), Block::Glass);
+        assert_eq!(chunk.get_block(14, 10, 0), Block::Air);
+
+        let chunk = generator.chunk(1, 0);
+        assert_eq!(chunk.get_block(0, 10, 0), Block::Glass);
+        assert_eq!(chunk.get_block(1, 10, 0), Block::Glass);
+        assert_eq!(chunk.get_block(2, 10, 0), Block::Air);
+
+        let chunk = generator.chunk(-1, 0);
+        assert_eq!(chunk.get_block(15, 10, 0), Block::Air);
+    }
+
+    #[test]
+    fn test_trees() {
+        let generator =
+            DecoratedGenerator::new(FlatChunkGenerator::new()).with_populator(Trees::new(3));
+        let (mut logs, mut leaves) = (0, 0);
+        for c_x in -2..2 {
+            let chunk = generator.chunk(c_x, 5);
+            let again = generator.chunk(c_x, 5);
+            for z in 0..16 {
+                for x in 0..16 {
+                    for y in 0..16 {
+                        assert_eq!(chunk.get_block(x, y, z), again.get_block(x, y, z));
+                        match chunk.get_block(x, y, z) {
+                            Block::OakLog => {
+                                assert!(y >= 4);
+                                assert_eq!(chunk.get_block(x, 3, z), Block::Dirt);
+                                logs += 1;
+                            }
+                            Block::OakLeaves => leaves += 1,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        assert!(logs > 0 && leaves > logs);
+    }
+
+    #[test]
+    fn test_ores() {
+        let mut structure = Structure::new(16, 16, 16);
+        structure.fill(Block::Stone);
+        let generator = DecoratedGenerator::new(FlatChunkGenerator::new())
+            .with_populator(Structures::new(structure, 1))
+            .with_populator(Ores::new(Block::DiamondOre, 4, 8, 5, 15));
+
+        let chunk = generator.chunk(3, 3);
+        let mut ores = 0;
+        for y in 0..64 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    if chunk.get_block(x, y, z) == Block::DiamondOre {
+                        ores += 1;
+                    }
+                }
+            }
+        }
+        assert!(ores > 0);
+        // Ores don't replace the bedrock or the dirt layers.
+        for z in 0..16 {
+            for x in 0..16 {
+                assert_eq!(chunk.get_block(x, 0, z), Block::Bedrock);
+                assert_eq!(chunk.get_block(x, 3, z), Block::GrassBlock);
+            }
+        }
+    }
+}
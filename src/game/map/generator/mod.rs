@@ -0,0 +1,215 @@
+pub mod combinator;
+pub mod decorator;
+pub mod noise;
+
+pub use noise::NoiseChunkGenerator;
+
+use crate::packets::play::{Biome, Block, Chunk};
+
+pub trait ChunkGenerator {
+    fn chunk(&self, x: i32, z: i32) -> Chunk;
+
+    /// The seed the world is generated from, sent (hashed) to the clients.
+    fn seed(&self) -> i64 {
+        0
+    }
+
+    /// The y coordinate of the ground of a column, used to place features on top of it.
+    ///
+    /// It is called for every feature, the default implementation generates the whole chunk
+    /// of the column so generators should override it, as the built-in ones do.
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.chunk(x.div_euclid(16), z.div_euclid(16))
+            .height(x.rem_euclid(16) as u8, z.rem_euclid(16) as u8)
+    }
+}
+
+pub struct SameChunkGenerator(Chunk);
+
+impl SameChunkGenerator {
+    pub fn new(original: Chunk) -> Self {
+        Self(original)
+    }
+}
+
+impl ChunkGenerator for SameChunkGenerator {
+    fn chunk(&self, x: i32, z: i32) -> Chunk {
+        self.0.clone(x, z)
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.0
+            .height(x.rem_euclid(16) as u8, z.rem_euclid(16) as u8)
+    }
+}
+
+pub struct FlatChunkGenerator(SameChunkGenerator);
+
+impl FlatChunkGenerator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A flat world with the same biome everywhere.
+    pub fn with_biome(biome: Biome) -> Self {
+        let mut original = Self::layers();
+        original.fill_biome(biome);
+        Self(SameChunkGenerator::new(original))
+    }
+
+    fn layers() -> Chunk {
+        let mut original = Chunk::new(0, 0);
+        for z in 0..16 {
+            for x in 0..16 {
+                original.set_block(x, 0, z, Block::Bedrock);
+                original.set_block(x, 1, z, Block::Dirt);
+                original.set_block(x, 2, z, Block::Dirt);
+                original.set_block(x, 3, z, Block::GrassBlock);
+            }
+        }
+        original
+    }
+}
+
+impl Default for FlatChunkGenerator {
+    fn default() -> Self {
+        Self(SameChunkGenerator::new(Self::layers()))
+    }
+}
+
+impl ChunkGenerator for FlatChunkGenerator {
+    fn chunk(&self, x: i32, z: i32) -> Chunk {
+        self.0.chunk(x, z)
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        self.0.height(x, z)
+    }
+}
+
+/// When implementing this trait you `block` function will be called with all the needed blocks
+/// ranging from y=0 to y=255.
+/// You can return `None` once there is nothing higher than the current y
+/// Here is an example of a FlatWorld:
+/// ```
+/// use minecrust::game::map::generator::SingleBlockGenerator;
+/// use minecrust::packets::play::block::Block;
+/// pub struct Flat {}
+/// impl SingleBlockGenerator for Flat {
+///     fn block(&self, x: i32, y: u16, z: i32) -> Option<Block> {
+///         match y {
+///             0 => Some(Block::Bedrock),
+///             1 => Some(Block::Dirt),
+///             2 => Some(Block::Dirt),
+///             3 => Some(Block::GrassBlock),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+/// In this case, for each pair (x, z), the block function will be called with y = 0, 1, 2 and 3
+/// before moving to the next (x, z).
+///
+/// The `biome` function is called once per cell of 4x4x4 blocks, with the coordinates of the
+/// lowest corner of the cell. It defaults to `Biome::Plains` everywhere.
+pub trait SingleBlockGenerator {
+    fn block(&self, x: i32, y: u16, z: i32) -> Option<Block>;
+
+    fn biome(&self, _x: i32, _y: u16, _z: i32) -> Biome {
+        Biome::Plains
+    }
+}
+
+impl<G> ChunkGenerator for G
+where
+    G: SingleBlockGenerator,
+{
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let mut chunk = Chunk::new(c_x, c_z);
+        for z in 0..16_u8 {
+            for x in 0..16_u8 {
+                for y in 0..255 {
+                    if let Some(block) = self.block(c_x * 16 + x as i32, y, c_z * 16 + z as i32) {
+                        chunk.set_block(x, y, z, block);
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+
+        for z in (0..16_u8).step_by(4) {
+            for x in (0..16_u8).step_by(4) {
+                for y in (0..256).step_by(4) {
+                    let biome = self.biome(c_x * 16 + x as i32, y, c_z * 16 + z as i32);
+                    chunk.set_biome(x, y, z, biome);
+                }
+            }
+        }
+
+        chunk
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        (1..255)
+            .take_while(|y| self.block(x, *y, z).is_some())
+            .last()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Stripes;
+
+    impl SingleBlockGenerator for Stripes {
+        fn block(&self, _x: i32, y: u16, _z: i32) -> Option<Block> {
+            match y {
+                0 => Some(Block::Bedrock),
+                _ => None,
+            }
+        }
+
+        fn biome(&self, x: i32, _y: u16, _z: i32) -> Biome {
+            if x.rem_euclid(8) < 4 {
+                Biome::Desert
+            } else {
+                Biome::Ocean
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_block_biomes() {
+        let chunk = Stripes.chunk(-1, 2);
+        assert_eq!(chunk.get_block(3, 0, 3), Block::Bedrock);
+        assert_eq!(chunk.biome(0, 0, 0), Biome::Desert);
+        assert_eq!(chunk.biome(3, 100, 15), Biome::Desert);
+        assert_eq!(chunk.biome(4, 100, 15), Biome::Ocean);
+        assert_eq!(chunk.biome(15, 255, 0), Biome::Ocean);
+    }
+
+    /// Only implements `chunk`, with a pillar at the center of the chunk (0, 0).
+    struct Pillar;
+
+    impl ChunkGenerator for Pillar {
+        fn chunk(&self, x: i32, z: i32) -> Chunk {
+            let mut chunk = Chunk::new(x, z);
+            if (x, z) == (0, 0) {
+                for y in 0..10 {
+                    chunk.set_block(8, y, 8, Block::Stone);
+                }
+            }
+            chunk
+        }
+    }
+
+    #[test]
+    fn test_default_height() {
+        assert_eq!(Pillar.height(8, 8), 9);
+        assert_eq!(Pillar.height(7, 8), 0);
+        assert_eq!(Pillar.height(24, 8), 0);
+    }
+}
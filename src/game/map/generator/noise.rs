@@ -0,0 +1,464 @@
+use super::ChunkGenerator;
+use crate::packets::play::{Biome, Block, Chunk};
+use std::cmp::max;
+
+/// A small pseudo random number generator (SplitMix64).
+///
+/// Generated worlds only depend on their seed, not on the version of an external crate.
+#[derive(Debug, Clone)]
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// A generator only depending on the world seed, a position and a salt, so each feature of
+    /// each chunk gets its own independent sequence.
+    pub fn at(seed: i64, x: i32, y: i32, z: i32, salt: u64) -> Self {
+        let mut random = Self::new(seed as u64 ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        for coordinate in [x, y, z].iter() {
+            random.0 ^= random.next_u64().wrapping_add(*coordinate as i64 as u64);
+        }
+        random
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number between 0 (inclusive) and 1 (exclusive).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// A number between 0 (inclusive) and `bound` (exclusive).
+    pub fn next_bounded(&mut self, bound: u32) -> u32 {
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as u32
+    }
+}
+
+/// Improved Perlin noise, returning values between -1 and 1.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    permutation: Vec<u8>,
+    offset: (f64, f64, f64),
+}
+
+impl Perlin {
+    pub fn new(random: &mut Random) -> Self {
+        let mut permutation = (0..=255_u8).collect::<Vec<_>>();
+        for i in (1..256).rev() {
+            let j = random.next_bounded(i as u32 + 1) as usize;
+            permutation.swap(i, j);
+        }
+        permutation.extend_from_within(..256);
+
+        Self {
+            permutation,
+            offset: (
+                random.next_f64() * 256.,
+                random.next_f64() * 256.,
+                random.next_f64() * 256.,
+            ),
+        }
+    }
+
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset.0, y + self.offset.1, z + self.offset.2);
+        let (x_floor, y_floor, z_floor) = (x.floor(), y.floor(), z.floor());
+        let (x_cell, y_cell, z_cell) = (
+            x_floor as i64 as usize & 255,
+            y_floor as i64 as usize & 255,
+            z_floor as i64 as usize & 255,
+        );
+        let (x, y, z) = (x - x_floor, y - y_floor, z - z_floor);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[x_cell] as usize + y_cell;
+        let aa = p[a] as usize + z_cell;
+        let ab = p[a + 1] as usize + z_cell;
+        let b = p[x_cell + 1] as usize + y_cell;
+        let ba = p[b] as usize + z_cell;
+        let bb = p[b + 1] as usize + z_cell;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1., y, z)),
+                lerp(u, grad(p[ab], x, y - 1., z), grad(p[bb], x - 1., y - 1., z)),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.),
+                    grad(p[ba + 1], x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1., z - 1.),
+                    grad(p[bb + 1], x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Several layers of Perlin noise, each one with twice the frequency and half the amplitude of
+/// the previous one. Values are normalized between -1 and 1.
+#[derive(Debug, Clone)]
+pub struct Octaves(Vec<Perlin>);
+
+impl Octaves {
+    pub fn new(random: &mut Random, count: usize) -> Self {
+        Self((0..count).map(|_| Perlin::new(random)).collect())
+    }
+
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (mut total, mut frequency, mut amplitude, mut max_amplitude) = (0., 1., 1., 0.);
+        for octave in &self.0 {
+            total += octave.noise(x * frequency, y * frequency, z * frequency) * amplitude;
+            max_amplitude += amplitude;
+            frequency *= 2.;
+            amplitude /= 2.;
+        }
+        total / max_amplitude
+    }
+
+    pub fn noise_2d(&self, x: f64, z: f64) -> f64 {
+        self.noise(x, 0., z)
+    }
+}
+
+/// A seeded terrain generator: hills, mountains and oceans with beaches, a few biomes and caves.
+///
+/// The world only depends on the seed, generating the same chunk twice gives the same blocks.
+/// ```
+/// use minecrust::game::map::generator::{ChunkGenerator, NoiseChunkGenerator};
+///
+/// let generator = NoiseChunkGenerator::new(42);
+/// let chunk = generator.chunk(0, 0);
+/// let height = generator.height(3, 5);
+/// assert_eq!(chunk.get_block(3, 0, 5), generator.chunk(0, 0).get_block(3, 0, 5));
+/// assert!(height > 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NoiseChunkGenerator {
+    seed: i64,
+    continents: Octaves,
+    hills: Octaves,
+    temperature: Octaves,
+    humidity: Octaves,
+    caves: (Octaves, Octaves),
+}
+
+impl NoiseChunkGenerator {
+    pub const WATER_LEVEL: u16 = 62;
+    const BEACH_HEIGHT: u16 = Self::WATER_LEVEL + 2;
+    const DEEP_OCEAN_HEIGHT: u16 = 40;
+    const MOUNTAINS_HEIGHT: u16 = 96;
+    const LAVA_LEVEL: u16 = 10;
+    const BEDROCK_SALT: u64 = 1;
+
+    pub fn new(seed: i64) -> Self {
+        let mut random = Random::new(seed as u64);
+        Self {
+            seed,
+            continents: Octaves::new(&mut random, 4),
+            hills: Octaves::new(&mut random, 4),
+            temperature: Octaves::new(&mut random, 2),
+            humidity: Octaves::new(&mut random, 2),
+            caves: (Octaves::new(&mut random, 2), Octaves::new(&mut random, 2)),
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    /// The y coordinate of the highest solid block of the column, before carving caves.
+    pub fn height(&self, x: i32, z: i32) -> u16 {
+        let (x, z) = (x as f64, z as f64);
+        let continent = self.continents.noise_2d(x / 512., z / 512.);
+        let hills = self.hills.noise_2d(x / 64., z / 64.);
+
+        let mut height = 64. + continent * 48. + hills * 8.;
+        if continent > 0.2 {
+            // Mountains grow on the inner part of continents.
+            height += (continent - 0.2) * 160. * (hills.abs() + 0.5);
+        }
+        height.clamp(1., 250.) as u16
+    }
+
+    /// The biome of a column, picked from its height and its climate.
+    pub fn biome(&self, x: i32, z: i32) -> Biome {
+        self.biome_at_height(x, z, self.height(x, z))
+    }
+
+    fn biome_at_height(&self, x: i32, z: i32, height: u16) -> Biome {
+        let (x, z) = (x as f64, z as f64);
+        let temperature = self.temperature.noise_2d(x / 1024., z / 1024.);
+        let humidity = self.humidity.noise_2d(x / 1024., z / 1024.);
+        let cold = temperature < -0.25;
+
+        match height {
+            h if h < Self::DEEP_OCEAN_HEIGHT => Biome::DeepOcean,
+            h if h < Self::WATER_LEVEL && cold => Biome::FrozenOcean,
+            h if h < Self::WATER_LEVEL => Biome::Ocean,
+            h if h <= Self::BEACH_HEIGHT && cold => Biome::SnowyBeach,
+            h if h <= Self::BEACH_HEIGHT => Biome::Beach,
+            h if h >= Self::MOUNTAINS_HEIGHT && cold => Biome::SnowyMountains,
+            h if h >= Self::MOUNTAINS_HEIGHT => Biome::Mountains,
+            _ if cold => Biome::SnowyTundra,
+            _ if temperature > 0.25 && humidity < 0. => Biome::Desert,
+            _ if humidity > 0.15 => Biome::Forest,
+            _ => Biome::Plains,
+        }
+    }
+
+    fn is_cave(&self, x: i32, y: u16, z: i32) -> bool {
+        let (x, y, z) = (x as f64 / 48., y as f64 / 24., z as f64 / 48.);
+        self.caves.0.noise(x, y, z).abs() < 0.04 && self.caves.1.noise(x, y, z).abs() < 0.04
+    }
+
+    /// The block of a column at `depth` blocks below its surface.
+    fn surface_block(biome: Biome, depth: u16, height: u16) -> Block {
+        use Biome::*;
+        match (biome, depth) {
+            (Desert, 0..=2) | (Beach, 0..=3) | (SnowyBeach, 0..=3) => Block::Sand,
+            (Desert, 3..=4) | (Beach, 4..=5) | (SnowyBeach, 4..=5) => Block::Sandstone,
+            (Ocean, 0..=2) | (FrozenOcean, 0..=2) => Block::Sand,
+            (DeepOcean, 0..=2) => Block::Gravel,
+            (Mountains, 0) | (SnowyMountains, 0) if height > 120 => Block::Stone,
+            (SnowyTundra, 0) | (SnowyMountains, 0) => {
+                Block::GrassBlock.with_property("snowy", "true").unwrap()
+            }
+            (_, 0) => Block::GrassBlock,
+            (_, depth) if depth <= 3 => Block::Dirt,
+            _ => Block::Stone,
+        }
+    }
+}
+
+impl ChunkGenerator for NoiseChunkGenerator {
+    fn chunk(&self, c_x: i32, c_z: i32) -> Chunk {
+        let mut chunk = Chunk::new(c_x, c_z);
+
+        for z in 0..16_u8 {
+            for x in 0..16_u8 {
+                let (w_x, w_z) = (c_x * 16 + x as i32, c_z * 16 + z as i32);
+                let height = self.height(w_x, w_z);
+                let biome = self.biome_at_height(w_x, w_z, height);
+                let mut bedrock = Random::at(self.seed, w_x, 0, w_z, Self::BEDROCK_SALT);
+
+                for y in 0..=max(height, Self::WATER_LEVEL) {
+                    let block = if y == 0 || (y < 5 && bedrock.next_bounded(5) >= y as u32) {
+                        Block::Bedrock
+                    } else if y <= height {
+                        // Don't open caves right below the sea floor.
+                        let under_water = height < Self::WATER_LEVEL && y + 8 > height;
+                        if !under_water && y + 1 < height && self.is_cave(w_x, y, w_z) {
+                            if y <= Self::LAVA_LEVEL {
+                                Block::Lava
+                            } else {
+                                continue;
+                            }
+                        } else {
+                            Self::surface_block(biome, height - y, height)
+                        }
+                    } else if y == Self::WATER_LEVEL
+                        && (biome == Biome::FrozenOcean || biome == Biome::SnowyBeach)
+                    {
+                        Block::Ice
+                    } else {
+                        Block::Water
+                    };
+                    chunk.set_block(x, y, z, block);
+                }
+
+                if height >= Self::WATER_LEVEL
+                    && (biome == Biome::SnowyTundra
+                        || (biome == Biome::SnowyMountains && height <= 120))
+                {
+                    chunk.set_block(x, height + 1, z, Block::Snow);
+                }
+            }
+        }
+
+        for z in (0..16_u8).step_by(4) {
+            for x in (0..16_u8).step_by(4) {
+                let biome = self.biome(c_x * 16 + x as i32 + 2, c_z * 16 + z as i32 + 2);
+                chunk.set_column_biome(x, z, biome);
+            }
+        }
+
+        chunk
+    }
+
+    fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        NoiseChunkGenerator::height(self, x, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random() {
+        let mut random = Random::new(42);
+        let first = (0..10).map(|_| random.next_u64()).collect::<Vec<_>>();
+        let mut random = Random::new(42);
+        assert_eq!(
+            (0..10).map(|_| random.next_u64()).collect::<Vec<_>>(),
+            first
+        );
+
+        let mut random = Random::new(42);
+        for _ in 0..1000 {
+            assert!(random.next_bounded(7) < 7);
+            let f = random.next_f64();
+            assert!((0. ..1.).contains(&f));
+        }
+
+        assert_ne!(
+            Random::at(42, 0, 0, 0, 0).next_u64(),
+            Random::at(42, 1, 0, 0, 0).next_u64()
+        );
+        assert_ne!(
+            Random::at(42, 0, 0, 0, 0).next_u64(),
+            Random::at(42, 0, 0, 0, 1).next_u64()
+        );
+    }
+
+    #[test]
+    fn test_perlin() {
+        let perlin = Perlin::new(&mut Random::new(0));
+        for i in 0..1000 {
+            let value = perlin.noise(i as f64 * 0.37, i as f64 * 0.11, i as f64 * -0.23);
+            assert!((-1. ..=1.).contains(&value));
+        }
+        assert_eq!(perlin.noise(1.5, 2.5, 3.5), perlin.noise(1.5, 2.5, 3.5));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let first = NoiseChunkGenerator::new(1234);
+        let second = NoiseChunkGenerator::new(1234);
+        let other = NoiseChunkGenerator::new(4321);
+
+        let heights = |generator: &NoiseChunkGenerator| {
+            (-64..64)
+                .map(|i| generator.height(i * 13, i * -7))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&first), heights(&second));
+        assert_ne!(heights(&first), heights(&other));
+
+        let (a, b) = (first.chunk(-3, 7), second.chunk(-3, 7));
+        for y in 0..256 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(a.get_block(x, y, z), b.get_block(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let generator = NoiseChunkGenerator::new(42);
+        let heights = (0..8)
+            .map(|i| generator.height(i * 100, i * -100))
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![46, 62, 71, 65, 59, 57, 64, 92]);
+
+        let chunk = generator.chunk(0, 0);
+        assert_eq!(generator.biome(0, 0), Biome::FrozenOcean);
+        assert_eq!(chunk.biome(0, 0, 0), Biome::FrozenOcean);
+        assert_eq!(chunk.get_block(0, 46, 0), Block::Sand);
+        assert_eq!(chunk.get_block(0, 47, 0), Block::Water);
+        assert_eq!(chunk.get_block(0, 62, 0), Block::Ice);
+        assert_eq!(chunk.get_block(0, 63, 0), Block::Air);
+        assert_eq!(generator.biome(700, -700), Biome::Forest);
+    }
+
+    #[test]
+    fn test_terrain() {
+        let generator = NoiseChunkGenerator::new(42);
+        for (c_x, c_z) in &[(0, 0), (-5, 3), (20, -40)] {
+            let chunk = generator.chunk(*c_x, *c_z);
+            for z in 0..16 {
+                for x in 0..16 {
+                    let (w_x, w_z) = (c_x * 16 + x as i32, c_z * 16 + z as i32);
+                    let height = generator.height(w_x, w_z);
+                    assert_eq!(chunk.get_block(x, 0, z), Block::Bedrock);
+                    assert_eq!(chunk.get_block(x, 255, z), Block::Air);
+
+                    let top = chunk.get_block(x, height, z);
+                    assert_ne!(top, Block::Air);
+                    if height < NoiseChunkGenerator::WATER_LEVEL {
+                        let surface = chunk.get_block(x, NoiseChunkGenerator::WATER_LEVEL, z);
+                        assert!(surface == Block::Water || surface == Block::Ice);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_biomes() {
+        let generator = NoiseChunkGenerator::new(42);
+        let mut ocean = false;
+        let mut land = false;
+        for i in -200..200 {
+            let (x, z) = (i * 37, i * -53);
+            let height = generator.height(x, z);
+            match generator.biome(x, z) {
+                Biome::Ocean | Biome::DeepOcean | Biome::FrozenOcean => {
+                    ocean = true;
+                    assert!(height < NoiseChunkGenerator::WATER_LEVEL);
+                }
+                Biome::Beach | Biome::SnowyBeach => {
+                    assert!(height >= NoiseChunkGenerator::WATER_LEVEL);
+                    assert!(height <= NoiseChunkGenerator::BEACH_HEIGHT);
+                }
+                _ => {
+                    land = true;
+                    assert!(height > NoiseChunkGenerator::BEACH_HEIGHT);
+                }
+            }
+        }
+        assert!(ocean && land);
+    }
+}
@@ -1,41 +1,686 @@
 pub mod generator;
+pub mod schematic;
+pub mod structure;
+mod worker;
 
 use crate::game::map::generator::ChunkGenerator;
-use crate::packets::play::{Block, Chunk};
+use crate::game::map::structure::Structure;
+use crate::game::map::worker::Workers;
+use crate::game::placement;
+use crate::packets::play::{light, Block, BlockKind, Chunk};
+use crate::types::BlockPosition;
+use anyhow::{anyhow, Result};
+use futures::future::{self, BoxFuture, FutureExt, Shared};
 use piper::{Lock, LockGuard};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+/// The distance in blocks from the requested spawn up to which a safe spawn is looked for.
+const SPAWN_SEARCH_RADIUS: i32 = 32;
+
+/// A chunk being generated or already loaded, every request for the same chunk shares it.
+/// `None` if its generator panicked.
+type SharedChunk = Shared<BoxFuture<'static, Option<Lock<Chunk>>>>;
 
 pub struct Map {
-    chunks: Lock<HashMap<(i32, i32), Lock<Chunk>>>,
-    generator: Box<dyn ChunkGenerator + Sync + std::marker::Send + 'static>,
+    chunks: Lock<HashMap<(i32, i32), SharedChunk>>,
+    /// The loaded chunks whose light was spread into their neighbours, also locked while
+    /// relighting chunks.
+    lit: Lock<HashSet<(i32, i32)>>,
+    /// The chunks relit because of their neighbours, see `take_relit`.
+    relit: Lock<BTreeSet<(i32, i32)>>,
+    generator: Arc<dyn ChunkGenerator + Sync + std::marker::Send + 'static>,
+    workers: Workers,
 }
 
 impl Map {
     pub async fn new(generator: impl ChunkGenerator + Sync + std::marker::Send + 'static) -> Self {
+        let generator: Arc<dyn ChunkGenerator + Sync + std::marker::Send> = Arc::new(generator);
         Self {
             chunks: Lock::new(HashMap::new()),
-            generator: Box::new(generator),
+            lit: Lock::new(HashSet::new()),
+            relit: Lock::new(BTreeSet::new()),
+            workers: Workers::new(Arc::clone(&generator)),
+            generator,
         }
     }
 
-    pub async fn chunk(&self, x: i32, z: i32) -> LockGuard<Chunk> {
+    pub fn seed(&self) -> i64 {
+        self.generator.seed()
+    }
+
+    /// Get the chunk or queue its generation. The map lock is only held while looking the chunk
+    /// up, never during generation.
+    async fn request(&self, x: i32, z: i32) -> SharedChunk {
         let mut chunks = self.chunks.lock().await;
         chunks
             .entry((x, z))
-            .or_insert_with(|| Lock::new(self.generator.chunk(x, z)))
+            .or_insert_with(|| {
+                self.workers
+                    .generate(x, z)
+                    .map(|chunk| chunk.ok().map(Lock::new))
+                    .boxed()
+                    .shared()
+            })
+            .clone()
+    }
+
+    /// Load a chunk, generating it if needed. Fails if its generator panicked.
+    pub async fn load(&self, x: i32, z: i32) -> Result<Lock<Chunk>> {
+        match self.request(x, z).await.await {
+            Some(chunk) => {
+                self.spread_light(x, z).await;
+                Ok(chunk)
+            }
+            None => Err(self.failed(x, z).await),
+        }
+    }
+
+    /// Forget a chunk whose generation panicked so the next request generates it again.
+    async fn failed(&self, x: i32, z: i32) -> anyhow::Error {
+        let mut chunks = self.chunks.lock().await;
+        if let Some(Some(None)) = chunks.get(&(x, z)).map(Shared::peek) {
+            chunks.remove(&(x, z));
+        }
+        anyhow!("failed to generate the chunk ({}, {})", x, z)
+    }
+
+    /// Load multiple chunks, generating the missing ones in parallel. Returns one result per
+    /// position, in the same order.
+    pub async fn load_all(
+        &self,
+        positions: impl IntoIterator<Item = (i32, i32)>,
+    ) -> Vec<Result<Lock<Chunk>>> {
+        let mut requests = Vec::new();
+        for (x, z) in positions {
+            let request = self.request(x, z).await;
+            requests.push(request.map(move |chunk| (x, z, chunk)));
+        }
+        let mut chunks = Vec::new();
+        for (x, z, chunk) in future::join_all(requests).await {
+            chunks.push(match chunk {
+                Some(chunk) => {
+                    self.spread_light(x, z).await;
+                    Ok(chunk)
+                }
+                None => Err(self.failed(x, z).await),
+            });
+        }
+        chunks
+    }
+
+    /// Spread the light of a newly loaded chunk into its loaded neighbours and theirs into it,
+    /// only once per chunk.
+    async fn spread_light(&self, x: i32, z: i32) {
+        let mut lit = self.lit.lock().await;
+        if !lit.insert((x, z)) {
+            return;
+        }
+        let positions = around(x, z).filter(|position| lit.contains(position));
+        let mut chunks = self.lock_loaded(positions.collect()).await;
+        let changed = light::spread(&mut chunks, &[(x, z)]);
+        drop(chunks);
+        self.relit
             .lock()
             .await
+            .extend(changed.into_iter().filter(|position| *position != (x, z)));
+    }
+
+    /// Recompute the light of edited chunks and of their neighbours, which may have been lit
+    /// through them, then spread it across the chunk borders.
+    async fn relight(&self, edited: &[(i32, i32)]) {
+        let lit = self.lit.lock().await;
+        let relit = edited
+            .iter()
+            .flat_map(|&(x, z)| around(x, z))
+            .filter(|position| lit.contains(position))
+            .collect::<BTreeSet<_>>();
+        // The chunks around them keep their light and only light them up.
+        let positions = relit
+            .iter()
+            .flat_map(|&(x, z)| around(x, z))
+            .filter(|position| lit.contains(position))
+            .collect();
+        let mut chunks = self.lock_loaded(positions).await;
+
+        let mut previous = Vec::with_capacity(relit.len());
+        for position in &relit {
+            if let Some(chunk) = chunks.get_mut(position) {
+                previous.push((*position, chunk.light().clone()));
+                chunk.update_light();
+            }
+        }
+        let mut changed = light::spread(&mut chunks, &relit.into_iter().collect::<Vec<_>>());
+        for (position, light) in previous {
+            if chunks[&position].light() == &light {
+                changed.remove(&position);
+            } else {
+                changed.insert(position);
+            }
+        }
+        drop(chunks);
+        drop(lit);
+        self.relit.lock().await.extend(
+            changed
+                .into_iter()
+                .filter(|position| !edited.contains(position)),
+        );
+    }
+
+    /// Lock the loaded chunks among `positions`, always in the same order.
+    async fn lock_loaded(
+        &self,
+        positions: BTreeSet<(i32, i32)>,
+    ) -> BTreeMap<(i32, i32), LockGuard<Chunk>> {
+        let loaded = {
+            let chunks = self.chunks.lock().await;
+            positions
+                .into_iter()
+                .filter_map(|position| Some((position, chunks.get(&position)?.peek()?.clone()?)))
+                .collect::<Vec<_>>()
+        };
+        let mut guards = BTreeMap::new();
+        for (position, chunk) in loaded {
+            guards.insert(position, chunk.lock().await);
+        }
+        guards
+    }
+
+    /// The chunks whose light changed since the last call because of their neighbours, e.g. a
+    /// chunk loaded next to them or edited blocks. Their new light should be sent again.
+    pub async fn take_relit(&self) -> BTreeSet<(i32, i32)> {
+        std::mem::take(&mut *self.relit.lock().await)
     }
 
-    pub async fn set_block(&self, mut x: i32, y: u16, mut z: i32, block: Block) {
-        if x < 0 {
-            x -= 16
+    /// Generate all the chunks up to `radius` chunks away from the `center` chunk, e.g. the
+    /// spawn chunk. The chunks which fail to generate are generated again when requested.
+    pub async fn pregenerate(&self, center: (i32, i32), radius: i32) {
+        let positions = (-radius..=radius)
+            .flat_map(|z| (-radius..=radius).map(move |x| (center.0 + x, center.1 + z)));
+        self.load_all(positions).await;
+    }
+
+    /// The closest position to the column where a player can spawn: right above the highest
+    /// block of its column, if it is solid and harmless. Falls back to the top of the column, or
+    /// of the world if its chunk fails to generate.
+    pub async fn safe_spawn(&self, x: i32, z: i32) -> BlockPosition {
+        for radius in 0..=SPAWN_SEARCH_RADIUS {
+            for (dx, dz) in ring(radius) {
+                if let Some(y) = self.safe_height(x + dx, z + dz).await {
+                    return BlockPosition::new(x + dx, y, z + dz);
+                }
+            }
         }
-        if z < 0 {
-            z -= 16
+        let height = match self.chunk(x.div_euclid(16), z.div_euclid(16)).await {
+            Ok(chunk) => chunk.height(x.rem_euclid(16) as u8, z.rem_euclid(16) as u8) + 1,
+            Err(_) => 255,
+        };
+        BlockPosition::new(x, height.min(255), z)
+    }
+
+    /// The height a player can spawn at in the column, using the heightmap of its chunk.
+    async fn safe_height(&self, x: i32, z: i32) -> Option<u16> {
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16)).await.ok()?;
+        let (x, z) = (x.rem_euclid(16) as u8, z.rem_euclid(16) as u8);
+        let height = chunk.height(x, z);
+        // The player needs two blocks of air above the ground.
+        if height >= 254 || !is_safe_ground(chunk.get_block(x, height, z)) {
+            return None;
         }
+        Some(height + 1)
+    }
+
+    pub async fn chunk(&self, x: i32, z: i32) -> Result<LockGuard<Chunk>> {
+        Ok(self.load(x, z).await?.lock().await)
+    }
+
+    pub async fn get_block(&self, x: i32, y: u16, z: i32) -> Result<Block> {
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16)).await?;
+        Ok(chunk.get_block(x.rem_euclid(16) as u8, y, z.rem_euclid(16) as u8))
+    }
+
+    /// Set a block and update the light around it, use `set_blocks` to set multiple blocks.
+    pub async fn set_block(&self, x: i32, y: u16, z: i32, block: Block) {
+        self.set_blocks(vec![((x, y, z), block)]).await;
+    }
+
+    /// Set multiple blocks, locking each chunk once and updating the light once. The blocks of
+    /// the chunks which fail to generate are skipped.
+    pub async fn set_blocks(
+        &self,
+        blocks: impl IntoIterator<Item = ((i32, u16, i32), Block)>,
+    ) -> Vec<ChunkEdit> {
+        let mut chunks = BTreeMap::new();
+        for ((x, y, z), block) in blocks {
+            chunks
+                .entry((x.div_euclid(16), z.div_euclid(16)))
+                .or_insert_with(Vec::new)
+                .push((x.rem_euclid(16) as u8, y, z.rem_euclid(16) as u8, block));
+        }
+
+        let mut edits = Vec::with_capacity(chunks.len());
+        for ((c_x, c_z), blocks) in chunks {
+            let mut chunk = match self.chunk(c_x, c_z).await {
+                Ok(chunk) => chunk,
+                Err(_) => continue,
+            };
+            let mut edit = ChunkEdit::new(c_x, c_z);
+            for (x, y, z, block) in blocks {
+                edit.set_block(&mut chunk, x, y, z, block);
+            }
+            edits.extend(edit.finish());
+        }
+        self.relight_edits(&edits).await;
+        edits
+    }
+
+    /// Set all the blocks between two corners (inclusive) of the map, skipping the chunks which
+    /// fail to generate.
+    pub async fn fill(
+        &self,
+        from: (i32, u16, i32),
+        to: (i32, u16, i32),
+        block: Block,
+    ) -> Vec<ChunkEdit> {
+        self.edit_region(from, to, |_| Some(block)).await
+    }
+
+    /// Replace the blocks matching `predicate` between two corners (inclusive) of the map,
+    /// skipping the chunks which fail to generate.
+    pub async fn replace(
+        &self,
+        from: (i32, u16, i32),
+        to: (i32, u16, i32),
+        block: Block,
+        predicate: impl Fn(Block) -> bool,
+    ) -> Vec<ChunkEdit> {
+        self.edit_region(from, to, |current| {
+            if predicate(current) {
+                Some(block)
+            } else {
+                None
+            }
+        })
+        .await
+    }
+
+    async fn edit_region(
+        &self,
+        from: (i32, u16, i32),
+        to: (i32, u16, i32),
+        edit: impl Fn(Block) -> Option<Block>,
+    ) -> Vec<ChunkEdit> {
+        let (min, max) = corners(from, to);
+        let mut edits = Vec::new();
+        for c_z in min.2.div_euclid(16)..=max.2.div_euclid(16) {
+            for c_x in min.0.div_euclid(16)..=max.0.div_euclid(16) {
+                let mut chunk = match self.chunk(c_x, c_z).await {
+                    Ok(chunk) => chunk,
+                    Err(_) => continue,
+                };
+                let mut chunk_edit = ChunkEdit::new(c_x, c_z);
+                for z in (c_z * 16).max(min.2)..=(c_z * 16 + 15).min(max.2) {
+                    for x in (c_x * 16).max(min.0)..=(c_x * 16 + 15).min(max.0) {
+                        let (x, z) = (x.rem_euclid(16) as u8, z.rem_euclid(16) as u8);
+                        for y in min.1..=max.1 {
+                            if let Some(block) = edit(chunk.get_block(x, y, z)) {
+                                chunk_edit.set_block(&mut chunk, x, y, z, block);
+                            }
+                        }
+                    }
+                }
+                edits.extend(chunk_edit.finish());
+            }
+        }
+        self.relight_edits(&edits).await;
+        edits
+    }
+
+    async fn relight_edits(&self, edits: &[ChunkEdit]) {
+        let positions = edits
+            .iter()
+            .map(|edit| (edit.x, edit.z))
+            .collect::<Vec<_>>();
+        self.relight(&positions).await;
+    }
+
+    /// Paste a structure and its block entities with its lowest corner at the given world
+    /// coordinates, skipping the chunks which fail to generate.
+    pub async fn paste(&self, structure: &Structure, x: i32, y: u16, z: i32) {
+        let (max_x, max_z) = (
+            x + structure.width() as i32 - 1,
+            z + structure.length() as i32 - 1,
+        );
+        let mut positions = Vec::new();
+        for c_z in z.div_euclid(16)..=max_z.div_euclid(16) {
+            for c_x in x.div_euclid(16)..=max_x.div_euclid(16) {
+                let mut chunk = match self.chunk(c_x, c_z).await {
+                    Ok(chunk) => chunk,
+                    Err(_) => continue,
+                };
+                structure.paste_into(&mut chunk, x, y, z);
+                positions.push((c_x, c_z));
+            }
+        }
+        self.relight(&positions).await;
+    }
+
+    /// Copy the blocks between two corners (inclusive) of the map into a structure, with copies
+    /// of their block entities.
+    pub async fn export(&self, from: (i32, u16, i32), to: (i32, u16, i32)) -> Result<Structure> {
+        let (min, max) = corners(from, to);
+        if min.1 > max.1 {
+            // The box is above the world.
+            return Ok(Structure::new(0, 0, 0));
+        }
+        let mut structure = Structure::new(
+            (max.0 - min.0 + 1) as u16,
+            max.1 - min.1 + 1,
+            (max.2 - min.2 + 1) as u16,
+        );
+
+        for c_z in min.2.div_euclid(16)..=max.2.div_euclid(16) {
+            for c_x in min.0.div_euclid(16)..=max.0.div_euclid(16) {
+                let chunk = self.chunk(c_x, c_z).await?;
+                for z in (c_z * 16).max(min.2)..=(c_z * 16 + 15).min(max.2) {
+                    for x in (c_x * 16).max(min.0)..=(c_x * 16 + 15).min(max.0) {
+                        let (l_x, l_z) = (x.rem_euclid(16) as u8, z.rem_euclid(16) as u8);
+                        let (s_x, s_z) = ((x - min.0) as u16, (z - min.2) as u16);
+                        for y in min.1..=max.1 {
+                            let block = chunk.get_block(l_x, y, l_z);
+                            structure.set_block(s_x, y - min.1, s_z, Some(block));
+                            if let Some(entity) = chunk.block_entity(l_x, y, l_z) {
+                                structure.set_block_entity(s_x, y - min.1, s_z, entity.duplicate());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(structure)
+    }
+}
+
+/// The lowest and the highest corners of the box between two corners, inside of the world.
+fn corners(from: (i32, u16, i32), to: (i32, u16, i32)) -> ((i32, u16, i32), (i32, u16, i32)) {
+    (
+        (from.0.min(to.0), from.1.min(to.1), from.2.min(to.2)),
+        (
+            from.0.max(to.0),
+            from.1.max(to.1).min(255),
+            from.2.max(to.2),
+        ),
+    )
+}
+
+/// The chunk and its 8 neighbours.
+fn around(x: i32, z: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-1..=1).flat_map(move |d_z| (-1..=1).map(move |d_x| (x + d_x, z + d_z)))
+}
+
+/// The blocks changed in a chunk by an edit, with their coordinates relative to the chunk.
+#[derive(Debug, Clone)]
+pub struct ChunkEdit {
+    pub x: i32,
+    pub z: i32,
+    pub blocks: Vec<(u8, u16, u8, Block)>,
+}
+
+impl ChunkEdit {
+    fn new(x: i32, z: i32) -> Self {
+        Self {
+            x,
+            z,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Change a block of the chunk, only recording actual changes.
+    fn set_block(&mut self, chunk: &mut Chunk, x: u8, y: u16, z: u8, block: Block) {
+        if chunk.get_block(x, y, z) != block {
+            chunk.set_block(x, y, z, block);
+            self.blocks.push((x, y, z, block));
+        }
+    }
+
+    /// `None` if nothing changed.
+    fn finish(self) -> Option<Self> {
+        if self.blocks.is_empty() {
+            return None;
+        }
+        Some(self)
+    }
+}
+
+/// The offsets of the square at `radius` blocks from a column, closest first.
+fn ring(radius: i32) -> Vec<(i32, i32)> {
+    let mut offsets = (-radius..=radius)
+        .flat_map(|dz| (-radius..=radius).map(move |dx| (dx, dz)))
+        .filter(|(dx, dz)| dx.abs().max(dz.abs()) == radius)
+        .collect::<Vec<_>>();
+    offsets.sort_by_key(|(dx, dz)| dx * dx + dz * dz);
+    offsets
+}
+
+/// Whether a player can stand on the block without being hurt, e.g. not on water or a cactus.
+fn is_safe_ground(block: Block) -> bool {
+    use BlockKind::*;
+    !placement::is_replaceable(block)
+        && !matches!(
+            block.kind(),
+            Cactus | Fire | MagmaBlock | Campfire | SweetBerryBush
+        )
+        && !block.kind().name().ends_with("_leaves")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::{BlockEntity, Item, ItemStack};
+    use futures_await_test::async_test;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(Arc<AtomicUsize>);
+
+    impl ChunkGenerator for Counting {
+        fn chunk(&self, x: i32, z: i32) -> Chunk {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let mut chunk = Chunk::new(x, z);
+            chunk.set_block(0, 0, 0, Block::Bedrock);
+            chunk
+        }
+
+        fn height(&self, _x: i32, _z: i32) -> u16 {
+            0
+        }
+    }
+
+    #[async_test]
+    async fn test_deduplication() -> Result<()> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let map = Map::new(Counting(Arc::clone(&count))).await;
+
+        let chunks = map.load_all((0..10).map(|i| (i % 3, 0))).await;
+        assert_eq!(chunks.len(), 10);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        let (a, b) = future::join(map.load(5, 5), map.load(5, 5)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+        assert_eq!(a?.lock().await.x, 5);
+        assert_eq!(b?.lock().await.get_block(0, 0, 0), Block::Bedrock);
+
+        map.pregenerate((0, 0), 2).await;
+        assert_eq!(count.load(Ordering::SeqCst), 4 + 25 - 3);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_safe_spawn() {
+        let map = Map::new(Counting(Arc::new(AtomicUsize::new(0)))).await;
+        assert_eq!(map.safe_spawn(0, 0).await, BlockPosition::new(0, 1, 0));
+
+        // The closest bedrock blocks are at the corners of the neighbour chunks.
+        map.set_block(0, 1, 0, Block::Water).await;
+        assert_eq!(map.safe_spawn(0, 0).await, BlockPosition::new(0, 1, -16));
+    }
+
+    /// Panics when generating the chunks of x = 1.
+    struct Panicking(Arc<AtomicUsize>);
+
+    impl ChunkGenerator for Panicking {
+        fn chunk(&self, x: i32, z: i32) -> Chunk {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            assert_ne!(x, 1, "generator failure");
+            Counting(Arc::new(AtomicUsize::new(0))).chunk(x, z)
+        }
+
+        fn height(&self, _x: i32, _z: i32) -> u16 {
+            0
+        }
+    }
+
+    #[async_test]
+    async fn test_generator_panic() -> Result<()> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let map = Map::new(Panicking(Arc::clone(&count))).await;
+
+        // More failures than workers, the workers survive and the chunk is generated again.
+        let attempts = std::thread::available_parallelism().map_or(1, |n| n.get()) + 1;
+        for attempt in 1..=attempts {
+            assert!(map.load(1, 0).await.is_err());
+            assert_eq!(count.load(Ordering::SeqCst), attempt);
+        }
+
+        let chunks = map.load_all(vec![(0, 0), (1, 1)]).await;
+        assert_eq!(chunks[0].as_ref().unwrap().lock().await.x, 0);
+        assert!(chunks[1].is_err());
+
+        // Edits skip the failed chunks.
+        let edits = map.fill((15, 0, 0), (16, 0, 0), Block::Stone).await;
+        assert_eq!(edits.len(), 1);
+        assert_eq!(map.get_block(15, 0, 0).await?, Block::Stone);
+        assert!(map.get_block(16, 0, 0).await.is_err());
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_set_block() -> Result<()> {
+        let map = Map::new(Counting(Arc::new(AtomicUsize::new(0)))).await;
+        map.set_block(-1, 10, 17, Block::Stone).await;
+        let chunk = map.chunk(-1, 1).await?;
+        assert_eq!(chunk.get_block(15, 10, 1), Block::Stone);
+        assert_eq!(chunk.light().sky_light(15, 9, 1), 14);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_light_across_chunks() -> Result<()> {
+        let map = Map::new(Counting(Arc::new(AtomicUsize::new(0)))).await;
+        map.load_all(vec![(0, 0), (1, 0)]).await;
+        map.take_relit().await;
+
+        map.set_block(15, 20, 4, Block::Lava).await;
+        assert_eq!(map.chunk(1, 0).await?.light().block_light(0, 20, 4), 14);
+        assert_eq!(
+            map.take_relit().await.into_iter().collect::<Vec<_>>(),
+            [(1, 0)]
+        );
+
+        // A chunk loaded next to the lava is lit by it.
+        map.set_block(0, 20, 4, Block::Lava).await;
+        map.take_relit().await;
+        assert_eq!(map.chunk(-1, 0).await?.light().block_light(15, 20, 4), 14);
+
+        // The light disappears from the neighbours with the lava.
+        map.set_blocks(vec![((15, 20, 4), Block::Air), ((0, 20, 4), Block::Air)])
+            .await;
+        assert_eq!(map.chunk(1, 0).await?.light().block_light(0, 20, 4), 0);
+        assert_eq!(map.chunk(-1, 0).await?.light().block_light(15, 20, 4), 0);
+        assert_eq!(
+            map.take_relit().await.into_iter().collect::<Vec<_>>(),
+            [(-1, 0), (1, 0)]
+        );
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_paste_export() -> Result<()> {
+        let map = Map::new(Counting(Arc::new(AtomicUsize::new(0)))).await;
+        let mut structure = Structure::new(20, 2, 3);
+        structure.fill(Block::Glass);
+        structure.set_block(19, 1, 2, Some(Block::Stone));
+        structure.set_block(3, 0, 1, Some(Block::Chest));
+        let chest = BlockEntity::new(Block::Chest).unwrap();
+        if let BlockEntity::Chest { items, .. } = &chest {
+            items.set(0, Some(ItemStack::new(Item::Diamond, 2)));
+        }
+        structure.set_block_entity(3, 0, 1, chest.clone());
+        map.paste(&structure, -5, 10, 14).await;
+
+        assert_eq!(map.chunk(-1, 0).await?.get_block(11, 10, 14), Block::Glass);
+        assert_eq!(map.chunk(0, 1).await?.get_block(14, 11, 0), Block::Stone);
+        assert_eq!(
+            map.chunk(-1, 0).await?.block_entity(14, 10, 15),
+            Some(&chest)
+        );
+        assert_eq!(map.export((-5, 10, 14), (14, 11, 16)).await?, structure);
+
+        let exported = map.export((-5, 12, 16), (-6, 9, 16)).await?;
+        assert_eq!(exported.get_block(1, 0, 0), Some(Block::Air));
+        assert_eq!(exported.get_block(1, 1, 0), Some(Block::Glass));
+        assert_eq!(exported.get_block(0, 2, 0), Some(Block::Air));
+
+        let above = map.export((0, 300, 0), (3, 280, 3)).await?;
+        assert_eq!(above.height(), 0);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_bulk_edits() -> Result<()> {
+        let map = Map::new(Counting(Arc::new(AtomicUsize::new(0)))).await;
+
+        let edits = map.fill((-2, 0, 3), (17, 1, 3), Block::Stone).await;
+        assert_eq!(edits.len(), 3);
+        assert_eq!((edits[0].x, edits[0].z), (-1, 0));
+        assert_eq!(edits[0].blocks.len(), 4);
+        assert_eq!(edits[1].blocks.len(), 32);
+        assert_eq!(
+            edits[2].blocks,
+            vec![
+                (0, 0, 3, Block::Stone),
+                (0, 1, 3, Block::Stone),
+                (1, 0, 3, Block::Stone),
+                (1, 1, 3, Block::Stone)
+            ]
+        );
+        assert_eq!(map.chunk(1, 0).await?.get_block(1, 1, 3), Block::Stone);
+
+        // Nothing changes, nothing is reported.
+        assert!(map
+            .fill((0, 0, 3), (5, 1, 3), Block::Stone)
+            .await
+            .is_empty());
+
+        let edits = map
+            .replace((-16, 0, 0), (31, 0, 15), Block::Dirt, |b| b != Block::Air)
+            .await;
+        let changed = edits.iter().map(|e| e.blocks.len()).sum::<usize>();
+        // 20 stone blocks at y = 0, and the bedrock of the 3 chunks.
+        assert_eq!(changed, 20 + 3);
+        assert_eq!(map.chunk(0, 0).await?.get_block(0, 0, 0), Block::Dirt);
+        assert_eq!(map.chunk(0, 0).await?.get_block(0, 1, 3), Block::Stone);
 
-        let mut chunk = self.chunk(x / 16, z / 16).await;
-        chunk.set_block(x.rem_euclid(16) as u8, y, z.rem_euclid(16) as u8, block);
+        let edits = map
+            .set_blocks(vec![
+                ((0, 5, 0), Block::Glass),
+                ((-1, 5, 0), Block::Glass),
+                ((1, 5, 0), Block::Glass),
+                ((1, 5, 0), Block::Glass),
+            ])
+            .await;
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].blocks.len(), 2);
+        assert_eq!(map.chunk(-1, 0).await?.get_block(15, 5, 0), Block::Glass);
+        Ok(())
     }
 }
@@ -0,0 +1,539 @@
+//! Read and write structures as schematic files.
+//!
+//! Sponge schematics (`.schem`, version 2) are read and written, legacy MCEdit schematics
+//! (`.schematic`) can only be read. Both are gzipped NBT files.
+//!
+//! Block entities are only read and written in Sponge schematics, unsupported ones are ignored.
+//! Entities and the offset of Sponge schematics are ignored.
+
+use crate::game::map::structure::Structure;
+use crate::packets::play::{Block, BlockEntity};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use nbt::{Blob, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const SPONGE_VERSION: i32 = 2;
+/// The data version of Minecraft 1.15.2.
+const DATA_VERSION: i32 = 2230;
+
+type Compound = HashMap<String, Value>;
+
+/// Load a schematic file, either a Sponge or a legacy MCEdit schematic.
+pub fn load(path: impl AsRef<Path>) -> Result<Structure> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("{}", path.display()))?;
+    read(&mut BufReader::new(file)).with_context(|| format!("{}", path.display()))
+}
+
+/// Save a structure as a Sponge schematic file.
+pub fn save(structure: &Structure, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let file = File::create(path).with_context(|| format!("{}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write(structure, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a gzipped schematic, either a Sponge or a legacy MCEdit schematic.
+pub fn read(reader: &mut impl Read) -> Result<Structure> {
+    let root = read_root(reader)?;
+    if root.contains_key("Palette") {
+        read_sponge(&root)
+    } else if root.contains_key("Blocks") {
+        read_legacy(&root)
+    } else {
+        Err(anyhow!("unknown schematic format"))
+    }
+}
+
+/// `nbt::Blob` doesn't give access to its content, read the root compound directly.
+fn read_root(reader: &mut impl Read) -> Result<Compound> {
+    let mut reader = GzDecoder::new(reader)?;
+    let mut header = [0; 3];
+    reader.read_exact(&mut header)?;
+    if header[0] != 0x0a {
+        return Err(anyhow!("missing NBT root compound"));
+    }
+    let name_length = u16::from_be_bytes([header[1], header[2]]);
+    io::copy(&mut (&mut reader).take(name_length as u64), &mut io::sink())?;
+
+    match Value::from_reader(0x0a, &mut reader)? {
+        Value::Compound(root) => Ok(root),
+        _ => unreachable!(),
+    }
+}
+
+fn get<'a>(compound: &'a Compound, key: &str) -> Result<&'a Value> {
+    compound
+        .get(key)
+        .ok_or_else(|| anyhow!("missing schematic tag: {}", key))
+}
+
+fn get_size(compound: &Compound, key: &str) -> Result<u16> {
+    match get(compound, key)? {
+        Value::Short(size) => Ok(*size as u16),
+        _ => Err(anyhow!("invalid schematic tag: {}", key)),
+    }
+}
+
+/// The width, height and length of a schematic, and its number of blocks, which must be checked
+/// against its data before allocating the structure.
+fn get_sizes(compound: &Compound) -> Result<((u16, u16, u16), usize)> {
+    let width = get_size(compound, "Width")?;
+    let height = get_size(compound, "Height")?;
+    let length = get_size(compound, "Length")?;
+    let volume = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|volume| volume.checked_mul(length as usize))
+        .ok_or_else(|| anyhow!("invalid schematic size"))?;
+    Ok(((width, height, length), volume))
+}
+
+fn get_bytes<'a>(compound: &'a Compound, key: &str) -> Result<&'a [i8]> {
+    match get(compound, key)? {
+        Value::ByteArray(bytes) => Ok(bytes),
+        _ => Err(anyhow!("invalid schematic tag: {}", key)),
+    }
+}
+
+fn read_sponge(root: &Compound) -> Result<Structure> {
+    let ((width, height, length), volume) = get_sizes(root)?;
+    let data = get_bytes(root, "BlockData")?;
+    // Each index takes at least a byte.
+    if data.len() < volume {
+        return Err(anyhow!("invalid schematic size"));
+    }
+    let mut data = data.iter().map(|b| *b as u8).peekable();
+    let mut indexes = Vec::with_capacity(volume);
+    while data.peek().is_some() {
+        indexes.push(read_var_int(&mut data)?);
+    }
+    if indexes.len() != volume {
+        return Err(anyhow!("invalid schematic size"));
+    }
+    let mut structure = Structure::new(width, height, length);
+
+    let palette = match get(root, "Palette")? {
+        Value::Compound(palette) => palette,
+        _ => return Err(anyhow!("invalid schematic tag: Palette")),
+    };
+    let mut blocks = vec![Block::Air; palette.len()];
+    for (name, index) in palette {
+        let index = match index {
+            Value::Int(index) if (*index as usize) < blocks.len() => *index as usize,
+            _ => return Err(anyhow!("invalid palette index for {}", name)),
+        };
+        blocks[index] = name.parse()?;
+    }
+
+    let mut indexes = indexes.into_iter();
+    for y in 0..height {
+        for z in 0..length {
+            for x in 0..width {
+                let index = indexes
+                    .next()
+                    .ok_or_else(|| anyhow!("truncated block data"))?;
+                let block = blocks
+                    .get(index)
+                    .ok_or_else(|| anyhow!("invalid block index: {}", index))?;
+                structure.set_block(x, y, z, Some(*block));
+            }
+        }
+    }
+
+    let entities = match root.get("BlockEntities") {
+        Some(Value::List(entities)) => entities.as_slice(),
+        _ => &[],
+    };
+    for entity in entities {
+        let mut entity = match entity {
+            Value::Compound(entity) => entity.clone(),
+            _ => return Err(anyhow!("invalid schematic tag: BlockEntities")),
+        };
+        let (x, y, z) = match entity.get("Pos") {
+            Some(Value::IntArray(position)) if position.len() == 3 => {
+                (position[0], position[1], position[2])
+            }
+            _ => return Err(anyhow!("invalid block entity position")),
+        };
+        let inside = |value: i32, size: u16| (0..size as i32).contains(&value);
+        if !inside(x, structure.width())
+            || !inside(y, structure.height())
+            || !inside(z, structure.length())
+        {
+            return Err(anyhow!("invalid block entity position"));
+        }
+        // Sponge schematics name the id `Id`, worlds `id`.
+        if let Some(id) = entity.remove("Id") {
+            entity.insert("id".into(), id);
+        }
+        if let Ok(entity) = BlockEntity::from_nbt(&entity) {
+            structure.set_block_entity(x as u16, y as u16, z as u16, entity);
+        }
+    }
+    Ok(structure)
+}
+
+fn read_var_int(data: &mut impl Iterator<Item = u8>) -> Result<usize> {
+    let mut value = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = data.next().ok_or_else(|| anyhow!("truncated block data"))?;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("invalid block data"))
+}
+
+fn write_var_int(data: &mut Vec<i8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            data.push(byte as i8);
+            return;
+        }
+        data.push((byte | 0x80) as i8);
+    }
+}
+
+/// Write a structure as a gzipped Sponge schematic. Empty positions are written as air.
+pub fn write(structure: &Structure, writer: &mut impl Write) -> Result<()> {
+    let mut palette = HashMap::new();
+    let mut data = Vec::new();
+    for y in 0..structure.height() {
+        for z in 0..structure.length() {
+            for x in 0..structure.width() {
+                let block = structure.get_block(x, y, z).unwrap_or(Block::Air);
+                let count = palette.len();
+                let index = *palette.entry(block).or_insert(count);
+                write_var_int(&mut data, index);
+            }
+        }
+    }
+
+    let palette = palette
+        .into_iter()
+        .map(|(block, index)| (block.to_string(), Value::Int(index as i32)))
+        .collect::<HashMap<_, _>>();
+
+    let entities = structure
+        .block_entities()
+        .map(|((x, y, z), entity)| {
+            let mut compound = entity.to_nbt(x as i32, y, z as i32);
+            for key in &["id", "x", "y", "z"] {
+                compound.remove(*key);
+            }
+            compound.insert("Id".into(), entity.id().into());
+            compound.insert(
+                "Pos".into(),
+                Value::IntArray(vec![x as i32, y as i32, z as i32]),
+            );
+            Value::Compound(compound)
+        })
+        .collect::<Vec<_>>();
+
+    let mut blob = Blob::named("Schematic");
+    blob.insert("Version", SPONGE_VERSION)?;
+    blob.insert("DataVersion", DATA_VERSION)?;
+    blob.insert("Width", structure.width() as i16)?;
+    blob.insert("Height", structure.height() as i16)?;
+    blob.insert("Length", structure.length() as i16)?;
+    blob.insert("PaletteMax", palette.len() as i32)?;
+    blob.insert("Palette", Value::Compound(palette))?;
+    blob.insert("BlockData", data)?;
+    if !entities.is_empty() {
+        blob.insert("BlockEntities", Value::List(entities))?;
+    }
+    blob.to_gzip_writer(writer)?;
+    Ok(())
+}
+
+fn read_legacy(root: &Compound) -> Result<Structure> {
+    match root.get("Materials") {
+        Some(Value::String(materials)) if materials != "Alpha" => {
+            return Err(anyhow!("unsupported schematic materials: {}", materials))
+        }
+        _ => {}
+    }
+
+    let ((width, height, length), volume) = get_sizes(root)?;
+    let ids = get_bytes(root, "Blocks")?;
+    let data = get_bytes(root, "Data")?;
+    if ids.len() != volume || data.len() != volume {
+        return Err(anyhow!("invalid schematic size"));
+    }
+    let add = match root.get("AddBlocks") {
+        Some(Value::ByteArray(add)) => add.as_slice(),
+        _ => &[],
+    };
+    let mut structure = Structure::new(width, height, length);
+
+    let mut index = 0;
+    for y in 0..height {
+        for z in 0..length {
+            for x in 0..width {
+                let mut id = ids[index] as u8 as u16;
+                if let Some(add) = add.get(index >> 1) {
+                    let add = *add as u8 as u16;
+                    id |= if index & 1 == 0 { add & 0x0F } else { add >> 4 } << 8;
+                }
+                let block = legacy_block(id, data[index] as u8 & 0x0F);
+                structure.set_block(x, y, z, block);
+                index += 1;
+            }
+        }
+    }
+    Ok(structure)
+}
+
+const COLORS: [&str; 16] = [
+    "white",
+    "orange",
+    "magenta",
+    "light_blue",
+    "yellow",
+    "lime",
+    "pink",
+    "gray",
+    "light_gray",
+    "cyan",
+    "purple",
+    "blue",
+    "brown",
+    "green",
+    "red",
+    "black",
+];
+const WOODS: [&str; 6] = ["oak", "spruce", "birch", "jungle", "acacia", "dark_oak"];
+
+/// Convert a pre-1.13 block id and its data value. Only the most common blocks are supported,
+/// other blocks are left empty.
+fn legacy_block(id: u16, data: u8) -> Option<Block> {
+    let wood = |index: u8| WOODS.get(index as usize).copied();
+    let color = COLORS[data as usize];
+    let axis = ["y", "x", "z", "y"][(data >> 2) as usize];
+
+    let name = match (id, data) {
+        (0, _) => "air".to_string(),
+        (1, _) => [
+            "stone",
+            "granite",
+            "polished_granite",
+            "diorite",
+            "polished_diorite",
+            "andesite",
+            "polished_andesite",
+        ]
+        .get(data as usize)?
+        .to_string(),
+        (2, _) => "grass_block".to_string(),
+        (3, _) => ["dirt", "coarse_dirt", "podzol"]
+            .get(data as usize)?
+            .to_string(),
+        (4, _) => "cobblestone".to_string(),
+        (5, _) => format!("{}_planks", wood(data)?),
+        (6, _) => format!("{}_sapling", wood(data & 7)?),
+        (7, _) => "bedrock".to_string(),
+        (8, _) | (9, _) => format!("water[level={}]", data),
+        (10, _) | (11, _) => format!("lava[level={}]", data),
+        (12, 0) => "sand".to_string(),
+        (12, 1) => "red_sand".to_string(),
+        (13, _) => "gravel".to_string(),
+        (14, _) => "gold_ore".to_string(),
+        (15, _) => "iron_ore".to_string(),
+        (16, _) => "coal_ore".to_string(),
+        (17, _) => format!("{}_log[axis={}]", wood(data & 3)?, axis),
+        (18, _) => format!("{}_leaves[persistent=true]", wood(data & 3)?),
+        (19, 0) => "sponge".to_string(),
+        (19, 1) => "wet_sponge".to_string(),
+        (20, _) => "glass".to_string(),
+        (21, _) => "lapis_ore".to_string(),
+        (22, _) => "lapis_block".to_string(),
+        (24, _) => ["sandstone", "chiseled_sandstone", "cut_sandstone"]
+            .get(data as usize)?
+            .to_string(),
+        (31, 1) => "grass".to_string(),
+        (31, 2) => "fern".to_string(),
+        (31, _) | (32, _) => "dead_bush".to_string(),
+        (35, _) => format!("{}_wool", color),
+        (37, _) => "dandelion".to_string(),
+        (38, 0) => "poppy".to_string(),
+        (41, _) => "gold_block".to_string(),
+        (42, _) => "iron_block".to_string(),
+        (45, _) => "bricks".to_string(),
+        (46, _) => "tnt".to_string(),
+        (47, _) => "bookshelf".to_string(),
+        (48, _) => "mossy_cobblestone".to_string(),
+        (49, _) => "obsidian".to_string(),
+        (50, 1) => "wall_torch[facing=east]".to_string(),
+        (50, 2) => "wall_torch[facing=west]".to_string(),
+        (50, 3) => "wall_torch[facing=south]".to_string(),
+        (50, 4) => "wall_torch[facing=north]".to_string(),
+        (50, _) => "torch".to_string(),
+        (52, _) => "spawner".to_string(),
+        (56, _) => "diamond_ore".to_string(),
+        (57, _) => "diamond_block".to_string(),
+        (58, _) => "crafting_table".to_string(),
+        (60, _) => "farmland".to_string(),
+        (73, _) | (74, _) => "redstone_ore".to_string(),
+        (78, _) => format!("snow[layers={}]", (data & 7) + 1),
+        (79, _) => "ice".to_string(),
+        (80, _) => "snow_block".to_string(),
+        (81, _) => "cactus".to_string(),
+        (82, _) => "clay".to_string(),
+        (87, _) => "netherrack".to_string(),
+        (88, _) => "soul_sand".to_string(),
+        (89, _) => "glowstone".to_string(),
+        (95, _) => format!("{}_stained_glass", color),
+        (98, _) => [
+            "stone_bricks",
+            "mossy_stone_bricks",
+            "cracked_stone_bricks",
+            "chiseled_stone_bricks",
+        ]
+        .get(data as usize)?
+        .to_string(),
+        (103, _) => "melon".to_string(),
+        (112, _) => "nether_bricks".to_string(),
+        (121, _) => "end_stone".to_string(),
+        (133, _) => "emerald_block".to_string(),
+        (152, _) => "redstone_block".to_string(),
+        (155, 0) => "quartz_block".to_string(),
+        (159, _) => format!("{}_terracotta", color),
+        (161, _) => format!("{}_leaves[persistent=true]", wood((data & 1) + 4)?),
+        (162, _) => format!("{}_log[axis={}]", wood((data & 1) + 4)?, axis),
+        (168, _) => ["prismarine", "prismarine_bricks", "dark_prismarine"]
+            .get(data as usize)?
+            .to_string(),
+        (169, _) => "sea_lantern".to_string(),
+        (172, _) => "terracotta".to_string(),
+        (173, _) => "coal_block".to_string(),
+        (174, _) => "packed_ice".to_string(),
+        (251, _) => format!("{}_concrete", color),
+        _ => return None,
+    };
+    Some(name.parse().expect("invalid legacy block conversion"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::{Item, ItemStack, Sign};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    #[test]
+    fn test_sponge() {
+        let mut structure = Structure::new(3, 2, 200);
+        structure.fill(Block::Stone);
+        structure.set_block(
+            0,
+            0,
+            0,
+            Some(Block::OakStairs.with_property("half", "top").unwrap()),
+        );
+        // Enough different blocks for indexes to take multiple bytes.
+        for (z, block) in (0..200).zip((1..).filter_map(Block::from_id).step_by(50)) {
+            structure.set_block(2, 1, z, Some(block));
+        }
+        structure.set_block(1, 1, 1, None);
+        structure.set_block(1, 0, 5, Some(Block::OakSign));
+        structure.set_block_entity(
+            1,
+            0,
+            5,
+            BlockEntity::Sign(Sign::new(["Hello", "", "", "world"])),
+        );
+        structure.set_block(1, 0, 6, Some(Block::Chest));
+        let chest = BlockEntity::new(Block::Chest).unwrap();
+        if let BlockEntity::Chest { items, .. } = &chest {
+            items.set(26, Some(ItemStack::new(Item::Diamond, 5)));
+        }
+        structure.set_block_entity(1, 0, 6, chest);
+
+        let mut file = Vec::new();
+        write(&structure, &mut file).unwrap();
+        let read = read(&mut file.as_slice()).unwrap();
+
+        structure.set_block(1, 1, 1, Some(Block::Air));
+        assert_eq!(read.block_entities().count(), 2);
+        assert_eq!(read, structure);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let mut blob = Blob::named("Schematic");
+        blob.insert("Width", 2_i16).unwrap();
+        blob.insert("Height", 1_i16).unwrap();
+        blob.insert("Length", 2_i16).unwrap();
+        blob.insert("Materials", "Alpha").unwrap();
+        blob.insert("Blocks", vec![35_i8, 17, 0, 123]).unwrap();
+        blob.insert("Data", vec![14_i8, 4 | 2, 0, 0]).unwrap();
+
+        let mut file = Vec::new();
+        blob.to_writer(&mut GzEncoder::new(&mut file, Compression::Default))
+            .unwrap();
+        let structure = read(&mut file.as_slice()).unwrap();
+
+        assert_eq!(structure.get_block(0, 0, 0), Some(Block::RedWool));
+        assert_eq!(
+            structure.get_block(1, 0, 0),
+            Some("birch_log[axis=x]".parse().unwrap())
+        );
+        assert_eq!(structure.get_block(0, 0, 1), Some(Block::Air));
+        assert_eq!(structure.get_block(1, 0, 1), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut blob = Blob::new();
+        blob.insert("Width", 1_i16).unwrap();
+        let mut file = Vec::new();
+        blob.to_gzip_writer(&mut file).unwrap();
+        assert!(read(&mut file.as_slice()).is_err());
+        assert!(read(&mut &b"not a schematic"[..]).is_err());
+    }
+
+    /// Huge sizes with little data are rejected before allocating the structure.
+    #[test]
+    fn test_invalid_sizes() {
+        for format in &["Sponge", "Legacy"] {
+            let mut blob = Blob::named("Schematic");
+            blob.insert("Width", -1_i16).unwrap();
+            blob.insert("Height", -1_i16).unwrap();
+            blob.insert("Length", -1_i16).unwrap();
+            if *format == "Sponge" {
+                blob.insert("Palette", Value::Compound(HashMap::new()))
+                    .unwrap();
+                blob.insert("BlockData", vec![0_i8; 16]).unwrap();
+            } else {
+                blob.insert("Blocks", vec![0_i8; 16]).unwrap();
+                blob.insert("Data", vec![0_i8; 16]).unwrap();
+            }
+            let mut file = Vec::new();
+            blob.to_gzip_writer(&mut file).unwrap();
+            assert!(read(&mut file.as_slice()).is_err());
+        }
+
+        // Multi-byte indexes: more bytes than blocks.
+        let mut blob = Blob::named("Schematic");
+        blob.insert("Width", 1_i16).unwrap();
+        blob.insert("Height", 1_i16).unwrap();
+        blob.insert("Length", 2_i16).unwrap();
+        let palette = vec![("minecraft:stone".to_string(), Value::Int(0))];
+        blob.insert("Palette", Value::Compound(palette.into_iter().collect()))
+            .unwrap();
+        blob.insert("BlockData", vec![0x80_u8 as i8, 0, 0, 0])
+            .unwrap();
+        let mut file = Vec::new();
+        blob.to_gzip_writer(&mut file).unwrap();
+        assert!(read(&mut file.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::game::map::generator::ChunkGenerator;
+use crate::packets::play::{Block, BlockEntity, Chunk};
+use std::collections::HashMap;
+
+/// A box of blocks which can be placed in a world, e.g. a house or a tree.
+///
+/// Empty positions (`None`) keep the block already in the world when the structure is placed.
+/// ```
+/// use minecrust::game::map::structure::Structure;
+/// use minecrust::packets::play::block::Block;
+///
+/// let mut pillar = Structure::new(1, 3, 1);
+/// pillar.fill(Block::Stone);
+/// pillar.set_block(0, 2, 0, Some(Block::Torch));
+/// assert_eq!(pillar.get_block(0, 2, 0), Some(Block::Torch));
+/// assert_eq!(pillar.blocks().count(), 3);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Structure {
+    width: u16,
+    height: u16,
+    length: u16,
+    blocks: Vec<Option<Block>>,
+    block_entities: HashMap<(u16, u16, u16), BlockEntity>,
+}
+
+impl Structure {
+    /// An empty structure of `width` (x) by `height` (y) by `length` (z) blocks.
+    pub fn new(width: u16, height: u16, length: u16) -> Self {
+        Self {
+            width,
+            height,
+            length,
+            blocks: vec![None; width as usize * height as usize * length as usize],
+            block_entities: HashMap::new(),
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    fn index(&self, x: u16, y: u16, z: u16) -> usize {
+        assert!(x < self.width && y < self.height && z < self.length);
+        (y as usize * self.length as usize + z as usize) * self.width as usize + x as usize
+    }
+
+    pub fn get_block(&self, x: u16, y: u16, z: u16) -> Option<Block> {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    /// Set a block, removing the block entity of the previous block if they don't have the
+    /// same type.
+    pub fn set_block(&mut self, x: u16, y: u16, z: u16, block: Option<Block>) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+        let id = block.and_then(|block| block.block_entity());
+        if self.block_entity(x, y, z).map(BlockEntity::id) != id {
+            self.block_entities.remove(&(x, y, z));
+        }
+    }
+
+    /// Set every position of the structure to `block`.
+    pub fn fill(&mut self, block: Block) {
+        self.blocks.iter_mut().for_each(|b| *b = Some(block));
+        self.block_entities.clear();
+    }
+
+    pub fn block_entity(&self, x: u16, y: u16, z: u16) -> Option<&BlockEntity> {
+        self.block_entities.get(&(x, y, z))
+    }
+
+    /// Set the block entity of a block, e.g. the items of a chest. Ignored if the block doesn't
+    /// hold this type of block entity.
+    pub fn set_block_entity(&mut self, x: u16, y: u16, z: u16, entity: BlockEntity) {
+        let block = self.get_block(x, y, z);
+        if block.and_then(|block| block.block_entity()) == Some(entity.id()) {
+            self.block_entities.insert((x, y, z), entity);
+        }
+    }
+
+    /// The block entities of the structure, with their position in the structure.
+    pub fn block_entities(&self) -> impl Iterator<Item = ((u16, u16, u16), &BlockEntity)> {
+        self.block_entities
+            .iter()
+            .map(|(position, entity)| (*position, entity))
+    }
+
+    /// The non empty positions of the structure and their block.
+    pub fn blocks(&self) -> impl Iterator<Item = ((u16, u16, u16), Block)> + '_ {
+        let (width, length) = (self.width as usize, self.length as usize);
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, block)| {
+                let position = (
+                    (i % width) as u16,
+                    (i / (width * length)) as u16,
+                    (i / width % length) as u16,
+                );
+                block.map(|block| (position, block))
+            })
+    }
+
+    /// Copy the part of the structure overlapping `chunk`, with copies of its block entities,
+    /// the lowest corner of the structure being at the given world coordinates.
+    pub fn paste_into(&self, chunk: &mut Chunk, x: i32, y: u16, z: i32) {
+        let (c_x, c_z) = (chunk.x * 16, chunk.z * 16);
+        let local = |(s_x, s_y, s_z): (u16, u16, u16)| {
+            let (l_x, l_y, l_z) = (x + s_x as i32 - c_x, y + s_y, z + s_z as i32 - c_z);
+            if (0..16).contains(&l_x) && (0..16).contains(&l_z) && l_y < 256 {
+                Some((l_x as u8, l_y, l_z as u8))
+            } else {
+                None
+            }
+        };
+        for (position, block) in self.blocks() {
+            if let Some((l_x, l_y, l_z)) = local(position) {
+                chunk.set_block(l_x, l_y, l_z, block);
+            }
+        }
+        for (position, entity) in self.block_entities() {
+            if let Some((l_x, l_y, l_z)) = local(position) {
+                chunk.set_block_entity(l_x, l_y, l_z, entity.duplicate());
+            }
+        }
+    }
+}
+
+/// A world only made of the structure, with its lowest corner at the origin of the world.
+impl ChunkGenerator for Structure {
+    fn chunk(&self, x: i32, z: i32) -> Chunk {
+        let mut chunk = Chunk::new(x, z);
+        self.paste_into(&mut chunk, 0, 0, 0);
+        chunk
+    }
+
+    fn height(&self, x: i32, z: i32) -> u16 {
+        if !(0..self.width as i32).contains(&x) || !(0..self.length as i32).contains(&z) {
+            return 0;
+        }
+        (0..self.height.min(256))
+            .rev()
+            .find(|y| {
+                self.get_block(x as u16, *y, z as u16)
+                    .filter(|block| *block != Block::Air)
+                    .is_some()
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let mut structure = Structure::new(2, 3, 4);
+        assert_eq!(structure.blocks().count(), 0);
+
+        structure.set_block(1, 2, 3, Some(Block::Stone));
+        structure.set_block(0, 1, 2, Some(Block::Dirt));
+        assert_eq!(structure.get_block(1, 2, 3), Some(Block::Stone));
+        assert_eq!(structure.get_block(1, 2, 2), None);
+        assert_eq!(
+            structure.blocks().collect::<Vec<_>>(),
+            vec![((0, 1, 2), Block::Dirt), ((1, 2, 3), Block::Stone)]
+        );
+
+        structure.fill(Block::Glass);
+        assert_eq!(structure.blocks().count(), 2 * 3 * 4);
+    }
+
+    #[test]
+    fn test_block_entities() {
+        let mut structure = Structure::new(2, 2, 2);
+        let chest = BlockEntity::new(Block::Chest).unwrap();
+        structure.set_block_entity(0, 0, 0, chest.clone());
+        assert!(structure.block_entity(0, 0, 0).is_none());
+
+        structure.set_block(0, 0, 0, Some(Block::Chest));
+        structure.set_block_entity(0, 0, 0, chest.clone());
+        assert_eq!(structure.block_entity(0, 0, 0), Some(&chest));
+        structure.set_block(0, 0, 0, Some(Block::TrappedChest));
+        assert!(structure.block_entity(0, 0, 0).is_none());
+
+        let mut chunk = Chunk::new(0, 0);
+        structure.set_block(1, 1, 1, Some(Block::Chest));
+        structure.set_block_entity(1, 1, 1, chest.clone());
+        structure.paste_into(&mut chunk, 14, 10, 14);
+        assert_eq!(chunk.block_entity(15, 11, 15), Some(&chest));
+    }
+
+    #[test]
+    fn test_paste_into() {
+        let mut structure = Structure::new(2, 1, 2);
+        structure.fill(Block::Stone);
+        structure.set_block(0, 0, 0, None);
+
+        let mut chunk = Chunk::new(1, -1);
+        chunk.set_block(15, 5, 15, Block::Dirt);
+        structure.paste_into(&mut chunk, 31, 5, -1);
+        assert_eq!(chunk.get_block(15, 5, 15), Block::Dirt);
+        assert_eq!(chunk.get_block(0, 5, 15), Block::Air);
+
+        structure.paste_into(&mut chunk, 30, 5, -2);
+        assert_eq!(chunk.get_block(15, 5, 14), Block::Stone);
+        assert_eq!(chunk.get_block(14, 5, 15), Block::Stone);
+        assert_eq!(chunk.get_block(15, 5, 15), Block::Stone);
+        assert_eq!(chunk.get_block(14, 5, 14), Block::Air);
+    }
+
+    #[test]
+    fn test_height() {
+        let mut structure = Structure::new(2, 4, 1);
+        structure.set_block(0, 2, 0, Some(Block::Stone));
+        structure.set_block(0, 3, 0, Some(Block::Air));
+        assert_eq!(ChunkGenerator::height(&structure, 0, 0), 2);
+        assert_eq!(ChunkGenerator::height(&structure, 1, 0), 0);
+        assert_eq!(ChunkGenerator::height(&structure, 0, 1), 0);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::game::map::generator::ChunkGenerator;
+use crate::packets::play::Chunk;
+use futures::channel::oneshot;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = (i32, i32, oneshot::Sender<Chunk>);
+
+/// A pool of threads generating chunks (and their light), so generation never blocks the async
+/// tasks of the server.
+pub struct Workers {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+impl Workers {
+    /// Start one worker per available CPU. Workers stop when the pool is dropped.
+    pub fn new(generator: Arc<dyn ChunkGenerator + Sync + Send>) -> Self {
+        let count = thread::available_parallelism().map_or(1, |n| n.get());
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for i in 0..count {
+            let generator = Arc::clone(&generator);
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(format!("chunk-worker-{}", i))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    let (x, z, result) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let chunk = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let mut chunk = generator.chunk(x, z);
+                        chunk.update_light();
+                        chunk
+                    }));
+                    // If the generator panicked, dropping the sender cancels the request and the
+                    // worker keeps running. The requester may also be gone, the chunk is then
+                    // just dropped.
+                    if let Ok(chunk) = chunk {
+                        let _ = result.send(chunk);
+                    }
+                })
+                .expect("failed to spawn a chunk worker");
+        }
+
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Queue the generation of a chunk, the receiver resolves once it's generated or is
+    /// cancelled if the generator panicked.
+    pub fn generate(&self, x: i32, z: i32) -> oneshot::Receiver<Chunk> {
+        let (sender, receiver) = oneshot::channel();
+        // Workers only stop with the pool, a failed send cancels the receiver anyway.
+        let _ = self.sender.lock().unwrap().send((x, z, sender));
+        receiver
+    }
+}
@@ -1,6 +1,13 @@
+pub mod border;
+pub mod container;
+pub mod entity;
+pub mod inventory;
 pub mod map;
+pub mod placement;
 pub mod player;
+pub mod scheduler;
 pub mod server_builder;
+pub mod weather;
 pub mod world;
 
 pub use player::Player;
@@ -0,0 +1,155 @@
+use crate::packets::play::{Block, BlockKind, Face, PlayerBlockPlacement};
+
+/// Whether a block can be replaced by a placed block, e.g. air or water.
+pub fn is_replaceable(block: Block) -> bool {
+    use BlockKind::*;
+    matches!(
+        block.kind(),
+        Air | CaveAir | VoidAir | Water | Lava | Grass | Fern | DeadBush | Seagrass | Vine | Snow
+    )
+}
+
+/// The horizontal direction a player is looking at, from its yaw in degrees.
+pub fn horizontal_facing(yaw: f32) -> &'static str {
+    match ((yaw / 90.).round() as i32).rem_euclid(4) {
+        0 => "south",
+        1 => "west",
+        2 => "north",
+        _ => "east",
+    }
+}
+
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "south" => "north",
+        "west" => "east",
+        "north" => "south",
+        "east" => "west",
+        "up" => "down",
+        _ => "up",
+    }
+}
+
+fn face_direction(face: Face) -> &'static str {
+    match face {
+        Face::Bottom => "down",
+        Face::Top => "up",
+        Face::North => "north",
+        Face::South => "south",
+        Face::West => "west",
+        Face::East => "east",
+    }
+}
+
+/// The state of a block placed against the clicked face by a player looking at `yaw`: logs
+/// follow the clicked face, slabs and stairs the clicked half, stairs face away from the player
+/// and other directional blocks face the player. Torches, signs, heads and banners placed on
+/// the side of a block use their wall variant.
+pub fn placed_block(kind: BlockKind, placement: &PlayerBlockPlacement, yaw: f32) -> Block {
+    let face = placement.face;
+    let side = face != Face::Top && face != Face::Bottom;
+    let facing = horizontal_facing(yaw);
+
+    if side {
+        if let Some(wall) = wall_variant(kind) {
+            let block = Block::from(wall);
+            return block
+                .with_property("facing", face_direction(face))
+                .unwrap_or(block);
+        }
+    }
+
+    let top_half = match face {
+        Face::Bottom => true,
+        Face::Top => false,
+        _ => placement.cursor.1 > 0.5,
+    };
+    let half = if top_half { "top" } else { "bottom" };
+
+    let mut block = Block::from(kind);
+    let mut set = |name: &str, value: &str| {
+        if let Some(changed) = block.with_property(name, value) {
+            block = changed;
+        }
+    };
+    if kind.name().ends_with("_slab") {
+        set("type", half);
+    }
+    if kind.name().ends_with("_stairs") {
+        set("half", half);
+        set("facing", facing);
+    } else {
+        set("facing", opposite(facing));
+    }
+    set(
+        "axis",
+        match face {
+            Face::Bottom | Face::Top => "y",
+            Face::North | Face::South => "z",
+            Face::West | Face::East => "x",
+        },
+    );
+    block
+}
+
+/// `minecraft:oak_sign` is placed on walls as `minecraft:oak_wall_sign`.
+fn wall_variant(kind: BlockKind) -> Option<BlockKind> {
+    let name = kind.name();
+    let split = name.rfind(['_', ':'])? + 1;
+    BlockKind::from_name(&format!("{}wall_{}", &name[..split], &name[split..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Hand;
+    use crate::types::BlockPosition;
+
+    fn placement(face: Face, cursor_y: f32) -> PlayerBlockPlacement {
+        PlayerBlockPlacement {
+            hand: Hand::Main,
+            position: BlockPosition::new(0, 64, 0),
+            face,
+            cursor: (0.5, cursor_y, 0.5),
+            inside_block: false,
+        }
+    }
+
+    #[test]
+    fn test_replaceable() {
+        assert!(is_replaceable(Block::Air));
+        assert!(is_replaceable(Block::Water));
+        assert!(!is_replaceable(Block::Stone));
+    }
+
+    #[test]
+    fn test_placed_block() {
+        let top = placement(Face::Top, 1.);
+        assert_eq!(placed_block(BlockKind::Stone, &top, 0.), Block::Stone);
+        assert_eq!(
+            placed_block(BlockKind::OakLog, &placement(Face::East, 0.5), 0.),
+            "oak_log[axis=x]".parse().unwrap()
+        );
+        assert_eq!(
+            placed_block(BlockKind::OakSlab, &placement(Face::North, 0.8), 0.),
+            "oak_slab[type=top]".parse().unwrap()
+        );
+        assert_eq!(
+            placed_block(BlockKind::OakStairs, &top, 90.),
+            "oak_stairs[facing=west,half=bottom]".parse().unwrap()
+        );
+        assert_eq!(
+            placed_block(BlockKind::Furnace, &top, 180.),
+            "furnace[facing=south]".parse().unwrap()
+        );
+        assert_eq!(placed_block(BlockKind::Torch, &top, 0.), Block::Torch);
+        assert_eq!(
+            placed_block(BlockKind::Torch, &placement(Face::West, 0.5), 0.),
+            "wall_torch[facing=west]".parse().unwrap()
+        );
+        assert_eq!(
+            placed_block(BlockKind::OakSign, &placement(Face::South, 0.5), 0.).kind(),
+            BlockKind::OakWallSign
+        );
+    }
+}
@@ -1,17 +1,33 @@
 use crate::fsm::Fsm;
+use crate::game::container::{Chest, Container, CraftingTable, Windows};
+use crate::game::entity::{Entity, Spawn};
+use crate::game::inventory::Inventory;
+use crate::game::placement;
 use crate::game::world::World;
 use crate::packets::play::{
     chat_message::{self, InChatMessage, OutChatMessage},
-    entity_position::{OutEntityHeadLook, OutPosition, OutPositionRotation, OutRotation},
+    entity_action::{self, EntityAction},
+    entity_position::{
+        EntityTeleport, OutEntityHeadLook, OutPosition, OutPositionRotation, OutRotation,
+    },
+    player_info::{self, PlayerInfo},
     player_position::{
         InPlayerPosition, InPlayerPositionRotation, InPlayerRotation, OutViewPosition,
+        PlayerPositionPacket, PlayerRotationPacket,
     },
-    Block, BlockChange, GameMode, OutPlayerPositionLook, PlayerDigging,
+    AcknowledgePlayerDigging, Animation, Block, BlockBreakAnimation, BlockChange, BlockEntity,
+    BlockKind, ChangeGameState, ClickMode, ClickWindow, ClientSettings, CloseWindow,
+    ConfirmTransaction, CreativeInventoryAction, DestroyEntity, DiggingStatus, Effect,
+    EntityAnimation, EntityEffect, EntityFlags, EntityMetadata, EntityStatus, GameMode, Hand,
+    HeldItemSlot, InAnimation, InCloseWindow, InConfirmTransaction, InHeldItemSlot, Item,
+    ItemStack, OpenSignEditor, OpenWindow, OutPlayerPositionLook, PlayerBlockPlacement,
+    PlayerDigging, Pose, RemoveEntityEffect, SetSlot, SkinParts, SpawnPlayer, Status, UpdateLight,
+    UpdateSign, UseItem, Window, WindowItems,
 };
 use crate::packets::Packet;
 use crate::types::{
-    self, chat::Chat, BoolOption, EntityPosition, LengthVec, Receive, ServerDescription,
-    TAsyncRead, TAsyncWrite, VarInt,
+    self, chat::Chat, BlockPosition, BoolOption, EntityPosition, LengthVec, PositionDelta, Receive,
+    ServerDescription, TAsyncRead, TAsyncWrite, VarInt,
 };
 use anyhow::Result;
 use futures::prelude::*;
@@ -19,7 +35,9 @@ use futures::AsyncWriteExt;
 use piper::{Lock, LockGuard};
 use std::cmp::min;
 use std::collections::HashSet;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Instant;
 use uuid::Uuid;
 
 pub struct Player {
@@ -30,10 +48,42 @@ pub struct Player {
     info: Info,
     position: Lock<EntityPosition>,
     loaded_chunks: Lock<HashSet<(i32, i32)>>,
+    tracked_entities: Lock<HashSet<VarInt>>,
+    flags: Lock<EntityFlags>,
+    skin_parts: Lock<SkinParts>,
+    /// The distance in chunks the client renders, up to the one of the server.
+    view_distance: Lock<i32>,
+    inventory: Lock<Inventory>,
+    windows: Lock<Windows>,
+    digging: Lock<Option<Digging>>,
+}
+
+/// A block being broken in survival mode.
+struct Digging {
+    position: BlockPosition,
+    started: Instant,
+    ticks: u32,
+    stage: u8,
+}
+
+impl Digging {
+    /// The part of the break time the client is allowed to skip, to absorb latency.
+    const TOLERANCE: f64 = 0.3;
+
+    fn progress(&self) -> f64 {
+        let ticks = self.started.elapsed().as_secs_f64() / World::TICK.as_secs_f64();
+        ticks / self.ticks as f64
+    }
 }
 
 impl Player {
     const RENDER_DISTANCE: i32 = 16;
+    /// The distance in chunks under which other players see the player.
+    pub const TRACKING_RANGE: i32 = 32;
+    /// Maximum distance between the eyes of the player and the center of a block it interacts
+    /// with.
+    const REACH: f64 = 6.;
+    const EYE_HEIGHT: f64 = 1.62;
 
     pub async fn new(
         reader: impl TAsyncRead + 'static,
@@ -50,20 +100,32 @@ impl Player {
         }
         let login = login.unwrap();
 
-        let id = (SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis()
-            % 1000) as i32;
+        let info = Info::from_name(&*login.user_name);
+        info.set_game_mode(world.game_mode());
+        let spawn = world.spawn().await;
+        let position = EntityPosition::new(
+            spawn.x as f64 + 0.5,
+            spawn.y as f64,
+            spawn.z as f64 + 0.5,
+            0,
+            0,
+        );
 
         Ok(Some(Self {
             read_stream: Lock::new(reader),
             write_stream: Lock::new(writer),
             world,
-            id: VarInt(id),
-            info: Info::from_name(&*login.user_name),
-            position: Lock::new(EntityPosition::new(0., 5., 0., 0, 0)),
+            id: world.next_entity_id(),
+            info,
+            position: Lock::new(position),
             loaded_chunks: Lock::new(HashSet::new()),
+            tracked_entities: Lock::new(HashSet::new()),
+            flags: Lock::new(EntityFlags::default()),
+            skin_parts: Lock::new(SkinParts::default()),
+            view_distance: Lock::new(Self::RENDER_DISTANCE),
+            inventory: Lock::new(Inventory::new()),
+            windows: Lock::new(Windows::default()),
+            digging: Lock::new(None),
         }))
     }
 
@@ -83,6 +145,436 @@ impl Player {
         self.position.lock().await
     }
 
+    /// Whether the chunk has been sent to the player.
+    pub async fn has_loaded_chunk(&self, x: i32, z: i32) -> bool {
+        self.loaded_chunks.lock().await.contains(&(x, z))
+    }
+
+    /// Whether the block is close enough to be broken or placed by the player.
+    pub async fn can_reach(&self, block: &BlockPosition) -> bool {
+        let position = self.position.lock().await;
+        let dx = block.x as f64 + 0.5 - position.x;
+        let dy = block.y as f64 + 0.5 - (position.y + Self::EYE_HEIGHT);
+        let dz = block.z as f64 + 0.5 - position.z;
+        dx * dx + dy * dy + dz * dz <= Self::REACH * Self::REACH
+    }
+
+    pub async fn inventory(&self) -> LockGuard<Inventory> {
+        self.inventory.lock().await
+    }
+
+    /// The item held in the given hand.
+    pub async fn held_item(&self, hand: Hand) -> Option<ItemStack> {
+        self.inventory.lock().await.held(hand).cloned()
+    }
+
+    /// Select a hotbar slot, from 0 to 8.
+    pub async fn set_held_slot(&self, slot: u8) -> Result<()> {
+        let packet = HeldItemSlot::new(slot as i8)?;
+        self.inventory.lock().await.set_selected(slot);
+        self.send_packet(&packet).await
+    }
+
+    /// Add items to the inventory, return what didn't fit.
+    pub async fn give_item(&self, item: ItemStack) -> Result<Option<ItemStack>> {
+        let leftover = self.inventory.lock().await.give(item);
+        self.send_inventory_changes().await?;
+        Ok(leftover)
+    }
+
+    /// Remove up to `count` items of a type from the inventory, return how many were removed.
+    pub async fn take_item(&self, item: Item, count: u32) -> Result<u32> {
+        let taken = self.inventory.lock().await.take(item, count);
+        self.send_inventory_changes().await?;
+        Ok(taken)
+    }
+
+    /// Set the content of a slot of the inventory, see `Inventory` for the indexes.
+    pub async fn set_slot(&self, index: u16, item: Option<ItemStack>) -> Result<()> {
+        self.inventory.lock().await.set(index, item);
+        self.send_inventory_changes().await
+    }
+
+    /// Send the slots changed since the last update.
+    async fn send_inventory_changes(&self) -> Result<()> {
+        let changes = {
+            let mut inventory = self.inventory.lock().await;
+            inventory
+                .take_changes()
+                .into_iter()
+                .map(|index| SetSlot::new(Window::Inventory, index, inventory.get(index).cloned()))
+                .collect::<Vec<_>>()
+        };
+        for change in changes {
+            self.send_packet(&change).await?;
+        }
+        Ok(())
+    }
+
+    /// Open the window of a container, the previous window is closed.
+    pub async fn open_window(&self, container: Box<dyn Container>) -> Result<()> {
+        self.close_window().await?;
+        let (kind, title) = (container.window_type(), container.title());
+        let (id, _) = self.windows.lock().await.open(container);
+        self.send_packet(&OpenWindow::new(id, kind, title)).await?;
+        self.send_window().await
+    }
+
+    /// Close the opened container window, if any.
+    pub async fn close_window(&self) -> Result<()> {
+        let id = self.windows.lock().await.id();
+        if id != 0 {
+            self.send_packet(&CloseWindow(id)).await?;
+            self.window_closed(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the player opened the container of the block at this position.
+    pub async fn is_viewing(&self, position: &BlockPosition) -> bool {
+        let windows = self.windows.lock().await;
+        windows
+            .container()
+            .and_then(|container| container.position())
+            == Some(position)
+    }
+
+    /// Send the content of the opened window again, with the item held by the cursor.
+    pub async fn send_window(&self) -> Result<()> {
+        let (items, cursor) = {
+            let mut windows = self.windows.lock().await;
+            let mut inventory = self.inventory.lock().await;
+            inventory.take_changes();
+            let id = windows.id();
+            let window = match id {
+                0 => Window::Inventory,
+                id => Window::Container(id),
+            };
+            let items = WindowItems::new(window, &windows.view(id, &mut inventory)?.0.items());
+            (items, SetSlot::cursor(inventory.cursor().cloned()))
+        };
+        self.send_packet(&items).await?;
+        self.send_packet(&cursor).await
+    }
+
+    /// Apply a click on a slot, or refuse it and send the actual content of the window if the
+    /// client doesn't see the same items as the server.
+    async fn click_window(&self, click: ClickWindow) -> Result<()> {
+        let creative = self.info.game_mode() == GameMode::Creative;
+        let (accepted, thrown, position) = {
+            let mut windows = self.windows.lock().await;
+            let mut inventory = self.inventory.lock().await;
+            let position = windows
+                .container()
+                .and_then(|container| container.position().cloned());
+            match windows.view(click.window, &mut inventory) {
+                Ok((mut view, drag)) => {
+                    let expected = match click.mode {
+                        ClickMode::Click(_) if click.slot >= 0 => view.get(click.slot as u16),
+                        _ => click.item.clone(),
+                    };
+                    if expected != click.item {
+                        (false, Vec::new(), None)
+                    } else {
+                        match view.click(&click, drag, creative) {
+                            Ok(thrown) => (true, thrown, position),
+                            Err(_) => (false, Vec::new(), None),
+                        }
+                    }
+                }
+                Err(_) => (false, Vec::new(), None),
+            }
+        };
+
+        let confirm = ConfirmTransaction::new(click.window, click.action, accepted);
+        self.send_packet(&confirm).await?;
+        // The client only shows the result of a drag once it ends.
+        let dragging = matches!(click.mode, ClickMode::Drag(button) if button % 4 != 2);
+        if !accepted || !dragging {
+            self.send_window().await?;
+        }
+
+        for item in thrown {
+            self.throw_item(item).await?;
+        }
+        if let Some(position) = position {
+            self.world.refresh_container(&position, self).await?;
+        }
+        Ok(())
+    }
+
+    /// Give back the items left in the closed window and held by the cursor.
+    async fn window_closed(&self, id: u8) -> Result<()> {
+        let leftovers = {
+            let mut windows = self.windows.lock().await;
+            let mut inventory = self.inventory.lock().await;
+            let mut items = windows
+                .close(id)
+                .map(|mut container| container.close())
+                .unwrap_or_default();
+            for index in Inventory::CRAFTING..Inventory::ARMOR {
+                items.extend(inventory.get(index).cloned());
+                inventory.set(index, None);
+            }
+            items.extend(inventory.cursor().cloned());
+            inventory.set_cursor(None);
+            items
+                .into_iter()
+                .filter_map(|item| inventory.give(item))
+                .collect::<Vec<_>>()
+        };
+        for item in leftovers {
+            self.throw_item(item).await?;
+        }
+        self.send_inventory_changes().await
+    }
+
+    /// Drop items at the feet of the player.
+    async fn throw_item(&self, item: ItemStack) -> Result<()> {
+        let position = {
+            let position = self.position.lock().await;
+            BlockPosition::new(
+                position.x.floor() as i32,
+                position.y.max(0.) as u16,
+                position.z.floor() as i32,
+            )
+        };
+        self.world.drop_item(&position, item).await
+    }
+
+    /// The metadata sent when the player is spawned for another player.
+    pub async fn metadata(&self) -> EntityMetadata {
+        let flags = *self.flags.lock().await;
+        EntityMetadata::new(self.id)
+            .with_flags(flags)
+            .with_pose(Self::pose(flags))
+            .with_skin_parts(*self.skin_parts.lock().await)
+    }
+
+    fn pose(flags: EntityFlags) -> Pose {
+        if flags.contains(EntityFlags::SNEAKING) {
+            Pose::Sneaking
+        } else {
+            Pose::Standing
+        }
+    }
+
+    /// Show the other players that the player started or stopped sneaking or sprinting.
+    async fn entity_action(&self, action: EntityAction) -> Result<()> {
+        if action.id != self.id {
+            return Ok(());
+        }
+        let flags = {
+            let mut flags = self.flags.lock().await;
+            match action.action {
+                entity_action::Action::StartSneaking => flags.set(EntityFlags::SNEAKING, true),
+                entity_action::Action::StopSneaking => flags.set(EntityFlags::SNEAKING, false),
+                entity_action::Action::StartSprinting => flags.set(EntityFlags::SPRINTING, true),
+                entity_action::Action::StopSprinting => flags.set(EntityFlags::SPRINTING, false),
+                _ => return Ok(()),
+            }
+            *flags
+        };
+        let metadata = EntityMetadata::new(self.id)
+            .with_flags(flags)
+            .with_pose(Self::pose(flags));
+        self.send_to_trackers(&metadata, false).await
+    }
+
+    /// Show the skin layers chosen by the player, to everybody including the player itself.
+    async fn update_settings(&self, settings: ClientSettings) -> Result<()> {
+        *self.skin_parts.lock().await = settings.skin_parts;
+        *self.view_distance.lock().await =
+            (settings.view_distance as i32).clamp(2, Self::RENDER_DISTANCE);
+        let metadata = EntityMetadata::new(self.id).with_skin_parts(settings.skin_parts);
+        self.send_to_trackers(&metadata, true).await
+    }
+
+    /// Play an animation of the player for the other players, e.g. an arm swing.
+    pub async fn animate(&self, animation: Animation) -> Result<()> {
+        let packet = EntityAnimation::new(self.id, animation);
+        self.send_to_trackers(&packet, false).await
+    }
+
+    /// Play an event of the player for everybody including the player itself, e.g. the hurt
+    /// animation.
+    pub async fn send_status(&self, status: Status) -> Result<()> {
+        let packet = EntityStatus::new(self.id, status);
+        self.send_to_trackers(&packet, true).await
+    }
+
+    /// Give an effect to the player, `effect` is sent with the id of the player.
+    pub async fn add_effect(&self, effect: Effect, amplifier: u8, ticks: u32) -> Result<()> {
+        let packet = EntityEffect::new(self.id, effect, amplifier, ticks);
+        self.send_to_trackers(&packet, true).await
+    }
+
+    pub async fn remove_effect(&self, effect: Effect) -> Result<()> {
+        let packet = RemoveEntityEffect::new(self.id, effect);
+        self.send_to_trackers(&packet, true).await
+    }
+
+    /// Change the game mode of the player, and update it in the player list of everybody.
+    pub async fn set_game_mode(&self, game_mode: GameMode) -> Result<()> {
+        self.info.set_game_mode(game_mode);
+        self.send_packet(&ChangeGameState::game_mode(game_mode))
+            .await?;
+        let info = PlayerInfo::new(player_info::Action::UpdateGameMode, vec![&self.info]);
+        self.world.broadcast_packet(&info).await
+    }
+
+    /// Send a packet about the player to the players who see it, and to the player itself if
+    /// `include_self` is set.
+    async fn send_to_trackers(
+        &self,
+        packet: &(impl Packet + Sync),
+        include_self: bool,
+    ) -> Result<()> {
+        if include_self {
+            self.send_packet(packet).await?;
+        }
+        self.world.send_to_trackers(self.id, packet).await
+    }
+
+    /// Apply a move sent by the client. A move outside of the world border is refused and the
+    /// player is teleported back inside.
+    async fn move_to(
+        &self,
+        to: &(dyn PlayerPositionPacket + Sync),
+        rotation: Option<&(dyn PlayerRotationPacket + Sync)>,
+        on_ground: bool,
+    ) -> Result<()> {
+        if let Some(rotation) = rotation {
+            self.position.lock().await.update_angle(rotation);
+        }
+        if !self.world.border().await.contains(to.x(), to.z()) {
+            let (x, y, z) = {
+                let position = self.position.lock().await;
+                let (x, z) = self.world.border().await.clamp(position.x, position.z);
+                (x, position.y, z)
+            };
+            return self.teleport(x, y, z).await;
+        }
+        let delta = self.position.lock().await.update_position(to);
+        self.moved(&delta, on_ground, rotation.is_some()).await
+    }
+
+    /// Move the player, whatever the client thinks its position is.
+    pub async fn teleport(&self, x: f64, y: f64, z: f64) -> Result<()> {
+        let (delta, mut packet) = {
+            let mut position = self.position.lock().await;
+            let mut to = position.clone();
+            to.x = x;
+            to.y = y;
+            to.z = z;
+            let delta = position.delta(&to);
+            *position = to;
+            (delta, OutPlayerPositionLook::from(&*position))
+        };
+        // Keep the rotation of the client, the one of the server is less precise.
+        packet.x_angle = 0.;
+        packet.z_angle = 0.;
+        packet.relative_flag = OutPlayerPositionLook::RELATIVE_ROTATION;
+        self.send_packet(&packet).await?;
+        self.moved(&delta, false, false).await
+    }
+
+    /// Send a move to the other players, and the chunks newly in range to the player.
+    async fn moved(&self, delta: &PositionDelta, on_ground: bool, rotated: bool) -> Result<()> {
+        self.send_movement(delta, on_ground, rotated).await?;
+
+        if delta.subchunk_changed {
+            let out_view = OutViewPosition::from(&*self.position.lock().await);
+            self.send_packet(&out_view).await?;
+        }
+
+        self.send_needed_chunks(Self::RENDER_DISTANCE).await
+    }
+
+    /// Send the move of the player to the players who see it, as a relative move or as a
+    /// teleport when it is too long.
+    async fn send_movement(
+        &self,
+        delta: &PositionDelta,
+        on_ground: bool,
+        rotated: bool,
+    ) -> Result<()> {
+        if delta.teleport {
+            let teleport = EntityTeleport::new(self.id, &*self.position.lock().await, on_ground);
+            self.send_to_trackers(&teleport, false).await?;
+        } else if rotated {
+            let out_position_rotation = OutPositionRotation::from(self, delta, on_ground).await;
+            self.send_to_trackers(&out_position_rotation, false).await?;
+        } else {
+            let out_position = OutPosition::new(self.id, delta, on_ground);
+            self.send_to_trackers(&out_position, false).await?;
+        }
+        if rotated {
+            let out_head_look = OutEntityHeadLook::from(self).await;
+            self.send_to_trackers(&out_head_look, false).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether an entity at this position is close enough to be seen by the player, within the
+    /// tracking range of the entity and the view distance of the player.
+    pub async fn can_see(&self, position: &EntityPosition, range: i32) -> bool {
+        let (x, z) = position.chunk();
+        if !self.has_loaded_chunk(x, z).await {
+            return false;
+        }
+        let range = range.min(*self.view_distance.lock().await) as f64 * 16.;
+        let own = self.position.lock().await;
+        (own.x - position.x).abs() <= range && (own.z - position.z).abs() <= range
+    }
+
+    /// Spawn another player on the client if they are visible and weren't already, or destroy
+    /// them if they aren't visible anymore.
+    pub async fn track_player(&self, other: &Player, visible: bool) -> Result<()> {
+        let mut tracked = self.tracked_entities.lock().await;
+        if visible && tracked.insert(other.id) {
+            self.send_packet(&SpawnPlayer::new(other).await).await?;
+            self.send_packet(&other.metadata().await).await?;
+            self.send_packet(&OutEntityHeadLook::from(other).await)
+                .await?;
+        } else if !visible && tracked.remove(&other.id) {
+            self.send_packet(&DestroyEntity::single(other.id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the entity has been spawned on the client.
+    pub async fn is_tracking(&self, id: VarInt) -> bool {
+        self.tracked_entities.lock().await.contains(&id)
+    }
+
+    /// Spawn the entity on the client if it is visible and wasn't already, or destroy it if it
+    /// isn't visible anymore.
+    pub async fn track(&self, entity: &dyn Entity, visible: bool) -> Result<()> {
+        let mut tracked = self.tracked_entities.lock().await;
+        if visible && tracked.insert(entity.id()) {
+            match entity.spawn() {
+                Spawn::Object(spawn) => self.send_packet(&spawn).await?,
+                Spawn::Living(spawn) => self.send_packet(&spawn).await?,
+            }
+            if let Some(metadata) = entity.metadata() {
+                self.send_packet(&metadata).await?;
+            }
+        } else if !visible && tracked.remove(&entity.id()) {
+            self.send_packet(&DestroyEntity::single(entity.id()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Destroy a removed entity on the client, if it was spawned.
+    pub async fn untrack(&self, id: VarInt) -> Result<()> {
+        if self.tracked_entities.lock().await.remove(&id) {
+            self.send_packet(&DestroyEntity::single(id)).await?;
+        }
+        Ok(())
+    }
+
     pub async fn send_packet(&self, packet: &(impl Packet + Sync)) -> Result<()> {
         packet
             .send_packet(&mut *self.write_stream.lock().await)
@@ -106,6 +598,9 @@ impl Player {
         );
         self.send_packet(&message).await?;
 
+        let items = WindowItems::new(Window::Inventory, self.inventory.lock().await.slots());
+        self.send_packet(&items).await?;
+
         self.handle_packet().await
     }
 
@@ -124,96 +619,365 @@ impl Player {
                 }
                 InPlayerPosition::PACKET_ID => {
                     let in_position: InPlayerPosition = rest_reader.receive().await?;
-                    let delta = self.position.lock().await.update_position(&in_position);
-
-                    let out_position = OutPosition::from(&self, &delta, in_position.on_ground);
-                    self.world
-                        .broadcast_packet_except(&out_position, &self)
+                    self.move_to(&in_position, None, in_position.on_ground)
                         .await?;
-
-                    if delta.subchunk_changed {
-                        let out_view = OutViewPosition::from(&*self.position.lock().await);
-                        self.send_packet(&out_view).await?;
-                    }
-
-                    self.send_needed_chunks(Self::RENDER_DISTANCE).await?;
                 }
                 InPlayerPositionRotation::PACKET_ID => {
                     let in_position_rotation: InPlayerPositionRotation =
                         rest_reader.receive().await?;
-                    let delta = self
-                        .position
-                        .lock()
-                        .await
-                        .update_position(&in_position_rotation);
-                    self.position
-                        .lock()
-                        .await
-                        .update_angle(&in_position_rotation);
-
-                    let out_position_rotation =
-                        OutPositionRotation::from(&self, &delta, in_position_rotation.on_ground)
-                            .await;
-                    self.world
-                        .broadcast_packet_except(&out_position_rotation, &self)
-                        .await?;
-
-                    let out_head_look = OutEntityHeadLook::from(&self).await;
-                    self.world
-                        .broadcast_packet_except(&out_head_look, &self)
-                        .await?;
-
-                    if delta.subchunk_changed {
-                        let out_view = OutViewPosition::from(&*self.position.lock().await);
-                        self.send_packet(&out_view).await?;
-                    }
-
-                    self.send_needed_chunks(Self::RENDER_DISTANCE).await?;
+                    let on_ground = in_position_rotation.on_ground;
+                    self.move_to(
+                        &in_position_rotation,
+                        Some(&in_position_rotation),
+                        on_ground,
+                    )
+                    .await?;
                 }
                 InPlayerRotation::PACKET_ID => {
                     let in_rotation: InPlayerRotation = rest_reader.receive().await?;
                     self.position.lock().await.update_angle(&in_rotation);
 
-                    let out_rotation = OutRotation::from(&self, in_rotation.on_ground).await;
-                    self.world
-                        .broadcast_packet_except(&out_rotation, &self)
-                        .await?;
+                    let out_rotation = OutRotation::from(self, in_rotation.on_ground).await;
+                    self.send_to_trackers(&out_rotation, false).await?;
 
-                    let out_head_look = OutEntityHeadLook::from(&self).await;
-                    self.world
-                        .broadcast_packet_except(&out_head_look, &self)
-                        .await?;
+                    let out_head_look = OutEntityHeadLook::from(self).await;
+                    self.send_to_trackers(&out_head_look, false).await?;
                 }
                 PlayerDigging::PACKET_ID => {
                     let action: PlayerDigging = rest_reader.receive().await?;
-                    if let PlayerDigging::FinishedDigging(position, _face) = action {
-                        let block_change = BlockChange::new(position.clone(), Block::Air);
-                        self.world
-                            .broadcast_packet_except(&block_change, &self)
-                            .await?;
+                    self.dig(action).await?;
+                }
+                InHeldItemSlot::PACKET_ID => {
+                    let InHeldItemSlot(slot) = rest_reader.receive().await?;
+                    self.inventory.lock().await.set_selected(slot);
+                }
+                CreativeInventoryAction::PACKET_ID => {
+                    let action: CreativeInventoryAction = rest_reader.receive().await?;
+                    let creative = self.info.game_mode() == GameMode::Creative;
+                    if creative && action.index == CreativeInventoryAction::DROP {
+                        if let Some(item) = action.item {
+                            self.throw_item(item).await?;
+                        }
+                    } else if creative {
+                        let mut inventory = self.inventory.lock().await;
+                        inventory.set(action.index as u16, action.item);
+                        // The client already knows the new content of the slot.
+                        inventory.take_changes();
                     }
                 }
+                PlayerBlockPlacement::PACKET_ID => {
+                    let placement: PlayerBlockPlacement = rest_reader.receive().await?;
+                    self.place_block(&placement).await?;
+                }
+                ClickWindow::PACKET_ID => {
+                    let click: ClickWindow = rest_reader.receive().await?;
+                    self.click_window(click).await?;
+                }
+                InCloseWindow::PACKET_ID => {
+                    let InCloseWindow(id) = rest_reader.receive().await?;
+                    self.window_closed(id).await?;
+                }
+                InConfirmTransaction::PACKET_ID => {
+                    // Sent back after a refused click, the window has already been sent again.
+                    let _: InConfirmTransaction = rest_reader.receive().await?;
+                }
+                InAnimation::PACKET_ID => {
+                    let InAnimation(hand) = rest_reader.receive().await?;
+                    self.animate(hand.into()).await?;
+                }
+                EntityAction::PACKET_ID => {
+                    let action: EntityAction = rest_reader.receive().await?;
+                    self.entity_action(action).await?;
+                }
+                ClientSettings::PACKET_ID => {
+                    let settings: ClientSettings = rest_reader.receive().await?;
+                    self.update_settings(settings).await?;
+                }
+                UpdateSign::PACKET_ID => {
+                    let update: UpdateSign = rest_reader.receive().await?;
+                    self.write_sign(update).await?;
+                }
+                UseItem::PACKET_ID => {
+                    // No item has an effect when used in the air yet.
+                    let _: UseItem = rest_reader.receive().await?;
+                }
                 _ => {
                     let _error = futures::io::copy(rest_reader, &mut futures::io::sink()).await;
                     print!("{} ", packet_id);
                 }
             }
+
+            // Clients keep sending packets while digging, e.g. arm swings.
+            self.update_digging().await?;
+        }
+    }
+
+    /// Break blocks as soon as digging starts in creative mode, or when it is finished and
+    /// has lasted long enough with the held tool in survival mode, and acknowledge the action.
+    async fn dig(&self, action: PlayerDigging) -> Result<()> {
+        let (position, status) = match action {
+            PlayerDigging::StartedDigging(position, _) => (position, DiggingStatus::Started),
+            PlayerDigging::CancelledDigging(position, _) => (position, DiggingStatus::Cancelled),
+            PlayerDigging::FinishedDigging(position, _) => (position, DiggingStatus::Finished),
+            _ => return Ok(()),
+        };
+        if position.y >= 256 {
+            let ack = AcknowledgePlayerDigging::new(position, Block::Air, status, false);
+            return self.send_packet(&ack).await;
+        }
+
+        let map = &self.world.map;
+        let block = map.get_block(position.x, position.y, position.z).await?;
+        let tool = self
+            .held_item(Hand::Main)
+            .await
+            .and_then(|item| item.item().tool());
+        let creative = self.info.game_mode() == GameMode::Creative;
+        // Blocks of the spawn protection can't be broken, as if they were out of reach.
+        let reachable =
+            self.can_reach(&position).await && !self.world.is_protected(&position).await;
+
+        // Whether the action is accepted, and whether the block breaks.
+        let (successful, broken) = match status {
+            DiggingStatus::Started if creative => (reachable, reachable),
+            DiggingStatus::Started => match block.break_ticks(tool) {
+                Some(0) => (reachable, reachable),
+                Some(ticks) if reachable => {
+                    self.start_digging(position.clone(), ticks).await?;
+                    (true, false)
+                }
+                _ => (false, false),
+            },
+            DiggingStatus::Cancelled => {
+                self.stop_digging().await?;
+                (true, false)
+            }
+            DiggingStatus::Finished => {
+                let valid = match self.stop_digging().await? {
+                    Some(digging) => {
+                        reachable
+                            && digging.position == position
+                            && digging.progress() >= 1. - Digging::TOLERANCE
+                    }
+                    None => false,
+                };
+                (valid, valid)
+            }
+        };
+
+        if broken {
+            if let BlockKind::Chest | BlockKind::TrappedChest = block.kind() {
+                self.world.remove_chest(&position).await?;
+            }
+            self.world.set_block(position.clone(), Block::Air).await?;
+            let drop = block.drops(tool, &mut rand::thread_rng());
+            if let Some(item) = drop.filter(|_| !creative) {
+                self.world.drop_item(&position, item).await?;
+            }
+        }
+
+        let block = map.get_block(position.x, position.y, position.z).await?;
+        let ack = AcknowledgePlayerDigging::new(position, block, status, successful);
+        self.send_packet(&ack).await
+    }
+
+    async fn start_digging(&self, position: BlockPosition, ticks: u32) -> Result<()> {
+        let animation = BlockBreakAnimation::new(self.id, position.clone(), Some(0));
+        *self.digging.lock().await = Some(Digging {
+            position,
+            started: Instant::now(),
+            ticks,
+            stage: 0,
+        });
+        self.world.broadcast_packet_except(&animation, self).await
+    }
+
+    /// Stop digging and remove the cracks of the block.
+    async fn stop_digging(&self) -> Result<Option<Digging>> {
+        let digging = self.digging.lock().await.take();
+        if let Some(digging) = &digging {
+            let animation = BlockBreakAnimation::new(self.id, digging.position.clone(), None);
+            self.world.broadcast_packet_except(&animation, self).await?;
+        }
+        Ok(digging)
+    }
+
+    /// Show the progress of the current dig to the other players.
+    async fn update_digging(&self) -> Result<()> {
+        let animation = match &mut *self.digging.lock().await {
+            Some(digging) => {
+                let stage = (digging.progress() * 10.).min(9.) as u8;
+                if stage == digging.stage {
+                    return Ok(());
+                }
+                digging.stage = stage;
+                BlockBreakAnimation::new(self.id, digging.position.clone(), Some(stage))
+            }
+            None => return Ok(()),
+        };
+        self.world.broadcast_packet_except(&animation, self).await
+    }
+
+    /// Place the held block against the clicked face. The change is sent to every player who
+    /// loaded the chunk, the placer included, who is sent back the actual blocks if the
+    /// placement is refused, e.g. out of reach.
+    async fn place_block(&self, placement: &PlayerBlockPlacement) -> Result<()> {
+        if self.open_container(&placement.position).await? {
+            return Ok(());
+        }
+
+        let kind = match self.held_item(placement.hand).await {
+            Some(item) => match item.item().block() {
+                Some(kind) => kind,
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        let clicked = &placement.position;
+        if clicked.y >= 256 {
+            return Ok(());
+        }
+        let reachable = self.can_reach(clicked).await;
+        let map = &self.world.map;
+        let target =
+            if placement::is_replaceable(map.get_block(clicked.x, clicked.y, clicked.z).await?) {
+                Some(clicked.clone())
+            } else {
+                placement
+                    .face
+                    .offset(clicked)
+                    .filter(|target| target.y < 256)
+            };
+
+        let target = match target {
+            Some(target)
+                if reachable
+                    && !self.world.is_protected(&target).await
+                    && placement::is_replaceable(
+                        map.get_block(target.x, target.y, target.z).await?,
+                    ) =>
+            {
+                target
+            }
+            target => {
+                for position in target.iter().chain(std::iter::once(clicked)) {
+                    let block = map.get_block(position.x, position.y, position.z).await?;
+                    self.send_packet(&BlockChange::new(position.clone(), block))
+                        .await?;
+                }
+                return Ok(());
+            }
+        };
+
+        let yaw = self.position.lock().await.rotation().0;
+        let block = placement::placed_block(kind, placement, yaw);
+        self.world.set_block(target.clone(), block).await?;
+        if block.block_entity() == Some("minecraft:sign") {
+            self.edit_sign(target).await?;
+        }
+
+        if self.info.game_mode() != GameMode::Creative {
+            let mut inventory = self.inventory.lock().await;
+            let index = inventory.held_index(placement.hand);
+            if let Some(mut item) = inventory.get(index).cloned() {
+                item.set_count(item.count() - 1);
+                inventory.set(index, Some(item));
+            }
+        }
+        self.send_inventory_changes().await
+    }
+
+    /// Open the window of the clicked block if it has one, e.g. a chest.
+    async fn open_container(&self, position: &BlockPosition) -> Result<bool> {
+        if position.y >= 256 || !self.can_reach(position).await {
+            return Ok(false);
         }
+        let block = self
+            .world
+            .map
+            .get_block(position.x, position.y, position.z)
+            .await?;
+        let container: Box<dyn Container> = match block.kind() {
+            BlockKind::Chest | BlockKind::TrappedChest => {
+                let items = match self.world.chest(position).await {
+                    Some(items) => items,
+                    None => return Ok(false),
+                };
+                Box::new(Chest::new(position.clone(), items))
+            }
+            BlockKind::CraftingTable => Box::new(CraftingTable::new()),
+            _ => return Ok(false),
+        };
+        self.open_window(container).await?;
+        Ok(true)
+    }
+
+    /// Let the player write the text of the sign they just placed.
+    async fn edit_sign(&self, position: BlockPosition) -> Result<()> {
+        let id = self.id;
+        self.world
+            .update_block_entity(&position, |entity| {
+                if let BlockEntity::Sign(sign) = entity {
+                    sign.set_editor(Some(id));
+                }
+                false
+            })
+            .await?;
+        self.send_packet(&OpenSignEditor(position)).await
+    }
+
+    /// Set the text written by the player, only on the sign they were allowed to edit.
+    async fn write_sign(&self, update: UpdateSign) -> Result<()> {
+        let id = self.id;
+        let lines = [
+            update.lines[0].as_str(),
+            update.lines[1].as_str(),
+            update.lines[2].as_str(),
+            update.lines[3].as_str(),
+        ];
+        self.world
+            .update_block_entity(&update.position, |entity| match entity {
+                BlockEntity::Sign(sign) if sign.editor() == Some(id) => {
+                    sign.set_lines(lines);
+                    sign.set_editor(None);
+                    true
+                }
+                _ => false,
+            })
+            .await
     }
 
     async fn send_chunks_around(&self, range: i32) -> Result<()> {
         let (p_x, p_z) = self.position.lock().await.chunk();
-        let mut chunks = self.loaded_chunks.lock().await;
+        // The chunks are marked as loaded before being sent, the lock isn't held while they are
+        // generated and sent. Blocks changed in between are already in the sent chunks.
+        let positions = {
+            let mut chunks = self.loaded_chunks.lock().await;
+            let mut positions = Vec::new();
+            for z in p_z - range..p_z + range {
+                for x in p_x - range..p_x + range {
+                    if chunks.insert((x, z)) {
+                        positions.push((x, z));
+                    }
+                }
+            }
+            positions
+        };
 
-        for z in p_z - range..p_z + range {
-            for x in p_x - range..p_x + range {
-                if !chunks.insert((x, z)) {
+        // Generate the missing chunks in parallel before sending them. The chunks which fail to
+        // generate are requested again later.
+        let chunks = self.world.map.load_all(positions.clone()).await;
+        for (position, chunk) in positions.into_iter().zip(chunks) {
+            let chunk = match chunk {
+                Ok(chunk) => chunk.lock().await,
+                Err(_) => {
+                    self.loaded_chunks.lock().await.remove(&position);
                     continue;
                 }
-
-                let chunk = self.world.map.chunk(x, z).await;
-                self.send_packet(&*chunk).await?;
-            }
+            };
+            self.send_packet(&UpdateLight::new(&chunk)).await?;
+            self.send_packet(&*chunk).await?;
         }
         Ok(())
     }
@@ -249,7 +1013,7 @@ pub struct Info {
     uuid: Uuid,
     name: types::String,
     properties: LengthVec<InfoProperty>,
-    game_mode: GameMode,
+    game_mode: SharedGameMode,
     ping: VarInt,
     display_name: BoolOption<Chat>,
 }
@@ -261,7 +1025,7 @@ impl Info {
             uuid: offline_uuid(&name),
             name,
             properties: LengthVec::new(),
-            game_mode: GameMode::Creative,
+            game_mode: SharedGameMode::new(GameMode::Creative),
             ping: VarInt::new(5),
             display_name: BoolOption(None),
         }
@@ -273,7 +1037,7 @@ impl Info {
             uuid: offline_uuid(&name),
             name: types::String::new(&name[..min(name.len(), 16)]),
             properties: LengthVec::new(),
-            game_mode: GameMode::Creative,
+            game_mode: SharedGameMode::new(GameMode::Creative),
             ping: VarInt::new(5),
             display_name: BoolOption(None),
         }
@@ -286,6 +1050,40 @@ impl Info {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn game_mode(&self) -> GameMode {
+        self.game_mode.get()
+    }
+
+    pub(crate) fn set_game_mode(&self, game_mode: GameMode) {
+        self.game_mode.set(game_mode);
+    }
+}
+
+/// A game mode which can be changed while the info of the player is borrowed.
+#[derive(Debug)]
+struct SharedGameMode(AtomicU8);
+
+impl SharedGameMode {
+    fn new(game_mode: GameMode) -> Self {
+        Self(AtomicU8::new(game_mode as u8))
+    }
+
+    fn get(&self) -> GameMode {
+        GameMode::try_from(self.0.load(Ordering::Relaxed)).unwrap()
+    }
+
+    fn set(&self, game_mode: GameMode) {
+        self.0.store(game_mode as u8, Ordering::Relaxed);
+    }
+}
+
+crate::impl_size!(SharedGameMode, 1);
+#[async_trait::async_trait]
+impl types::Send for SharedGameMode {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        self.get().send(writer).await
+    }
 }
 
 #[derive(Debug, macro_derive::Size, macro_derive::Send)]
@@ -326,3 +1124,120 @@ fn offline_uuid(name: &str) -> Uuid {
 
     builder.build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::generator::FlatChunkGenerator;
+    use crate::packets::play::Face;
+    use futures::io::Cursor;
+    use futures::task::{Context, Poll};
+    use futures_await_test::async_test;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    /// A stream keeping what the player is sent.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl AsyncWrite for Output {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, std::io::Error>> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), std::io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), std::io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn player(world: &'static World, x: f64, y: f64, z: f64) -> (Player, Output) {
+        let output = Output::default();
+        let player = Player {
+            read_stream: Lock::new(Box::new(Cursor::new(Vec::new()))),
+            write_stream: Lock::new(Box::new(output.clone())),
+            world,
+            id: world.next_entity_id(),
+            info: Info::from_name("Player"),
+            position: Lock::new(EntityPosition::new(x, y, z, 0, 0)),
+            loaded_chunks: Lock::new(HashSet::new()),
+            tracked_entities: Lock::new(HashSet::new()),
+            flags: Lock::new(EntityFlags::default()),
+            skin_parts: Lock::new(SkinParts::default()),
+            view_distance: Lock::new(Player::RENDER_DISTANCE),
+            inventory: Lock::new(Inventory::new()),
+            windows: Lock::new(Windows::default()),
+            digging: Lock::new(None),
+        };
+        (player, output)
+    }
+
+    async fn world() -> &'static World {
+        let world = World::new(ServerDescription::default(), FlatChunkGenerator::new()).await;
+        Box::leak(Box::new(world))
+    }
+
+    async fn acknowledgement(
+        position: BlockPosition,
+        block: Block,
+        successful: bool,
+    ) -> Result<Vec<u8>> {
+        let ack =
+            AcknowledgePlayerDigging::new(position, block, DiggingStatus::Started, successful);
+        let mut buffer = Vec::new();
+        ack.send_packet(&mut buffer).await?;
+        Ok(buffer)
+    }
+
+    #[async_test]
+    async fn test_can_reach() -> Result<()> {
+        // The eyes are at the height of the center of the blocks at y = 64.
+        let (player, _) = player(world().await, 0.5, 64.5 - Player::EYE_HEIGHT, 0.5);
+        assert!(player.can_reach(&BlockPosition { x: 6, y: 64, z: 0 }).await);
+        assert!(
+            player
+                .can_reach(&BlockPosition { x: 0, y: 64, z: -6 })
+                .await
+        );
+        assert!(player.can_reach(&BlockPosition { x: 0, y: 70, z: 0 }).await);
+        assert!(!player.can_reach(&BlockPosition { x: 7, y: 64, z: 0 }).await);
+        assert!(!player.can_reach(&BlockPosition { x: 6, y: 64, z: 1 }).await);
+        assert!(!player.can_reach(&BlockPosition { x: 0, y: 71, z: 0 }).await);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_creative_dig() -> Result<()> {
+        let world = world().await;
+        let (player, output) = player(world, 0.5, 4., 0.5);
+        assert_eq!(player.info.game_mode(), GameMode::Creative);
+
+        // Blocks break as soon as digging starts, and the client is sent the air.
+        let position = BlockPosition { x: 0, y: 3, z: 0 };
+        player
+            .dig(PlayerDigging::StartedDigging(position.clone(), Face::Top))
+            .await?;
+        assert_eq!(world.map.get_block(0, 3, 0).await?, Block::Air);
+        let expected = acknowledgement(position, Block::Air, true).await?;
+        assert_eq!(*output.0.lock().unwrap(), expected);
+
+        // Blocks out of reach are refused and sent back.
+        output.0.lock().unwrap().clear();
+        let position = BlockPosition { x: 10, y: 3, z: 0 };
+        player
+            .dig(PlayerDigging::StartedDigging(position.clone(), Face::Top))
+            .await?;
+        assert_eq!(world.map.get_block(10, 3, 0).await?, Block::GrassBlock);
+        let expected = acknowledgement(position, Block::GrassBlock, false).await?;
+        assert_eq!(*output.0.lock().unwrap(), expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use futures::future::BoxFuture;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+type OnceTask = Box<dyn FnOnce() -> BoxFuture<'static, ()> + std::marker::Send>;
+type RepeatingTask = Box<dyn FnMut() -> BoxFuture<'static, ()> + std::marker::Send>;
+
+/// The id of a scheduled task, to cancel it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TaskId(u64);
+
+enum Task {
+    Once(OnceTask),
+    Repeating { period: u64, task: RepeatingTask },
+}
+
+struct Scheduled {
+    id: TaskId,
+    tick: u64,
+    task: Task,
+}
+
+/// Tasks run by the world at the end of a given tick, once or periodically.
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Vec<Scheduled>,
+    last_id: u64,
+    tick: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a task once, `delay` ticks after the current one.
+    pub fn run_later<F>(&mut self, delay: u64, task: F) -> TaskId
+    where
+        F: FnOnce() -> BoxFuture<'static, ()> + std::marker::Send + 'static,
+    {
+        self.schedule(delay, Task::Once(Box::new(task)))
+    }
+
+    /// Run a task every `period` ticks, starting `delay` ticks after the current one.
+    pub fn run_repeating<F>(&mut self, delay: u64, period: u64, task: F) -> TaskId
+    where
+        F: FnMut() -> BoxFuture<'static, ()> + std::marker::Send + 'static,
+    {
+        let task = Task::Repeating {
+            period: period.max(1),
+            task: Box::new(task),
+        };
+        self.schedule(delay, task)
+    }
+
+    fn schedule(&mut self, delay: u64, task: Task) -> TaskId {
+        self.last_id += 1;
+        let id = TaskId(self.last_id);
+        self.tasks.push(Scheduled {
+            id,
+            tick: self.tick + delay.max(1),
+            task,
+        });
+        id
+    }
+
+    /// Cancel a task, returns false if it was already run or cancelled.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        let count = self.tasks.len();
+        self.tasks.retain(|scheduled| scheduled.id != id);
+        self.tasks.len() != count
+    }
+
+    /// Move to the next tick and start the tasks due, in the order they were scheduled.
+    pub(crate) fn advance(&mut self) -> Vec<BoxFuture<'static, ()>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let mut due = Vec::new();
+        let mut index = 0;
+        while index < self.tasks.len() {
+            if self.tasks[index].tick > tick {
+                index += 1;
+                continue;
+            }
+            match &mut self.tasks[index].task {
+                Task::Repeating { period, task } => {
+                    due.push(task());
+                    self.tasks[index].tick = tick + *period;
+                    index += 1;
+                }
+                Task::Once(_) => {
+                    if let Task::Once(task) = self.tasks.remove(index).task {
+                        due.push(task());
+                    }
+                }
+            }
+        }
+        due
+    }
+}
+
+/// The durations of the last ticks, to measure the load of the server.
+#[derive(Debug, Default)]
+pub struct TickTimes {
+    durations: VecDeque<Duration>,
+    skipped: u64,
+}
+
+impl TickTimes {
+    /// The number of ticks averaged, the last five seconds.
+    const SAMPLES: usize = 100;
+
+    pub(crate) fn record(&mut self, duration: Duration) {
+        if self.durations.len() == Self::SAMPLES {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+    }
+
+    pub(crate) fn skip(&mut self, ticks: u64) {
+        self.skipped += ticks;
+    }
+
+    /// The average duration of the last ticks.
+    pub fn average(&self) -> Duration {
+        if self.durations.is_empty() {
+            return Duration::default();
+        }
+        self.durations.iter().sum::<Duration>() / self.durations.len() as u32
+    }
+
+    /// The longest of the last ticks.
+    pub fn max(&self) -> Duration {
+        self.durations.iter().max().cloned().unwrap_or_default()
+    }
+
+    /// The number of ticks skipped because the server couldn't keep up.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn counter(count: &Arc<AtomicU32>) -> impl FnMut() -> BoxFuture<'static, ()> {
+        let count = Arc::clone(count);
+        move || {
+            count.fetch_add(1, Ordering::Relaxed);
+            async {}.boxed()
+        }
+    }
+
+    fn advance(scheduler: &mut Scheduler, ticks: u64) {
+        for _ in 0..ticks {
+            for task in scheduler.advance() {
+                futures::executor::block_on(task);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scheduler() {
+        let mut scheduler = Scheduler::new();
+        let once = Arc::new(AtomicU32::new(0));
+        let repeating = Arc::new(AtomicU32::new(0));
+        let cancelled = Arc::new(AtomicU32::new(0));
+
+        scheduler.run_later(3, counter(&once));
+        scheduler.run_repeating(1, 2, counter(&repeating));
+        let id = scheduler.run_later(3, counter(&cancelled));
+        assert!(scheduler.cancel(id));
+        assert!(!scheduler.cancel(id));
+
+        advance(&mut scheduler, 2);
+        assert_eq!(once.load(Ordering::Relaxed), 0);
+        assert_eq!(repeating.load(Ordering::Relaxed), 1);
+        advance(&mut scheduler, 4);
+        assert_eq!(once.load(Ordering::Relaxed), 1);
+        assert_eq!(repeating.load(Ordering::Relaxed), 3);
+        assert_eq!(cancelled.load(Ordering::Relaxed), 0);
+        assert_eq!(scheduler.tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_tick_times() {
+        let mut times = TickTimes::default();
+        assert_eq!(times.average(), Duration::default());
+        for millis in 0..=TickTimes::SAMPLES as u64 {
+            times.record(Duration::from_millis(millis));
+        }
+        assert_eq!(times.average(), Duration::from_micros(50_500));
+        assert_eq!(times.max(), Duration::from_millis(100));
+    }
+}
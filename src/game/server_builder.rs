@@ -1,5 +1,6 @@
 use super::map::generator::ChunkGenerator;
 use super::world::World;
+use crate::packets::play::GameMode;
 use crate::types::{ServerDescription, Version};
 use anyhow::Result;
 
@@ -7,6 +8,10 @@ use anyhow::Result;
 #[derive(Clone)]
 pub struct ServerBuilder {
     description: ServerDescription,
+    pregeneration_radius: Option<i32>,
+    game_mode: GameMode,
+    spawn: Option<(i32, i32)>,
+    spawn_protection: u32,
 }
 
 impl ServerBuilder {
@@ -17,7 +22,10 @@ impl ServerBuilder {
 
     /// create a ServerBuilder with your ServerDescription
     pub fn from_description(description: ServerDescription) -> Self {
-        Self { description }
+        Self {
+            description,
+            ..Default::default()
+        }
     }
 
     /// Set the version of your server in place
@@ -77,12 +85,70 @@ impl ServerBuilder {
         Ok(self)
     }
 
+    /// Set the radius (in chunks) of the area generated around the spawn before the server
+    /// starts in place
+    pub fn set_pregeneration_radius(&mut self, radius: i32) {
+        self.pregeneration_radius = Some(radius);
+    }
+
+    /// Set the radius (in chunks) of the area generated around the spawn before the server
+    /// starts
+    pub fn with_pregeneration_radius(mut self, radius: i32) -> Self {
+        self.set_pregeneration_radius(radius);
+        self
+    }
+
+    /// Set the game mode of the players joining the server in place
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+    }
+
+    /// Set the game mode of the players joining the server
+    pub fn with_game_mode(mut self, game_mode: GameMode) -> Self {
+        self.set_game_mode(game_mode);
+        self
+    }
+
+    /// Set the column around which the spawn of the world is looked for in place
+    pub fn set_spawn(&mut self, x: i32, z: i32) {
+        self.spawn = Some((x, z));
+    }
+
+    /// Set the column around which the spawn of the world is looked for
+    pub fn with_spawn(mut self, x: i32, z: i32) -> Self {
+        self.set_spawn(x, z);
+        self
+    }
+
+    /// Set the distance (in blocks) around the spawn under which players can't edit blocks in
+    /// place
+    pub fn set_spawn_protection(&mut self, radius: u32) {
+        self.spawn_protection = radius;
+    }
+
+    /// Set the distance (in blocks) around the spawn under which players can't edit blocks
+    pub fn with_spawn_protection(mut self, radius: u32) -> Self {
+        self.set_spawn_protection(radius);
+        self
+    }
+
     /// Build a World from the provided generator
     pub async fn build<G>(self, generator: G) -> World
     where
         G: ChunkGenerator + Sync + Send + 'static,
     {
-        World::new(self.description, generator).await
+        let mut world = World::new(self.description, generator).await;
+        if let Some((x, z)) = self.spawn {
+            world = world.with_spawn(x, z).await;
+        }
+        world.set_game_mode(self.game_mode);
+        world.set_spawn_protection(self.spawn_protection);
+        if let Some(radius) = self.pregeneration_radius {
+            let spawn = world.spawn().await;
+            let center = (spawn.x.div_euclid(16), spawn.z.div_euclid(16));
+            world.map.pregenerate(center, radius).await;
+        }
+        world
     }
 
     /// Build a World from the provided generator, put it in the heap,
@@ -99,6 +165,10 @@ impl Default for ServerBuilder {
     fn default() -> Self {
         Self {
             description: ServerDescription::default(),
+            pregeneration_radius: None,
+            game_mode: GameMode::Creative,
+            spawn: None,
+            spawn_protection: 0,
         }
     }
 }
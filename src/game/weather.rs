@@ -0,0 +1,117 @@
+use crate::packets::play::ChangeGameState;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Thunder,
+}
+
+/// The weather of a world, its rain and thunder levels move towards the ones of the weather
+/// over a few seconds.
+#[derive(Debug, Default)]
+pub struct WeatherState {
+    weather: Weather,
+    rain_level: f32,
+    thunder_level: f32,
+}
+
+impl WeatherState {
+    /// The change of level per tick, as in vanilla.
+    const TRANSITION: f32 = 0.01;
+
+    pub fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    pub fn rain_level(&self) -> f32 {
+        self.rain_level
+    }
+
+    pub fn thunder_level(&self) -> f32 {
+        self.thunder_level
+    }
+
+    /// Change the weather, returns the packet starting or stopping the rain if needed.
+    pub(crate) fn set(&mut self, weather: Weather) -> Option<ChangeGameState> {
+        let was_raining = self.weather != Weather::Clear;
+        self.weather = weather;
+        match (was_raining, weather != Weather::Clear) {
+            (false, true) => Some(ChangeGameState::begin_raining()),
+            (true, false) => Some(ChangeGameState::end_raining()),
+            _ => None,
+        }
+    }
+
+    /// Move the levels towards the ones of the weather, returns the packets of the levels
+    /// which changed.
+    pub(crate) fn tick(&mut self) -> Vec<ChangeGameState> {
+        let (rain, thunder) = match self.weather {
+            Weather::Clear => (0., 0.),
+            Weather::Rain => (1., 0.),
+            Weather::Thunder => (1., 1.),
+        };
+        let mut changes = Vec::new();
+        if Self::approach(&mut self.rain_level, rain) {
+            changes.push(ChangeGameState::rain_level(self.rain_level));
+        }
+        if Self::approach(&mut self.thunder_level, thunder) {
+            changes.push(ChangeGameState::thunder_level(self.thunder_level));
+        }
+        changes
+    }
+
+    fn approach(level: &mut f32, target: f32) -> bool {
+        if *level == target {
+            return false;
+        }
+        *level = if *level < target {
+            (*level + Self::TRANSITION).min(target)
+        } else {
+            (*level - Self::TRANSITION).max(target)
+        };
+        true
+    }
+
+    /// The packets bringing a client which just joined to the current weather.
+    pub(crate) fn packets(&self) -> Vec<ChangeGameState> {
+        if self.weather == Weather::Clear && self.rain_level == 0. {
+            return Vec::new();
+        }
+        vec![
+            ChangeGameState::begin_raining(),
+            ChangeGameState::rain_level(self.rain_level),
+            ChangeGameState::thunder_level(self.thunder_level),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Reason;
+
+    #[test]
+    fn test_weather_transition() {
+        let mut state = WeatherState::default();
+        assert!(state.tick().is_empty());
+
+        let begin = state.set(Weather::Thunder).unwrap();
+        assert_eq!(begin.reason(), Reason::BeginRaining as u8);
+        assert!(state.set(Weather::Thunder).is_none());
+        assert_eq!(state.tick().len(), 2);
+        while !state.tick().is_empty() {}
+        assert_eq!(state.rain_level(), 1.);
+        assert_eq!(state.thunder_level(), 1.);
+
+        assert!(state.set(Weather::Rain).is_none());
+        let changes = state.tick();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].reason(), Reason::ThunderLevel as u8);
+
+        let end = state.set(Weather::Clear).unwrap();
+        assert_eq!(end.reason(), Reason::EndRaining as u8);
+        assert_eq!(state.packets().len(), 3);
+    }
+}
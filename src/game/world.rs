@@ -1,40 +1,374 @@
-use crate::game::map::{generator::ChunkGenerator, Map};
+use crate::game::border::Border;
+use crate::game::entity::{Entity, ItemEntity};
+use crate::game::map::{generator::ChunkGenerator, ChunkEdit, Map};
 use crate::game::player::Player;
+use crate::game::scheduler::{Scheduler, TickTimes};
+use crate::game::weather::{Weather, WeatherState};
 use crate::packets::play::chat_message::{OutChatMessage, Position};
-use crate::packets::play::{Action, DestroyEntity, JoinGame, KeepAlive, PlayerInfo, SpawnPlayer};
+use crate::packets::play::entity_position::{EntityTeleport, OutPosition};
+use crate::packets::play::{
+    Action, Block, BlockChange, BlockEntity, ChangeGameState, ChestItems, GameMode, ItemStack,
+    JoinGame, KeepAlive, MultiBlockChange, PlayerInfo, Recipes, SpawnPosition, TimeUpdate,
+    UpdateBlockEntity, UpdateLight, WorldBorder,
+};
 use crate::packets::Packet;
 use crate::types::chat::Chat;
-use crate::types::{self, ServerDescription, TAsyncRead, TAsyncStream, TAsyncWrite};
+use crate::types::{self, BlockPosition, ServerDescription, TAsyncRead, TAsyncStream, TAsyncWrite};
 use anyhow::Result;
 use futures_timer::Delay;
-use piper::{Arc, Lock};
+use piper::{Arc, Lock, LockGuard};
+use rand::Rng;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Above this number of changed blocks in a chunk, the whole chunk is sent again instead of a
+/// Multi Block Change packet.
+const FULL_CHUNK_EDIT: usize = 1024;
 
 pub struct World {
     players: Lock<HashMap<types::VarInt, Arc<Player>>>,
     server_description: ServerDescription,
+    game_mode: GameMode,
+    entity_ids: AtomicI32,
+    entities: Lock<HashMap<types::VarInt, Box<dyn Entity>>>,
+    ticks: AtomicU64,
+    /// The time of day in ticks, counting the previous days.
+    time: AtomicU64,
+    daylight_cycle: AtomicBool,
+    weather: Lock<WeatherState>,
+    respawn_screen: AtomicBool,
+    border: Lock<Border>,
+    spawn: Lock<BlockPosition>,
+    spawn_protection: u32,
+    tick_times: Lock<TickTimes>,
+    pub scheduler: Lock<Scheduler>,
     pub map: Map,
 }
 
 impl World {
+    /// The duration of a game tick, the world updates 20 times per second.
+    pub const TICK: Duration = Duration::from_millis(50);
+    /// The clients are sent the time once per second, they advance it themselves in between.
+    const TIME_UPDATE_PERIOD: u64 = 20;
+    /// Above this delay, the missed ticks are skipped instead of being run as fast as possible.
+    const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+
+    /// A world whose spawn is looked for around the column (0, 0), see `Map::safe_spawn`.
     pub async fn new(
         server_description: ServerDescription,
         generator: impl ChunkGenerator + Sync + std::marker::Send + 'static,
     ) -> Self {
+        let map = Map::new(generator).await;
+        let spawn = map.safe_spawn(0, 0).await;
         Self {
             players: Lock::new(HashMap::new()),
             server_description,
-            map: Map::new(generator).await,
+            game_mode: GameMode::Creative,
+            entity_ids: AtomicI32::new(0),
+            entities: Lock::new(HashMap::new()),
+            ticks: AtomicU64::new(0),
+            time: AtomicU64::new(0),
+            daylight_cycle: AtomicBool::new(true),
+            weather: Lock::new(WeatherState::default()),
+            respawn_screen: AtomicBool::new(false),
+            border: Lock::new(Border::default()),
+            spawn: Lock::new(spawn),
+            spawn_protection: 0,
+            tick_times: Lock::new(TickTimes::default()),
+            scheduler: Lock::new(Scheduler::new()),
+            map,
+        }
+    }
+
+    /// Look for the spawn around the `x` and `z` column instead, see `Map::safe_spawn`.
+    pub async fn with_spawn(self, x: i32, z: i32) -> Self {
+        *self.spawn.lock().await = self.map.safe_spawn(x, z).await;
+        self
+    }
+
+    /// The game mode of the players joining the world.
+    pub fn game_mode(&self) -> GameMode {
+        self.game_mode
+    }
+
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+    }
+
+    /// The position where the players join the world.
+    pub async fn spawn(&self) -> BlockPosition {
+        self.spawn.lock().await.clone()
+    }
+
+    /// Move the spawn of the world, see `Map::safe_spawn` to find a safe one.
+    pub async fn set_spawn(&self, spawn: BlockPosition) -> Result<()> {
+        *self.spawn.lock().await = spawn.clone();
+        self.broadcast_packet(&SpawnPosition(spawn)).await
+    }
+
+    /// The distance in blocks around the spawn under which players can't edit blocks, 0 if
+    /// disabled.
+    pub fn spawn_protection(&self) -> u32 {
+        self.spawn_protection
+    }
+
+    pub fn set_spawn_protection(&mut self, radius: u32) {
+        self.spawn_protection = radius;
+    }
+
+    /// Whether the block is inside the spawn protection.
+    pub async fn is_protected(&self, position: &BlockPosition) -> bool {
+        if self.spawn_protection == 0 {
+            return false;
         }
+        let spawn = self.spawn.lock().await;
+        let radius = self.spawn_protection as i32;
+        (position.x - spawn.x).abs() <= radius && (position.z - spawn.z).abs() <= radius
+    }
+
+    /// Allocate a new entity id, unique in the world.
+    pub fn next_entity_id(&self) -> types::VarInt {
+        types::VarInt(self.entity_ids.fetch_add(1, Ordering::Relaxed))
     }
 
+    /// Run the game loop and send keep alives to the players every `heartbeat`.
     pub async fn run(&self, heartbeat: Duration) {
+        let keep_alive = async {
+            loop {
+                Delay::new(heartbeat).await;
+                let keep_alive_packet = KeepAlive::new();
+                let _ = self.broadcast_packet(&keep_alive_packet).await;
+            }
+        };
+        futures::future::join(keep_alive, self.run_ticks()).await;
+    }
+
+    /// Tick the world 20 times per second. Late ticks are run right away to catch up, unless
+    /// the server is too far behind.
+    async fn run_ticks(&self) {
+        let mut next = Instant::now() + Self::TICK;
         loop {
-            Delay::new(heartbeat).await;
-            let keep_alive_packet = KeepAlive::new();
-            let _ = self.broadcast_packet(&keep_alive_packet).await;
+            let now = Instant::now();
+            if now < next {
+                Delay::new(next - now).await;
+            } else if now - next > Self::MAX_CATCH_UP {
+                let late = (now - next).as_millis() / Self::TICK.as_millis();
+                self.tick_times.lock().await.skip(late as u64);
+                next = now;
+            }
+
+            let started = Instant::now();
+            if let Err(error) = self.tick().await {
+                eprintln!("tick {} failed: {:#}", self.ticks(), error);
+            }
+            self.tick_times.lock().await.record(started.elapsed());
+            next += Self::TICK;
+        }
+    }
+
+    /// Run a single tick: advance the time and the weather, send the light of the relit chunks,
+    /// update the entities, then run the scheduled tasks. Every step runs even if a previous one failed, the first error is
+    /// returned.
+    pub async fn tick(&self) -> Result<()> {
+        let ticks = self.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.daylight_cycle() {
+            self.time.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut result = Ok(());
+        if ticks % Self::TIME_UPDATE_PERIOD == 0 {
+            result = result.and(self.broadcast_packet(&self.time_update()).await);
+        }
+        let changes = self.weather.lock().await.tick();
+        for change in changes {
+            result = result.and(self.broadcast_packet(&change).await);
+        }
+        result = result.and(self.send_relit_chunks().await);
+        result = result.and(self.tick_entities().await);
+        let tasks = self.scheduler.lock().await.advance();
+        for task in tasks {
+            task.await;
+        }
+        result
+    }
+
+    /// The number of ticks since the world started.
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// The time of day in ticks, counting the previous days: 0 is sunrise, 6000 noon and 18000
+    /// midnight.
+    pub fn time(&self) -> u64 {
+        self.time.load(Ordering::Relaxed)
+    }
+
+    /// Set the time of day and send it to the players.
+    pub async fn set_time(&self, time: u64) -> Result<()> {
+        self.time.store(time, Ordering::Relaxed);
+        self.broadcast_packet(&self.time_update()).await
+    }
+
+    /// Whether the time of day advances.
+    pub fn daylight_cycle(&self) -> bool {
+        self.daylight_cycle.load(Ordering::Relaxed)
+    }
+
+    /// Stop or restart the daylight cycle, for the server and the players.
+    pub async fn set_daylight_cycle(&self, enabled: bool) -> Result<()> {
+        self.daylight_cycle.store(enabled, Ordering::Relaxed);
+        self.broadcast_packet(&self.time_update()).await
+    }
+
+    /// The weather and the current rain and thunder levels.
+    pub async fn weather(&self) -> LockGuard<WeatherState> {
+        self.weather.lock().await
+    }
+
+    /// Change the weather, the rain and thunder levels then change progressively.
+    pub async fn set_weather(&self, weather: Weather) -> Result<()> {
+        let change = self.weather.lock().await.set(weather);
+        match change {
+            Some(change) => self.broadcast_packet(&change).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Whether dying shows the respawn screen, instead of respawning the player immediately.
+    pub fn respawn_screen(&self) -> bool {
+        self.respawn_screen.load(Ordering::Relaxed)
+    }
+
+    pub async fn set_respawn_screen(&self, enabled: bool) -> Result<()> {
+        self.respawn_screen.store(enabled, Ordering::Relaxed);
+        self.broadcast_packet(&ChangeGameState::respawn_screen(enabled))
+            .await
+    }
+
+    pub async fn border(&self) -> LockGuard<Border> {
+        self.border.lock().await
+    }
+
+    /// Change the world border and send the update returned by `update` to the players.
+    pub async fn update_border<F>(&self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut Border) -> WorldBorder,
+    {
+        let packet = update(&mut *self.border.lock().await);
+        self.broadcast_packet(&packet).await
+    }
+
+    fn time_update(&self) -> TimeUpdate {
+        TimeUpdate::new(self.ticks(), self.time(), self.daylight_cycle())
+    }
+
+    /// The durations of the last ticks.
+    pub async fn tick_times(&self) -> LockGuard<TickTimes> {
+        self.tick_times.lock().await
+    }
+
+    /// Add an entity to the world, spawned for the players close enough.
+    pub async fn spawn_entity(&self, entity: impl Entity + 'static) -> Result<()> {
+        for player in self.players().await {
+            if player
+                .can_see(entity.position(), entity.tracking_range())
+                .await
+            {
+                player.track(&entity, true).await?;
+            }
+        }
+        self.entities
+            .lock()
+            .await
+            .insert(entity.id(), Box::new(entity));
+        Ok(())
+    }
+
+    /// Remove an entity from the world and destroy it for the players who see it.
+    pub async fn remove_entity(&self, id: types::VarInt) -> Result<()> {
+        if self.entities.lock().await.remove(&id).is_none() {
+            return Ok(());
+        }
+        for player in self.players().await {
+            player.untrack(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a packet about an entity to the players who see it, e.g. an Entity Animation. A
+    /// failed send doesn't prevent the other players from being sent the packet, the first
+    /// error is returned.
+    pub async fn send_to_trackers(
+        &self,
+        id: types::VarInt,
+        packet: &(impl Packet + Sync),
+    ) -> Result<()> {
+        let mut result = Ok(());
+        for player in self.players().await {
+            if player.is_tracking(id).await {
+                result = result.and(player.send_packet(packet).await);
+            }
+        }
+        result
+    }
+
+    /// Update the entities, send their moves and remove the expired ones, then spawn or
+    /// destroy the entities and players entering or leaving the range of each player. A player
+    /// failing to be sent an update doesn't prevent the others from being updated.
+    async fn tick_entities(&self) -> Result<()> {
+        let mut expired = Vec::new();
+        let mut moves = Vec::new();
+        self.entities.lock().await.retain(|id, entity| {
+            let before = entity.position().clone();
+            let alive = entity.tick();
+            let after = entity.position();
+            if !alive {
+                expired.push(*id);
+            } else if (before.x, before.y, before.z) != (after.x, after.y, after.z) {
+                let delta = before.delta(after);
+                moves.push((*id, delta, after.clone()));
+            }
+            alive
+        });
+
+        let mut result = Ok(());
+        let players = self.players().await;
+        for id in expired {
+            for player in &players {
+                result = result.and(player.untrack(id).await);
+            }
+        }
+        for (id, delta, position) in moves {
+            if delta.teleport {
+                let teleport = EntityTeleport::new(id, &position, false);
+                result = result.and(self.send_to_trackers(id, &teleport).await);
+            } else {
+                let relative = OutPosition::new(id, &delta, false);
+                result = result.and(self.send_to_trackers(id, &relative).await);
+            }
+        }
+
+        for player in &players {
+            for other in players.iter().filter(|other| other.id() != player.id()) {
+                let position = other.position().await.clone();
+                let visible = player.can_see(&position, Player::TRACKING_RANGE).await;
+                result = result.and(player.track_player(other, visible).await);
+            }
+        }
+        let entities = self.entities.lock().await;
+        for player in &players {
+            for entity in entities.values() {
+                let visible = player
+                    .can_see(entity.position(), entity.tracking_range())
+                    .await;
+                result = result.and(player.track(&**entity, visible).await);
+            }
         }
+        result
+    }
+
+    async fn players(&self) -> Vec<Arc<Player>> {
+        self.players.lock().await.values().cloned().collect()
     }
 
     pub async fn broadcast_packet(&self, packet: &(impl Packet + Sync)) -> Result<()> {
@@ -62,6 +396,251 @@ impl World {
         Ok(())
     }
 
+    /// Change a block of the map and send the change and the new light of its chunk to the
+    /// players who loaded it.
+    pub async fn set_block(&self, position: BlockPosition, block: Block) -> Result<()> {
+        self.set_blocks(vec![((position.x, position.y, position.z), block)])
+            .await
+    }
+
+    /// Change multiple blocks of the map and send the changes to the players who loaded them.
+    pub async fn set_blocks(
+        &self,
+        blocks: impl IntoIterator<Item = ((i32, u16, i32), Block)>,
+    ) -> Result<()> {
+        let edits = self.map.set_blocks(blocks).await;
+        self.send_edits(&edits).await
+    }
+
+    /// Set all the blocks between two corners (inclusive) and send the changes to the players
+    /// who loaded them.
+    pub async fn fill(
+        &self,
+        from: (i32, u16, i32),
+        to: (i32, u16, i32),
+        block: Block,
+    ) -> Result<()> {
+        let edits = self.map.fill(from, to, block).await;
+        self.send_edits(&edits).await
+    }
+
+    /// Replace the blocks matching `predicate` between two corners (inclusive) and send the
+    /// changes to the players who loaded them.
+    pub async fn replace(
+        &self,
+        from: (i32, u16, i32),
+        to: (i32, u16, i32),
+        block: Block,
+        predicate: impl Fn(Block) -> bool,
+    ) -> Result<()> {
+        let edits = self.map.replace(from, to, block, predicate).await;
+        self.send_edits(&edits).await
+    }
+
+    /// Spawn an item entity thrown from a block, for the players who loaded its chunk.
+    pub async fn drop_item(&self, position: &BlockPosition, item: ItemStack) -> Result<()> {
+        let (center, velocity) = {
+            let mut random = rand::thread_rng();
+            let center = (
+                position.x as f64 + random.gen_range(0.25, 0.75),
+                position.y as f64 + random.gen_range(0.25, 0.75),
+                position.z as f64 + random.gen_range(0.25, 0.75),
+            );
+            let velocity = (
+                random.gen_range(-0.1, 0.1),
+                0.2,
+                random.gen_range(-0.1, 0.1),
+            );
+            (center, velocity)
+        };
+        let entity = ItemEntity::new(self.next_entity_id(), center, velocity, item);
+        self.spawn_entity(entity).await
+    }
+
+    /// A copy of the block entity at this position, sharing the items of chests.
+    pub async fn block_entity(&self, position: &BlockPosition) -> Option<BlockEntity> {
+        let chunk = self
+            .map
+            .chunk(position.x.div_euclid(16), position.z.div_euclid(16))
+            .await
+            .ok()?;
+        chunk
+            .block_entity(
+                position.x.rem_euclid(16) as u8,
+                position.y,
+                position.z.rem_euclid(16) as u8,
+            )
+            .cloned()
+    }
+
+    /// Modify the block entity at this position, the players who loaded it receive its new data
+    /// if `update` returns true.
+    pub async fn update_block_entity(
+        &self,
+        position: &BlockPosition,
+        update: impl FnOnce(&mut BlockEntity) -> bool,
+    ) -> Result<()> {
+        let (c_x, c_z) = (position.x.div_euclid(16), position.z.div_euclid(16));
+        let packet = {
+            let mut chunk = self.map.chunk(c_x, c_z).await?;
+            let entity = chunk.block_entity_mut(
+                position.x.rem_euclid(16) as u8,
+                position.y,
+                position.z.rem_euclid(16) as u8,
+            );
+            entity.and_then(|entity| {
+                if update(entity) {
+                    UpdateBlockEntity::new(position.clone(), entity)
+                } else {
+                    None
+                }
+            })
+        };
+        if let Some(packet) = packet {
+            for viewer in self.viewers(c_x, c_z).await {
+                viewer.send_packet(&packet).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The items of the chest at this position, shared by the players who open it.
+    pub async fn chest(&self, position: &BlockPosition) -> Option<ChestItems> {
+        match self.block_entity(position).await? {
+            BlockEntity::Chest { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Empty a chest about to be broken: its windows are closed and the items dropped.
+    pub async fn remove_chest(&self, position: &BlockPosition) -> Result<()> {
+        let items = match self.chest(position).await {
+            Some(items) => items,
+            None => return Ok(()),
+        };
+        let players = self
+            .players
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for player in players {
+            if player.is_viewing(position).await {
+                player.close_window().await?;
+            }
+        }
+
+        for item in items.take_all() {
+            self.drop_item(position, item).await?;
+        }
+        Ok(())
+    }
+
+    /// Send the content of a container again to the players viewing it, after a change.
+    pub async fn refresh_container(&self, position: &BlockPosition, except: &Player) -> Result<()> {
+        let players = self
+            .players
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for player in players.iter().filter(|player| &***player != except) {
+            if player.is_viewing(position).await {
+                player.send_window().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send the new light of the chunks relit because of their neighbours to the players who
+    /// loaded them.
+    async fn send_relit_chunks(&self) -> Result<()> {
+        let mut result = Ok(());
+        for (x, z) in self.map.take_relit().await {
+            let viewers = self.viewers(x, z).await;
+            if viewers.is_empty() {
+                continue;
+            }
+            let light = match self.map.chunk(x, z).await {
+                Ok(chunk) => chunk.light().clone(),
+                Err(error) => {
+                    result = result.and(Err(error));
+                    continue;
+                }
+            };
+            let packet = UpdateLight::with_light(x, z, &light);
+            for viewer in viewers {
+                result = result.and(viewer.send_packet(&packet).await);
+            }
+        }
+        result
+    }
+
+    /// Players who loaded the given chunk.
+    async fn viewers(&self, x: i32, z: i32) -> Vec<Arc<Player>> {
+        let players = self
+            .players
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut viewers = Vec::new();
+        for player in players {
+            if player.has_loaded_chunk(x, z).await {
+                viewers.push(player);
+            }
+        }
+        viewers
+    }
+
+    /// Send the changed blocks, grouped by chunk, and the new light of the chunks: a single
+    /// Block Change, a Multi Block Change or the whole chunk for big edits.
+    async fn send_edits(&self, edits: &[ChunkEdit]) -> Result<()> {
+        for edit in edits {
+            let viewers = self.viewers(edit.x, edit.z).await;
+            if viewers.is_empty() {
+                continue;
+            }
+
+            // Copy what is sent so the chunk isn't locked while sending.
+            let (light, full) = {
+                let chunk = self.map.chunk(edit.x, edit.z).await?;
+                let full = if edit.blocks.len() > FULL_CHUNK_EDIT {
+                    Some(chunk.clone(edit.x, edit.z))
+                } else {
+                    None
+                };
+                (chunk.light().clone(), full)
+            };
+            let light = UpdateLight::with_light(edit.x, edit.z, &light);
+            for viewer in viewers {
+                match (edit.blocks.as_slice(), &full) {
+                    (_, Some(chunk)) => viewer.send_packet(chunk).await?,
+                    ([(x, y, z, block)], None) => {
+                        let position = BlockPosition::new(
+                            edit.x * 16 + *x as i32,
+                            *y,
+                            edit.z * 16 + *z as i32,
+                        );
+                        viewer
+                            .send_packet(&BlockChange::new(position, *block))
+                            .await?
+                    }
+                    (blocks, None) => {
+                        viewer
+                            .send_packet(&MultiBlockChange::new(edit.x, edit.z, blocks))
+                            .await?
+                    }
+                }
+                viewer.send_packet(&light).await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn handle_connection_stream<S>(&'static self, stream: S) -> Result<()>
     where
         for<'a> &'a S: TAsyncRead + TAsyncWrite,
@@ -99,8 +678,25 @@ impl World {
 
     // TODO: Find better name.
     async fn _add_player(&self, player: Arc<Player>) -> Result<()> {
-        let join_game = JoinGame::default();
+        let join_game = JoinGame {
+            id: *player.id(),
+            game_mode: self.game_mode,
+            hash_seed: JoinGame::hash_seed(self.map.seed()),
+            enable_respawn_screen: self.respawn_screen(),
+            ..Default::default()
+        };
         player.send_packet(&join_game).await?;
+        player.send_packet(&Recipes::all()).await?;
+        player
+            .send_packet(&SpawnPosition(self.spawn().await))
+            .await?;
+        player.send_packet(&self.time_update()).await?;
+        let border = self.border.lock().await.packet();
+        player.send_packet(&border).await?;
+        let weather = self.weather.lock().await.packets();
+        for packet in weather {
+            player.send_packet(&packet).await?;
+        }
 
         // Send all players info to the new player.
         {
@@ -115,22 +711,7 @@ impl World {
         self.broadcast_packet_except(&new_player_info, &player)
             .await?;
 
-        // Spawn the new player in everybody else game.
-        let spawn_player = SpawnPlayer::new(&player).await;
-        self.broadcast_packet_except(&spawn_player, &player).await?;
-
-        // Spawn other players in the new player game.
-        for other in self
-            .players
-            .lock()
-            .await
-            .values_mut()
-            .filter(|other| ***other != *player)
-        {
-            let spawn_other = SpawnPlayer::new(&other).await;
-            player.send_packet(&spawn_other).await?;
-        }
-
+        // The players close to each other are spawned on the next tick.
         let announcement = OutChatMessage::new(
             Chat::player_joined(&player.info().name()),
             Position::SystemMessage,
@@ -146,8 +727,9 @@ impl World {
             return Ok(());
         }
 
-        let destroy = DestroyEntity::single(id);
-        self.broadcast_packet(&destroy).await?;
+        for other in self.players().await {
+            other.untrack(id).await?;
+        }
 
         let info = PlayerInfo::new(Action::Remove, vec![player.info()]);
         self.broadcast_packet(&info).await?;
@@ -0,0 +1,292 @@
+use crate::{impl_send, impl_size};
+
+/// The biomes of the game, with their network ids.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[repr(i32)]
+pub enum Biome {
+    Ocean = 0,
+    #[default]
+    Plains = 1,
+    Desert = 2,
+    Mountains = 3,
+    Forest = 4,
+    Taiga = 5,
+    Swamp = 6,
+    River = 7,
+    Nether = 8,
+    TheEnd = 9,
+    FrozenOcean = 10,
+    FrozenRiver = 11,
+    SnowyTundra = 12,
+    SnowyMountains = 13,
+    MushroomFields = 14,
+    MushroomFieldShore = 15,
+    Beach = 16,
+    DesertHills = 17,
+    WoodedHills = 18,
+    TaigaHills = 19,
+    MountainEdge = 20,
+    Jungle = 21,
+    JungleHills = 22,
+    JungleEdge = 23,
+    DeepOcean = 24,
+    StoneShore = 25,
+    SnowyBeach = 26,
+    BirchForest = 27,
+    BirchForestHills = 28,
+    DarkForest = 29,
+    SnowyTaiga = 30,
+    SnowyTaigaHills = 31,
+    GiantTreeTaiga = 32,
+    GiantTreeTaigaHills = 33,
+    WoodedMountains = 34,
+    Savanna = 35,
+    SavannaPlateau = 36,
+    Badlands = 37,
+    WoodedBadlandsPlateau = 38,
+    BadlandsPlateau = 39,
+    SmallEndIslands = 40,
+    EndMidlands = 41,
+    EndHighlands = 42,
+    EndBarrens = 43,
+    WarmOcean = 44,
+    LukewarmOcean = 45,
+    ColdOcean = 46,
+    DeepWarmOcean = 47,
+    DeepLukewarmOcean = 48,
+    DeepColdOcean = 49,
+    DeepFrozenOcean = 50,
+    Void = 127,
+    SunflowerPlains = 129,
+    DesertLakes = 130,
+    GravellyMountains = 131,
+    FlowerForest = 132,
+    TaigaMountains = 133,
+    SwampHills = 134,
+    IceSpikes = 140,
+    ModifiedJungle = 149,
+    ModifiedJungleEdge = 151,
+    TallBirchForest = 155,
+    TallBirchHills = 156,
+    DarkForestHills = 157,
+    SnowyTaigaMountains = 158,
+    GiantSpruceTaiga = 160,
+    GiantSpruceTaigaHills = 161,
+    ModifiedGravellyMountains = 162,
+    ShatteredSavanna = 163,
+    ShatteredSavannaPlateau = 164,
+    ErodedBadlands = 165,
+    ModifiedWoodedBadlandsPlateau = 166,
+    ModifiedBadlandsPlateau = 167,
+    BambooJungle = 168,
+    BambooJungleHills = 169,
+}
+impl_size!(Biome, 4);
+impl_send!(Biome as i32);
+
+impl Biome {
+    pub const ALL: [Biome; 75] = {
+        use Biome::*;
+        [
+            Ocean,
+            Plains,
+            Desert,
+            Mountains,
+            Forest,
+            Taiga,
+            Swamp,
+            River,
+            Nether,
+            TheEnd,
+            FrozenOcean,
+            FrozenRiver,
+            SnowyTundra,
+            SnowyMountains,
+            MushroomFields,
+            MushroomFieldShore,
+            Beach,
+            DesertHills,
+            WoodedHills,
+            TaigaHills,
+            MountainEdge,
+            Jungle,
+            JungleHills,
+            JungleEdge,
+            DeepOcean,
+            StoneShore,
+            SnowyBeach,
+            BirchForest,
+            BirchForestHills,
+            DarkForest,
+            SnowyTaiga,
+            SnowyTaigaHills,
+            GiantTreeTaiga,
+            GiantTreeTaigaHills,
+            WoodedMountains,
+            Savanna,
+            SavannaPlateau,
+            Badlands,
+            WoodedBadlandsPlateau,
+            BadlandsPlateau,
+            SmallEndIslands,
+            EndMidlands,
+            EndHighlands,
+            EndBarrens,
+            WarmOcean,
+            LukewarmOcean,
+            ColdOcean,
+            DeepWarmOcean,
+            DeepLukewarmOcean,
+            DeepColdOcean,
+            DeepFrozenOcean,
+            Void,
+            SunflowerPlains,
+            DesertLakes,
+            GravellyMountains,
+            FlowerForest,
+            TaigaMountains,
+            SwampHills,
+            IceSpikes,
+            ModifiedJungle,
+            ModifiedJungleEdge,
+            TallBirchForest,
+            TallBirchHills,
+            DarkForestHills,
+            SnowyTaigaMountains,
+            GiantSpruceTaiga,
+            GiantSpruceTaigaHills,
+            ModifiedGravellyMountains,
+            ShatteredSavanna,
+            ShatteredSavannaPlateau,
+            ErodedBadlands,
+            ModifiedWoodedBadlandsPlateau,
+            ModifiedBadlandsPlateau,
+            BambooJungle,
+            BambooJungleHills,
+        ]
+    };
+
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|biome| biome.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        use Biome::*;
+        match self {
+            Ocean => "minecraft:ocean",
+            Plains => "minecraft:plains",
+            Desert => "minecraft:desert",
+            Mountains => "minecraft:mountains",
+            Forest => "minecraft:forest",
+            Taiga => "minecraft:taiga",
+            Swamp => "minecraft:swamp",
+            River => "minecraft:river",
+            Nether => "minecraft:nether",
+            TheEnd => "minecraft:the_end",
+            FrozenOcean => "minecraft:frozen_ocean",
+            FrozenRiver => "minecraft:frozen_river",
+            SnowyTundra => "minecraft:snowy_tundra",
+            SnowyMountains => "minecraft:snowy_mountains",
+            MushroomFields => "minecraft:mushroom_fields",
+            MushroomFieldShore => "minecraft:mushroom_field_shore",
+            Beach => "minecraft:beach",
+            DesertHills => "minecraft:desert_hills",
+            WoodedHills => "minecraft:wooded_hills",
+            TaigaHills => "minecraft:taiga_hills",
+            MountainEdge => "minecraft:mountain_edge",
+            Jungle => "minecraft:jungle",
+            JungleHills => "minecraft:jungle_hills",
+            JungleEdge => "minecraft:jungle_edge",
+            DeepOcean => "minecraft:deep_ocean",
+            StoneShore => "minecraft:stone_shore",
+            SnowyBeach => "minecraft:snowy_beach",
+            BirchForest => "minecraft:birch_forest",
+            BirchForestHills => "minecraft:birch_forest_hills",
+            DarkForest => "minecraft:dark_forest",
+            SnowyTaiga => "minecraft:snowy_taiga",
+            SnowyTaigaHills => "minecraft:snowy_taiga_hills",
+            GiantTreeTaiga => "minecraft:giant_tree_taiga",
+            GiantTreeTaigaHills => "minecraft:giant_tree_taiga_hills",
+            WoodedMountains => "minecraft:wooded_mountains",
+            Savanna => "minecraft:savanna",
+            SavannaPlateau => "minecraft:savanna_plateau",
+            Badlands => "minecraft:badlands",
+            WoodedBadlandsPlateau => "minecraft:wooded_badlands_plateau",
+            BadlandsPlateau => "minecraft:badlands_plateau",
+            SmallEndIslands => "minecraft:small_end_islands",
+            EndMidlands => "minecraft:end_midlands",
+            EndHighlands => "minecraft:end_highlands",
+            EndBarrens => "minecraft:end_barrens",
+            WarmOcean => "minecraft:warm_ocean",
+            LukewarmOcean => "minecraft:lukewarm_ocean",
+            ColdOcean => "minecraft:cold_ocean",
+            DeepWarmOcean => "minecraft:deep_warm_ocean",
+            DeepLukewarmOcean => "minecraft:deep_lukewarm_ocean",
+            DeepColdOcean => "minecraft:deep_cold_ocean",
+            DeepFrozenOcean => "minecraft:deep_frozen_ocean",
+            Void => "minecraft:the_void",
+            SunflowerPlains => "minecraft:sunflower_plains",
+            DesertLakes => "minecraft:desert_lakes",
+            GravellyMountains => "minecraft:gravelly_mountains",
+            FlowerForest => "minecraft:flower_forest",
+            TaigaMountains => "minecraft:taiga_mountains",
+            SwampHills => "minecraft:swamp_hills",
+            IceSpikes => "minecraft:ice_spikes",
+            ModifiedJungle => "minecraft:modified_jungle",
+            ModifiedJungleEdge => "minecraft:modified_jungle_edge",
+            TallBirchForest => "minecraft:tall_birch_forest",
+            TallBirchHills => "minecraft:tall_birch_hills",
+            DarkForestHills => "minecraft:dark_forest_hills",
+            SnowyTaigaMountains => "minecraft:snowy_taiga_mountains",
+            GiantSpruceTaiga => "minecraft:giant_spruce_taiga",
+            GiantSpruceTaigaHills => "minecraft:giant_spruce_taiga_hills",
+            ModifiedGravellyMountains => "minecraft:modified_gravelly_mountains",
+            ShatteredSavanna => "minecraft:shattered_savanna",
+            ShatteredSavannaPlateau => "minecraft:shattered_savanna_plateau",
+            ErodedBadlands => "minecraft:eroded_badlands",
+            ModifiedWoodedBadlandsPlateau => "minecraft:modified_wooded_badlands_plateau",
+            ModifiedBadlandsPlateau => "minecraft:modified_badlands_plateau",
+            BambooJungle => "minecraft:bamboo_jungle",
+            BambooJungleHills => "minecraft:bamboo_jungle_hills",
+        }
+    }
+
+    /// Find a biome from its name, the namespace is optional.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim_start_matches("minecraft:");
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|biome| &biome.name()["minecraft:".len()..] == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        assert_eq!(Biome::from_id(0), Some(Biome::Ocean));
+        assert_eq!(Biome::from_id(127), Some(Biome::Void));
+        assert_eq!(Biome::from_id(128), None);
+        assert_eq!(Biome::BambooJungleHills.id(), 169);
+
+        assert_eq!(Biome::Plains.name(), "minecraft:plains");
+        assert_eq!(
+            Biome::from_name("minecraft:snowy_tundra"),
+            Some(Biome::SnowyTundra)
+        );
+        assert_eq!(Biome::from_name("desert"), Some(Biome::Desert));
+        assert_eq!(Biome::from_name("void"), None);
+
+        for biome in Biome::ALL.iter() {
+            assert_eq!(Biome::from_id(biome.id()), Some(*biome));
+            assert_eq!(Biome::from_name(biome.name()), Some(*biome));
+        }
+    }
+}
@@ -1,82 +1,361 @@
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-#[repr(u16)]
-pub enum Block {
-    Air = 0,
-    Bedrock = 33,
-    Dirt = 10,
-    Grass = 9,
-    Water = 49,
-    Lava = 50,
-    WhiteWool = 1383,
-    OrangeWool = 1384,
-    MagentaWool = 1385,
-    LightBlueWool = 1386,
-    YellowWool = 1387,
-    LimeWool = 1388,
-    PinkWool = 1389,
-    GrayWool = 1390,
-    LightGrayWool = 1391,
-    CyanWool = 1392,
-    PurpleWool = 1393,
-    BlueWool = 1394,
-    BrownWool = 1395,
-    GreenWool = 1396,
-    RedWool = 1397,
-    BlackWool = 1398,
-    WhiteStainedGlass = 4081,
-    OrangeStainedGlass = 4082,
-    MagentaStainedGlass = 4083,
-    LightBlueStainedGlass = 4084,
-    YellowStainedGlass = 4085,
-    LimeStainedGlass = 4086,
-    PinkStainedGlass = 4087,
-    GrayStainedGlass = 4088,
-    LightGrayStainedGlass = 4089,
-    CyanStainedGlass = 4090,
-    PurpleStainedGlass = 4091,
-    BlueStainedGlass = 4092,
-    BrownStainedGlass = 4093,
-    GreenStainedGlass = 4094,
-    RedStainedGlass = 4095,
-    BlackStainedGlass = 4096,
-    SlimeBlock = 6999,
-    WhiteConcrete = 8902,
-    OrangeConcrete = 8903,
-    MagentaConcrete = 8904,
-    LightBlueConcrete = 8905,
-    YellowConcrete = 8906,
-    LimeConcrete = 8907,
-    PinkConcrete = 8908,
-    GrayConcrete = 8909,
-    LightGrayConcrete = 8910,
-    CyanConcrete = 8911,
-    PurpleConcrete = 8912,
-    BlueConcrete = 8913,
-    BrownConcrete = 8914,
-    GreenConcrete = 8915,
-    RedConcrete = 8916,
-    BlackConcrete = 8917,
-    WhiteConcretePowder = 8918,
-    OrangeConcretePowder = 8919,
-    MagentaConcretePowder = 8920,
-    LightBlueConcretePowder = 8921,
-    YellowConcretePowder = 8922,
-    LimeConcretePowder = 8923,
-    PinkConcretePowder = 8924,
-    GrayConcretePowder = 8925,
-    LightGrayConcretePowder = 8926,
-    CyanConcretePowder = 8927,
-    PurpleConcretePowder = 8928,
-    BlueConcretePowder = 8929,
-    BrownConcretePowder = 8930,
-    GreenConcretePowder = 8931,
-    RedConcretePowder = 8932,
-    BlackConcretePowder = 8933,
-    HoneyBlock = 11335,
+use crate::packets::play::{Drop, Item, ToolKind};
+use anyhow::{anyhow, Error, Result};
+use std::convert::TryFrom;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+
+/// A block state, as sent over the network.
+///
+/// Every block type of the game has a constant for its default state, e.g. `Block::Stone`, other
+/// states can be reached by changing properties:
+/// ```
+/// use minecrust::packets::play::block::Block;
+///
+/// let stairs = Block::OakStairs.with_property("facing", "east").unwrap();
+/// assert_eq!(stairs.name(), "minecraft:oak_stairs");
+/// assert_eq!(stairs.property("facing"), Some("east"));
+/// assert_eq!(stairs.property("half"), Some("bottom"));
+/// assert_eq!(stairs, "minecraft:oak_stairs[facing=east]".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Block(u16);
+
+struct KindData {
+    kind: BlockKind,
+    name: &'static str,
+    first_state: u16,
+    default_state: u16,
+    properties: &'static [(&'static str, &'static [&'static str])],
+    light_filter: u8,
+    light_emission: u8,
+    /// The light filter and emission of the states with a property value, e.g. lit furnaces.
+    light_states: &'static [(&'static str, &'static str, u8, u8)],
+    hardness: f32,
+    tool: Option<ToolKind>,
+    harvest_tool: Option<(ToolKind, u8)>,
+    drop: Option<Drop>,
+    block_entity: Option<&'static str>,
+}
+
+impl BlockKind {
+    fn data(self) -> &'static KindData {
+        &KINDS[self as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        self.data().name
+    }
+
+    pub fn default_state(self) -> Block {
+        Block(self.data().default_state)
+    }
+
+    /// The names of the properties of this block type and their possible values.
+    pub fn properties(self) -> &'static [(&'static str, &'static [&'static str])] {
+        self.data().properties
+    }
+
+    /// All the states of this block type.
+    pub fn states(self) -> impl Iterator<Item = Block> {
+        let data = self.data();
+        let count: usize = data
+            .properties
+            .iter()
+            .map(|(_, values)| values.len())
+            .product();
+        (data.first_state..data.first_state + count as u16).map(Block)
+    }
 }
 
-impl From<u16> for Block {
-    fn from(n: u16) -> Block {
-        unsafe { std::mem::transmute(n) }
+impl Block {
+    /// Get a block from its state id, `None` if the id doesn't exist.
+    pub fn from_id(id: u16) -> Option<Self> {
+        if id <= LAST_STATE {
+            Some(Self(id))
+        } else {
+            None
+        }
+    }
+
+    pub fn id(self) -> u16 {
+        self.0
+    }
+
+    fn data(self) -> &'static KindData {
+        let index = KINDS.partition_point(|kind| kind.first_state <= self.0);
+        &KINDS[index - 1]
+    }
+
+    pub fn kind(self) -> BlockKind {
+        self.data().kind
+    }
+
+    pub fn name(self) -> &'static str {
+        self.data().name
+    }
+
+    /// The id of the block entity holding the data of the block, e.g. `minecraft:sign`.
+    pub fn block_entity(self) -> Option<&'static str> {
+        self.data().block_entity
+    }
+
+    /// Index of each property value, the last property changes the fastest between states.
+    fn value_indexes(self) -> Vec<usize> {
+        let data = self.data();
+        let mut offset = (self.0 - data.first_state) as usize;
+        let mut indexes = vec![0; data.properties.len()];
+        for (i, (_, values)) in data.properties.iter().enumerate().rev() {
+            indexes[i] = offset % values.len();
+            offset /= values.len();
+        }
+        indexes
+    }
+
+    fn from_value_indexes(kind: BlockKind, indexes: &[usize]) -> Self {
+        let data = kind.data();
+        let offset = data
+            .properties
+            .iter()
+            .zip(indexes)
+            .fold(0, |offset, ((_, values), index)| {
+                offset * values.len() + index
+            });
+        Self(data.first_state + offset as u16)
+    }
+
+    /// The properties of this state and their values.
+    pub fn properties(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.data()
+            .properties
+            .iter()
+            .zip(self.value_indexes())
+            .map(|((name, values), index)| (*name, values[index]))
+    }
+
+    pub fn property(self, name: &str) -> Option<&'static str> {
+        self.properties()
+            .find(|(property, _)| *property == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The same block with a different property value, `None` if the block doesn't have this
+    /// property or if the value is invalid.
+    pub fn with_property(self, name: &str, value: &str) -> Option<Self> {
+        let data = self.data();
+        let property = data.properties.iter().position(|(p, _)| *p == name)?;
+        let value = data.properties[property]
+            .1
+            .iter()
+            .position(|v| *v == value)?;
+
+        let mut indexes = self.value_indexes();
+        indexes[property] = value;
+        Some(Self::from_value_indexes(data.kind, &indexes))
+    }
+
+    /// How much light is absorbed when going through this block, from 0 (fully transparent) to
+    /// 15 (opaque).
+    pub fn light_filter(self) -> u8 {
+        match self.light_state() {
+            Some((_, _, filter, _)) => *filter,
+            None => self.data().light_filter,
+        }
+    }
+
+    /// The light level emitted by this block, 0 if it isn't a light source.
+    pub fn light_emission(self) -> u8 {
+        match self.light_state() {
+            Some((_, _, _, emission)) => *emission,
+            None => self.data().light_emission,
+        }
+    }
+
+    fn light_state(self) -> Option<&'static (&'static str, &'static str, u8, u8)> {
+        self.data()
+            .light_states
+            .iter()
+            .find(|(property, value, ..)| self.property(property) == Some(value))
+    }
+
+    /// How long the block takes to break, None if it can't be broken.
+    pub fn hardness(self) -> Option<f32> {
+        Some(self.data().hardness).filter(|hardness| *hardness >= 0.)
+    }
+
+    /// The type of tool breaking this block faster.
+    pub fn tool(self) -> Option<ToolKind> {
+        self.data().tool
+    }
+
+    /// The type and minimum harvest level of the tool needed to get drops from this block, None
+    /// if it drops with any tool.
+    pub fn harvest_tool(self) -> Option<(ToolKind, u8)> {
+        self.data().harvest_tool
+    }
+
+    /// What this block drops when it is harvested.
+    pub fn drop(self) -> Option<&'static Drop> {
+        self.data().drop.as_ref()
+    }
+}
+
+impl From<BlockKind> for Block {
+    fn from(kind: BlockKind) -> Self {
+        kind.default_state()
+    }
+}
+
+impl TryFrom<u16> for Block {
+    type Error = Error;
+
+    fn try_from(id: u16) -> Result<Self> {
+        Self::from_id(id).ok_or_else(|| anyhow!("invalid block state id: {}", id))
+    }
+}
+
+impl Display for Block {
+    /// Format the block the same way commands do, e.g. `minecraft:oak_log[axis=x]`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        let properties = self
+            .properties()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>();
+        if !properties.is_empty() {
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self, self.0)
+    }
+}
+
+impl FromStr for Block {
+    type Err = Error;
+
+    /// Parse a block the same way commands do, e.g. `minecraft:oak_log[axis=x]`. The namespace
+    /// is optional and missing properties keep their default value.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, properties) = match s.find('[') {
+            Some(start) if s.ends_with(']') => (&s[..start], &s[start + 1..s.len() - 1]),
+            Some(_) => return Err(anyhow!("invalid block: {}", s)),
+            None => (s, ""),
+        };
+
+        let kind = if name.contains(':') {
+            BlockKind::from_name(name)
+        } else {
+            BlockKind::from_name(&format!("minecraft:{}", name))
+        }
+        .ok_or_else(|| anyhow!("unknown block: {}", name))?;
+
+        let mut block = kind.default_state();
+        for property in properties.split(',').filter(|p| !p.is_empty()) {
+            let mut split = property.splitn(2, '=');
+            let (name, value) = match (split.next(), split.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => return Err(anyhow!("invalid block property: {}", property)),
+            };
+            block = block.with_property(name, value).ok_or_else(|| {
+                anyhow!("invalid property {}={} for {}", name, value, kind.name())
+            })?;
+        }
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(Block::Air.id(), 0);
+        assert_eq!(Block::Stone.id(), 1);
+        assert_eq!(Block::GrassBlock.id(), 9);
+        assert_eq!(Block::Water.id(), 34);
+        assert_eq!(Block::HoneyBlock.id(), 11335);
+
+        assert_eq!(Block::from_id(11336), Some(Block::HoneycombBlock));
+        assert_eq!(Block::from_id(11337), None);
+        assert!(Block::try_from(u16::MAX).is_err());
+
+        for id in 0..=LAST_STATE {
+            let block = Block::from_id(id).unwrap();
+            let indexes = block.value_indexes();
+            assert_eq!(Block::from_value_indexes(block.kind(), &indexes), block);
+        }
+    }
+
+    #[test]
+    fn test_properties() {
+        let grass = Block::from_id(8).unwrap();
+        assert_eq!(grass.kind(), BlockKind::GrassBlock);
+        assert_eq!(grass.property("snowy"), Some("true"));
+        assert_eq!(Block::GrassBlock.property("snowy"), Some("false"));
+
+        // facing=north, half=bottom, shape=straight, waterlogged=false
+        let stairs = Block::OakStairs;
+        assert_eq!(stairs.id(), 1963);
+        assert_eq!(stairs.property("facing"), Some("north"));
+        assert_eq!(stairs.property("half"), Some("bottom"));
+        assert_eq!(stairs.property("shape"), Some("straight"));
+        assert_eq!(stairs.property("waterlogged"), Some("false"));
+        assert_eq!(stairs.property("axis"), None);
+
+        let top = stairs.with_property("half", "top").unwrap();
+        assert_eq!(top.id(), 1953);
+        assert_eq!(top.property("half"), Some("top"));
+        assert_eq!(top.with_property("half", "bottom"), Some(stairs));
+        assert_eq!(stairs.with_property("half", "middle"), None);
+        assert_eq!(stairs.with_property("axis", "x"), None);
+
+        assert_eq!(Block::Water.with_property("level", "15").unwrap().id(), 49);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("minecraft:stone".parse::<Block>().unwrap(), Block::Stone);
+        assert_eq!("stone".parse::<Block>().unwrap(), Block::Stone);
+        assert_eq!(
+            "oak_log[axis=x]".parse::<Block>().unwrap().to_string(),
+            "minecraft:oak_log[axis=x]"
+        );
+        assert_eq!(
+            Block::OakStairs.to_string(),
+            "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]"
+        );
+        assert!("minecraft:unknown".parse::<Block>().is_err());
+        assert!("oak_log[axis=w]".parse::<Block>().is_err());
+        assert!("oak_log[axis]".parse::<Block>().is_err());
+
+        for id in 0..=LAST_STATE {
+            let block = Block::from_id(id).unwrap();
+            assert_eq!(block.to_string().parse::<Block>().unwrap(), block);
+        }
+    }
+
+    #[test]
+    fn test_light() {
+        assert_eq!(Block::Air.light_filter(), 0);
+        assert_eq!(Block::Stone.light_filter(), 15);
+        assert_eq!(Block::Glass.light_filter(), 0);
+        assert_eq!(Block::Lava.light_emission(), 15);
+        assert_eq!(Block::Torch.light_emission(), 14);
+        assert_eq!(Block::Grass.light_filter(), 0);
+
+        let ore = Block::RedstoneOre;
+        assert_eq!(ore.light_filter(), 15);
+        assert_eq!(ore.light_emission(), 0);
+        let lit = ore.with_property("lit", "true").unwrap();
+        assert_eq!(lit.light_filter(), 15);
+        assert_eq!(lit.light_emission(), 9);
+        assert_eq!(Block::RedstoneTorch.light_emission(), 7);
     }
 }
@@ -0,0 +1,21 @@
+use crate::types::{BlockPosition, VarInt};
+
+/// Show the cracks of a block being broken by an entity.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct BlockBreakAnimation {
+    id: VarInt,
+    position: BlockPosition,
+    stage: i8,
+}
+
+impl BlockBreakAnimation {
+    /// `stage` goes from 0 to 9, None removes the cracks.
+    pub fn new(id: VarInt, position: BlockPosition, stage: Option<u8>) -> Self {
+        Self {
+            id,
+            position,
+            stage: stage.map_or(-1, |stage| stage.min(9) as i8),
+        }
+    }
+}
+crate::impl_packet!(BlockBreakAnimation, 0x09);
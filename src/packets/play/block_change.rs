@@ -11,7 +11,7 @@ impl BlockChange {
     pub fn new(position: BlockPosition, block: Block) -> Self {
         Self {
             position,
-            block: VarInt(block as i32),
+            block: VarInt(block.id() as i32),
         }
     }
 }
@@ -0,0 +1,504 @@
+use crate::packets::play::{Block, Item, ItemStack};
+use crate::types::{self, BlockPosition, Receive, TAsyncRead, VarInt};
+use anyhow::{anyhow, Result};
+use nbt::{Blob, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Compound = HashMap<String, Value>;
+
+/// The data of the blocks which don't fit in a block state, e.g. the text of a sign.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BlockEntity {
+    Sign(Sign),
+    Chest {
+        trapped: bool,
+        items: ChestItems,
+    },
+    /// Patterns drawn over the base color of the banner block.
+    Banner(Vec<BannerPattern>),
+    /// The name of the player whose head it is, for player heads.
+    Skull(Option<String>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Sign {
+    /// The lines of text, as JSON chat components.
+    lines: [String; 4],
+    /// The player allowed to write the text, once, after placing the sign.
+    editor: Option<VarInt>,
+}
+
+/// The items of a chest, shared by the windows of the players who opened it.
+#[derive(Debug, Clone)]
+pub struct ChestItems(Arc<Mutex<Vec<Option<ItemStack>>>>);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BannerPattern {
+    /// The code of the pattern, e.g. `bs` for the bottom stripe.
+    pub pattern: String,
+    pub color: u8,
+}
+
+impl BlockEntity {
+    /// The empty block entity of a block, if it has one.
+    pub fn new(block: Block) -> Option<Self> {
+        Some(match block.block_entity()? {
+            "minecraft:chest" | "minecraft:trapped_chest" => BlockEntity::Chest {
+                trapped: block.block_entity() == Some("minecraft:trapped_chest"),
+                items: ChestItems::new(),
+            },
+            "minecraft:sign" => BlockEntity::Sign(Sign::new(["", "", "", ""])),
+            "minecraft:banner" => BlockEntity::Banner(Vec::new()),
+            "minecraft:skull" => BlockEntity::Skull(None),
+            id => unreachable!("unsupported block entity: {}", id),
+        })
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            BlockEntity::Sign(_) => "minecraft:sign",
+            BlockEntity::Chest { trapped: false, .. } => "minecraft:chest",
+            BlockEntity::Chest { trapped: true, .. } => "minecraft:trapped_chest",
+            BlockEntity::Banner(_) => "minecraft:banner",
+            BlockEntity::Skull(_) => "minecraft:skull",
+        }
+    }
+
+    /// A copy of the block entity, with a copy of the items of a chest instead of sharing them.
+    pub fn duplicate(&self) -> Self {
+        match self {
+            BlockEntity::Chest { trapped, items } => BlockEntity::Chest {
+                trapped: *trapped,
+                items: items.duplicate(),
+            },
+            entity => entity.clone(),
+        }
+    }
+
+    /// The action of the Update Block Entity packet sending this type of block entity, None if
+    /// the client doesn't need its data.
+    fn action(&self) -> Option<u8> {
+        match self {
+            BlockEntity::Skull(_) => Some(4),
+            BlockEntity::Banner(_) => Some(6),
+            BlockEntity::Sign(_) => Some(9),
+            BlockEntity::Chest { .. } => None,
+        }
+    }
+
+    /// The full NBT data of the block entity, as saved in worlds.
+    pub fn to_nbt(&self, x: i32, y: u16, z: i32) -> Compound {
+        let mut compound = self.client_compound(x, y, z);
+        if let BlockEntity::Chest { items, .. } = self {
+            let items = items
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .filter_map(|(slot, item)| Some(item_nbt(slot as i8, item.as_ref()?)))
+                .collect();
+            insert(&mut compound, "Items", Value::List(items));
+        }
+        compound
+    }
+
+    /// The NBT data known by the client: the content of containers is only sent when opened.
+    fn client_nbt(&self, x: i32, y: u16, z: i32) -> Blob {
+        let mut blob = Blob::new();
+        for (key, value) in self.client_compound(x, y, z) {
+            // The keys are valid and the values are never lists of mixed types.
+            blob.insert(key, value).expect("invalid block entity NBT");
+        }
+        blob
+    }
+
+    fn client_compound(&self, x: i32, y: u16, z: i32) -> Compound {
+        let mut compound = Compound::new();
+        insert(&mut compound, "id", self.id());
+        insert(&mut compound, "x", x);
+        insert(&mut compound, "y", y as i32);
+        insert(&mut compound, "z", z);
+        match self {
+            BlockEntity::Sign(sign) => {
+                for (index, line) in sign.lines.iter().enumerate() {
+                    insert(&mut compound, &format!("Text{}", index + 1), line.as_str());
+                }
+            }
+            BlockEntity::Banner(patterns) => {
+                let patterns = patterns
+                    .iter()
+                    .map(|pattern| {
+                        let mut compound = Compound::new();
+                        compound.insert("Pattern".into(), pattern.pattern.clone().into());
+                        compound.insert("Color".into(), Value::Int(pattern.color as i32));
+                        Value::Compound(compound)
+                    })
+                    .collect();
+                insert(&mut compound, "Patterns", Value::List(patterns));
+            }
+            BlockEntity::Skull(Some(owner)) => {
+                let mut owner_compound = Compound::new();
+                owner_compound.insert("Name".into(), owner.clone().into());
+                insert(&mut compound, "SkullOwner", Value::Compound(owner_compound));
+            }
+            BlockEntity::Skull(None) | BlockEntity::Chest { .. } => {}
+        }
+        compound
+    }
+
+    /// Read the block entity saved in a world, from its NBT data.
+    pub fn from_nbt(compound: &Compound) -> Result<Self> {
+        let string = |key: &str| match compound.get(key) {
+            Some(Value::String(value)) => Some(value.clone()),
+            _ => None,
+        };
+        let list = |key: &str| match compound.get(key) {
+            Some(Value::List(values)) => values.as_slice(),
+            _ => &[],
+        };
+        let id = string("id").ok_or_else(|| anyhow!("missing block entity id"))?;
+        Ok(match id.as_str() {
+            "minecraft:sign" => BlockEntity::Sign(Sign {
+                lines: [
+                    string("Text1").unwrap_or_default(),
+                    string("Text2").unwrap_or_default(),
+                    string("Text3").unwrap_or_default(),
+                    string("Text4").unwrap_or_default(),
+                ],
+                editor: None,
+            }),
+            "minecraft:chest" | "minecraft:trapped_chest" => {
+                let items = ChestItems::new();
+                for item in list("Items") {
+                    if let Value::Compound(item) = item {
+                        let (slot, item) = nbt_item(item)?;
+                        items.set(slot, Some(item));
+                    }
+                }
+                BlockEntity::Chest {
+                    trapped: id == "minecraft:trapped_chest",
+                    items,
+                }
+            }
+            "minecraft:banner" => BlockEntity::Banner(
+                list("Patterns")
+                    .iter()
+                    .filter_map(|pattern| match pattern {
+                        Value::Compound(pattern) => {
+                            match (pattern.get("Pattern"), pattern.get("Color")) {
+                                (Some(Value::String(code)), Some(Value::Int(color))) => {
+                                    Some(BannerPattern {
+                                        pattern: code.clone(),
+                                        color: *color as u8,
+                                    })
+                                }
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            "minecraft:skull" => BlockEntity::Skull(match compound.get("SkullOwner") {
+                Some(Value::Compound(owner)) => match owner.get("Name") {
+                    Some(Value::String(name)) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            }),
+            id => return Err(anyhow!("unsupported block entity: {}", id)),
+        })
+    }
+}
+
+fn insert(compound: &mut Compound, key: &str, value: impl Into<Value>) {
+    compound.insert(key.into(), value.into());
+}
+
+fn item_nbt(slot: i8, item: &ItemStack) -> Value {
+    let mut compound = Compound::new();
+    compound.insert("Slot".into(), Value::Byte(slot));
+    compound.insert("id".into(), item.item().name().into());
+    compound.insert("Count".into(), Value::Byte(item.count()));
+    if let Ok(Some(tag)) = item.nbt() {
+        compound.insert("tag".into(), Value::Compound(tag));
+    }
+    Value::Compound(compound)
+}
+
+fn nbt_item(compound: &Compound) -> Result<(u16, ItemStack)> {
+    match (
+        &compound.get("Slot"),
+        &compound.get("id"),
+        &compound.get("Count"),
+    ) {
+        (Some(Value::Byte(slot)), Some(Value::String(id)), Some(Value::Byte(count))) => {
+            let item = Item::from_name(id).ok_or_else(|| anyhow!("invalid item: {}", id))?;
+            let mut stack = ItemStack::new(item, *count);
+            if let Some(Value::Compound(tag)) = compound.get("tag") {
+                let mut blob = Blob::new();
+                for (key, value) in tag {
+                    blob.insert(key.as_str(), value.clone())?;
+                }
+                stack = stack.with_nbt(&blob)?;
+            }
+            Ok((*slot as u16, stack))
+        }
+        _ => Err(anyhow!("invalid chest item")),
+    }
+}
+
+impl Sign {
+    /// A sign with lines of plain text.
+    pub fn new(lines: [&str; 4]) -> Self {
+        let mut sign = Self {
+            lines: Default::default(),
+            editor: None,
+        };
+        sign.set_lines(lines);
+        sign
+    }
+
+    /// The lines of text, as JSON chat components.
+    pub fn lines(&self) -> &[String; 4] {
+        &self.lines
+    }
+
+    /// Set the lines of plain text.
+    pub fn set_lines(&mut self, lines: [&str; 4]) {
+        for (line, text) in self.lines.iter_mut().zip(lines.iter()) {
+            *line = serde_json::json!({ "text": text }).to_string();
+        }
+    }
+
+    pub fn editor(&self) -> Option<VarInt> {
+        self.editor
+    }
+
+    pub fn set_editor(&mut self, editor: Option<VarInt>) {
+        self.editor = editor;
+    }
+}
+
+impl ChestItems {
+    pub const SIZE: u16 = 27;
+
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(vec![None; Self::SIZE as usize])))
+    }
+
+    pub fn get(&self, index: u16) -> Option<ItemStack> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(index as usize)
+            .cloned()
+            .flatten()
+    }
+
+    pub fn set(&self, index: u16, item: Option<ItemStack>) {
+        if let Some(slot) = self.0.lock().unwrap().get_mut(index as usize) {
+            *slot = item;
+        }
+    }
+
+    /// Empty the chest and return its items.
+    pub fn take_all(&self) -> Vec<ItemStack> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(Option::take)
+            .collect()
+    }
+
+    /// A copy of the items, not shared with the windows opened on this chest.
+    pub fn duplicate(&self) -> Self {
+        Self(Arc::new(Mutex::new(self.0.lock().unwrap().clone())))
+    }
+}
+
+/// Chests are equal if they hold the same items, even if they don't share them.
+impl PartialEq for ChestItems {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || *self.0.lock().unwrap() == *other.0.lock().unwrap()
+    }
+}
+
+impl Eq for ChestItems {}
+
+impl Default for ChestItems {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Set the data of a block entity.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct UpdateBlockEntity {
+    position: BlockPosition,
+    action: u8,
+    nbt: Vec<u8>,
+}
+crate::impl_packet!(UpdateBlockEntity, 0x0A);
+
+impl UpdateBlockEntity {
+    /// None if the client doesn't need the data of this block entity.
+    pub fn new(position: BlockPosition, entity: &BlockEntity) -> Option<Self> {
+        let action = entity.action()?;
+        let mut nbt = Vec::new();
+        entity
+            .client_nbt(position.x, position.y, position.z)
+            .to_writer(&mut nbt)
+            .expect("invalid block entity NBT");
+        Some(Self {
+            position,
+            action,
+            nbt,
+        })
+    }
+}
+
+/// The block entities sent at the end of a chunk packet.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub(crate) struct ChunkBlockEntities {
+    count: VarInt,
+    nbt: Vec<u8>,
+}
+
+impl ChunkBlockEntities {
+    pub fn new<'a>(entities: impl Iterator<Item = ((i32, u16, i32), &'a BlockEntity)>) -> Self {
+        let mut count = 0;
+        let mut nbt = Vec::new();
+        for ((x, y, z), entity) in entities {
+            entity
+                .client_nbt(x, y, z)
+                .to_writer(&mut nbt)
+                .expect("invalid block entity NBT");
+            count += 1;
+        }
+        Self {
+            count: VarInt(count),
+            nbt,
+        }
+    }
+}
+
+/// Open the text editor of a sign the player just placed.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct OpenSignEditor(pub BlockPosition);
+crate::impl_packet!(OpenSignEditor, 0x30);
+
+/// The text written by a player on a sign.
+#[derive(Debug)]
+pub struct UpdateSign {
+    pub position: BlockPosition,
+    pub lines: [String; 4],
+}
+
+impl UpdateSign {
+    pub const PACKET_ID: VarInt = VarInt(0x29);
+    /// The longest line the client can send.
+    const MAX_LENGTH: usize = 384;
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for UpdateSign {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let position = reader.receive().await?;
+        let mut lines: [String; 4] = Default::default();
+        for line in lines.iter_mut() {
+            let text: types::String = reader.receive().await?;
+            if text.chars().count() > Self::MAX_LENGTH {
+                return Err(anyhow!("sign line too long"));
+            }
+            *line = text.to_string();
+        }
+        Ok(Self { position, lines })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Send, Size};
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[test]
+    fn test_new() {
+        assert!(BlockEntity::new(Block::Stone).is_none());
+        assert!(BlockEntity::new(Block::PistonHead).is_none());
+        let wall_sign = BlockEntity::new(Block::SpruceWallSign).unwrap();
+        assert_eq!(wall_sign.id(), "minecraft:sign");
+        assert_eq!(
+            BlockEntity::new(Block::TrappedChest).unwrap().id(),
+            "minecraft:trapped_chest"
+        );
+        assert_eq!(
+            BlockEntity::new(Block::ZombieWallHead).unwrap().id(),
+            "minecraft:skull"
+        );
+        assert_eq!(
+            BlockEntity::new(Block::RedBanner).unwrap().id(),
+            "minecraft:banner"
+        );
+    }
+
+    #[test]
+    fn test_nbt() -> Result<()> {
+        let chest = BlockEntity::new(Block::Chest).unwrap();
+        if let BlockEntity::Chest { items, .. } = &chest {
+            items.set(3, Some(ItemStack::new(Item::Diamond, 5)));
+        }
+        let compound = chest.to_nbt(1, 2, 3);
+        assert_eq!(compound["x"], Value::Int(1));
+        match BlockEntity::from_nbt(&compound)? {
+            BlockEntity::Chest { trapped, items } => {
+                assert!(!trapped);
+                assert_eq!(items.get(3), Some(ItemStack::new(Item::Diamond, 5)));
+                assert_eq!(items.take_all().len(), 1);
+            }
+            entity => panic!("unexpected block entity: {:?}", entity),
+        }
+
+        let mut compound = Compound::new();
+        compound.insert("id".into(), "minecraft:sign".into());
+        compound.insert("Text2".into(), r#"{"text":"Hello"}"#.into());
+        match BlockEntity::from_nbt(&compound)? {
+            BlockEntity::Sign(sign) => assert_eq!(sign.lines()[1], r#"{"text":"Hello"}"#),
+            entity => panic!("unexpected block entity: {:?}", entity),
+        }
+        compound.insert("id".into(), "minecraft:beacon".into());
+        assert!(BlockEntity::from_nbt(&compound).is_err());
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_update_block_entity() -> Result<()> {
+        let position = BlockPosition::new(0, 1, 0);
+        let chest = BlockEntity::new(Block::Chest).unwrap();
+        assert!(UpdateBlockEntity::new(position.clone(), &chest).is_none());
+
+        let sign = BlockEntity::Sign(Sign::new(["a", "", "", ""]));
+        let packet = UpdateBlockEntity::new(position, &sign).unwrap();
+        let mut buffer = Vec::new();
+        packet.send(&mut buffer).await?;
+        assert_eq!(*packet.size() as usize, buffer.len());
+        assert_eq!(&buffer[..9], &[0, 0, 0, 0, 0, 0, 0, 1, 9]);
+        assert_eq!(buffer[9], 0x0a);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn read_update_sign() -> Result<()> {
+        let mut data = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        data.extend(&[2, b'h', b'i', 0, 0, 0]);
+        let update: UpdateSign = Cursor::new(data).receive().await?;
+        assert_eq!(update.position, BlockPosition::new(0, 1, 0));
+        assert_eq!(update.lines, ["hi", "", "", ""]);
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::packets::play::Face;
+use crate::types::{self, BlockPosition, Receive, TAsyncRead, VarInt};
+use anyhow::{bail, ensure, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Hand {
+    Main,
+    Off,
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for Hand {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(match *reader.receive::<VarInt>().await? {
+            0 => Hand::Main,
+            1 => Hand::Off,
+            _ => bail!("invalid hand"),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PlayerBlockPlacement {
+    pub hand: Hand,
+    pub position: BlockPosition,
+    pub face: Face,
+    /// Position of the crosshair on the clicked face, from 0 to 1.
+    pub cursor: (f32, f32, f32),
+    pub inside_block: bool,
+}
+
+impl PlayerBlockPlacement {
+    pub const PACKET_ID: VarInt = VarInt(0x2C);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for PlayerBlockPlacement {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let hand = reader.receive().await?;
+        let position = reader.receive().await?;
+
+        let face: VarInt = reader.receive().await?;
+        ensure!((0..=5).contains(&*face), "invalid face");
+        let face = Face::from(*face as u8);
+
+        Ok(Self {
+            hand,
+            position,
+            face,
+            cursor: (
+                reader.receive().await?,
+                reader.receive().await?,
+                reader.receive().await?,
+            ),
+            inside_block: reader.receive().await?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct UseItem {
+    pub hand: Hand,
+}
+
+impl UseItem {
+    pub const PACKET_ID: VarInt = VarInt(0x2D);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for UseItem {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            hand: reader.receive().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn read_block_placement() -> Result<()> {
+        let mut data = vec![0x01];
+        data.extend(&((3u64 << 38) | (2u64 << 12) | 64).to_be_bytes());
+        data.push(0x01);
+        for cursor in &[0.5f32, 1., 0.25] {
+            data.extend(&cursor.to_be_bytes());
+        }
+        data.push(0x00);
+
+        let placement: PlayerBlockPlacement = Cursor::new(data).receive().await?;
+        assert_eq!(placement.hand, Hand::Off);
+        let position = placement.position;
+        assert_eq!((position.x, position.y, position.z), (3, 64, 2));
+        assert_eq!(placement.face, Face::Top);
+        assert_eq!(placement.cursor, (0.5, 1., 0.25));
+        assert!(!placement.inside_block);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn read_use_item() -> Result<()> {
+        assert_eq!(
+            Cursor::new(vec![0x00]).receive::<UseItem>().await?.hand,
+            Hand::Main
+        );
+        assert!(Cursor::new(vec![0x02]).receive::<UseItem>().await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::packets::play::GameMode;
+
+/// The changes of state applied by the client, see the constructors of `ChangeGameState`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Reason {
+    InvalidBed = 0,
+    /// Despite its name in some documentations, the client starts raining on 1 and stops on 2.
+    BeginRaining,
+    EndRaining,
+    ChangeGameMode,
+    ExitEnd,
+    DemoMessage,
+    ArrowHitPlayer,
+    RainLevel,
+    ThunderLevel,
+    PufferfishSting,
+    ElderGuardianAppearance,
+    EnableRespawnScreen,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct ChangeGameState {
+    reason: u8,
+    value: f32,
+}
+crate::impl_packet!(ChangeGameState, 0x1F);
+
+impl ChangeGameState {
+    pub fn new(reason: Reason, value: f32) -> Self {
+        Self {
+            reason: reason as u8,
+            value,
+        }
+    }
+
+    pub fn reason(&self) -> u8 {
+        self.reason
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn begin_raining() -> Self {
+        Self::new(Reason::BeginRaining, 0.)
+    }
+
+    pub fn end_raining() -> Self {
+        Self::new(Reason::EndRaining, 0.)
+    }
+
+    /// The intensity of the rain, from 0 to 1.
+    pub fn rain_level(level: f32) -> Self {
+        Self::new(Reason::RainLevel, level.clamp(0., 1.))
+    }
+
+    /// The darkness of the sky during a thunderstorm, from 0 to 1.
+    pub fn thunder_level(level: f32) -> Self {
+        Self::new(Reason::ThunderLevel, level.clamp(0., 1.))
+    }
+
+    /// Change the game mode of the client, the abilities follow it.
+    pub fn game_mode(game_mode: GameMode) -> Self {
+        Self::new(Reason::ChangeGameMode, game_mode as u8 as f32)
+    }
+
+    /// Whether dying shows the respawn screen or respawns the player immediately.
+    pub fn respawn_screen(enabled: bool) -> Self {
+        Self::new(Reason::EnableRespawnScreen, if enabled { 0. } else { 1. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Send;
+    use anyhow::Result;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_change_game_state() -> Result<()> {
+        let mut buffer = Vec::new();
+        ChangeGameState::game_mode(GameMode::Spectator)
+            .send(&mut buffer)
+            .await?;
+        assert_eq!(buffer, [3, 0x40, 0x40, 0, 0]);
+        assert_eq!(ChangeGameState::rain_level(1.5).value(), 1.);
+        assert_eq!(ChangeGameState::respawn_screen(false).value(), 1.);
+        Ok(())
+    }
+}
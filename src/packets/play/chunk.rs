@@ -1,6 +1,9 @@
+use crate::packets::play::biome::Biome;
 use crate::packets::play::block::Block;
-use crate::types::{self, BitArray, LengthVec, Size, TAsyncWrite, VarInt};
-use crate::{impl_send, impl_size};
+use crate::packets::play::block_entity::{BlockEntity, ChunkBlockEntities};
+use crate::packets::play::light::ChunkLight;
+use crate::packets::Packet;
+use crate::types::{self, BitArray, LengthVec, Send, Size, TAsyncWrite, VarInt};
 use anyhow::Result;
 use nbt::Blob;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
@@ -15,8 +18,9 @@ pub struct Chunk {
     heightmap: Heightmap,
     biomes: Biomes,
     sections: [Option<ChunkSection>; 16],
+    light: ChunkLight,
+    block_entities: HashMap<(u8, u16, u8), BlockEntity>,
 }
-crate::impl_packet!(Chunk, 0x22);
 
 impl Chunk {
     pub fn new(x: i32, z: i32) -> Self {
@@ -26,6 +30,8 @@ impl Chunk {
             heightmap: Heightmap::new(),
             biomes: Biomes::new(Biome::Plains),
             sections: Default::default(),
+            light: ChunkLight::new(),
+            block_entities: HashMap::new(),
         }
     }
 
@@ -36,6 +42,12 @@ impl Chunk {
             heightmap: self.heightmap.clone(),
             biomes: self.biomes.clone(),
             sections: self.sections.clone(),
+            light: self.light.clone(),
+            block_entities: self
+                .block_entities
+                .iter()
+                .map(|(position, entity)| (*position, entity.duplicate()))
+                .collect(),
         }
     }
 
@@ -49,6 +61,29 @@ impl Chunk {
         VarInt::new(bitmask)
     }
 
+    /// The biome at the given position. Biomes are stored by cells of 4x4x4 blocks, so
+    /// neighbour blocks share the same biome.
+    pub fn biome(&self, x: u8, y: u16, z: u8) -> Biome {
+        self.biomes.get(x, y, z)
+    }
+
+    /// Set the biome of the 4x4x4 cell containing the given position.
+    pub fn set_biome(&mut self, x: u8, y: u16, z: u8, biome: Biome) {
+        self.biomes.set(x, y, z, biome);
+    }
+
+    /// Set the biome of the whole height of the 4x4 column containing the given position.
+    pub fn set_column_biome(&mut self, x: u8, z: u8, biome: Biome) {
+        for y in (0..256).step_by(4) {
+            self.biomes.set(x, y, z, biome);
+        }
+    }
+
+    /// Set the biome of the whole chunk.
+    pub fn fill_biome(&mut self, biome: Biome) {
+        self.biomes = Biomes::new(biome);
+    }
+
     pub fn get_block(&self, x: u8, y: u16, z: u8) -> Block {
         if let Some(section) = &self.sections[y as usize / 16] {
             return section.get(x, (y % 16) as u8, z);
@@ -56,7 +91,78 @@ impl Chunk {
         Block::Air
     }
 
+    /// The y coordinate of the highest non-air block of the column, 0 if the column is empty.
+    pub fn height(&self, x: u8, z: u8) -> u16 {
+        self.heightmap.get(x, z)
+    }
+
+    pub(crate) fn section_contains(&self, index: usize, predicate: impl Fn(Block) -> bool) -> bool {
+        match &self.sections[index] {
+            Some(section) => section.mapping.keys().any(|block| predicate(*block)),
+            None => predicate(Block::Air),
+        }
+    }
+
+    pub fn light(&self) -> &ChunkLight {
+        &self.light
+    }
+
+    pub(crate) fn light_mut(&mut self) -> &mut ChunkLight {
+        &mut self.light
+    }
+
+    /// Recompute the sky and block light of the chunk on its own, should be called after
+    /// modifying blocks. See `light::spread` for the light of its neighbours.
+    pub fn update_light(&mut self) {
+        self.light = ChunkLight::compute(self);
+    }
+
+    pub fn block_entity(&self, x: u8, y: u16, z: u8) -> Option<&BlockEntity> {
+        self.block_entities.get(&(x, y, z))
+    }
+
+    pub fn block_entity_mut(&mut self, x: u8, y: u16, z: u8) -> Option<&mut BlockEntity> {
+        self.block_entities.get_mut(&(x, y, z))
+    }
+
+    /// Replace the block entity of a block, e.g. to load it from a world save. Ignored if the
+    /// block doesn't hold this type of block entity.
+    pub fn set_block_entity(&mut self, x: u8, y: u16, z: u8, entity: BlockEntity) {
+        if self.get_block(x, y, z).block_entity() == Some(entity.id()) {
+            self.block_entities.insert((x, y, z), entity);
+        }
+    }
+
+    /// The block entities of the chunk, with their position in the chunk.
+    pub fn block_entities(&self) -> impl Iterator<Item = ((u8, u16, u8), &BlockEntity)> {
+        self.block_entities
+            .iter()
+            .map(|(position, entity)| (*position, entity))
+    }
+
+    /// The block entities with their position in the world.
+    fn world_block_entities(&self) -> impl Iterator<Item = ((i32, u16, i32), &BlockEntity)> {
+        let (c_x, c_z) = (self.x * 16, self.z * 16);
+        self.block_entities()
+            .map(move |((x, y, z), entity)| ((c_x + x as i32, y, c_z + z as i32), entity))
+    }
+
+    /// Set a block, creating its empty block entity or removing the one of the previous block
+    /// if they don't have the same type.
     pub fn set_block(&mut self, x: u8, y: u16, z: u8, block: Block) {
+        match block.block_entity() {
+            Some(id) => {
+                if self.block_entity(x, y, z).map(BlockEntity::id) != Some(id) {
+                    if let Some(entity) = BlockEntity::new(block) {
+                        self.block_entities.insert((x, y, z), entity);
+                    }
+                }
+            }
+            None => {
+                self.block_entities.remove(&(x, y, z));
+            }
+        }
+
         let section_index = y as usize / 16;
         let section = match (&mut self.sections[section_index], block) {
             (None, Block::Air) => return,
@@ -78,7 +184,7 @@ impl Chunk {
         if block != Block::Air {
             self.heightmap.replace_if_bigger(x, z, y as u16);
         } else if y == self.heightmap.get(x, z) {
-            for i in (0..y - 1).rev() {
+            for i in (0..y).rev() {
                 if self.get_block(x, i, z) != Block::Air {
                     self.heightmap.set(x, z, i);
                     return;
@@ -89,8 +195,9 @@ impl Chunk {
     }
 }
 
-impl types::Size for Chunk {
-    fn size(&self) -> types::VarInt {
+impl Chunk {
+    /// The size of the chunk data, without the block entities at the end.
+    fn data_size(&self) -> VarInt {
         let sections_size = self.sections.size();
 
         self.x.size()
@@ -101,13 +208,9 @@ impl types::Size for Chunk {
             + self.biomes.size()
             + sections_size.size()
             + sections_size
-            + VarInt::new(0).size()
     }
-}
 
-#[async_trait::async_trait]
-impl types::Send for Chunk {
-    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+    async fn send_data<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
         self.x.send(writer).await?;
         self.z.send(writer).await?;
         true.send(writer).await?;
@@ -115,8 +218,39 @@ impl types::Send for Chunk {
         self.heightmap.send(writer).await?;
         self.biomes.send(writer).await?;
         self.sections.size().send(writer).await?;
-        self.sections.send(writer).await?;
-        VarInt::new(0).send(writer).await
+        self.sections.send(writer).await
+    }
+}
+
+impl types::Size for Chunk {
+    fn size(&self) -> types::VarInt {
+        self.data_size() + ChunkBlockEntities::new(self.world_block_entities()).size()
+    }
+}
+
+#[async_trait::async_trait]
+impl types::Send for Chunk {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        self.send_data(writer).await?;
+        ChunkBlockEntities::new(self.world_block_entities())
+            .send(writer)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl Packet for Chunk {
+    const PACKET_ID: VarInt = VarInt(0x22);
+
+    /// The NBT data of the block entities is only serialized once, for both the size and the
+    /// content of the packet.
+    async fn send_packet<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        let block_entities = ChunkBlockEntities::new(self.world_block_entities());
+        let size = Chunk::PACKET_ID.size() + self.data_size() + block_entities.size();
+        size.send(writer).await?;
+        Chunk::PACKET_ID.send(writer).await?;
+        self.send_data(writer).await?;
+        block_entities.send(writer).await
     }
 }
 
@@ -204,7 +338,7 @@ impl ChunkSection {
             available: (1..(1 << Self::INDIRECT_MIN_BITS_PER_BLOCK as usize))
                 .rev()
                 .collect(),
-            palette: LengthVec::from(vec![VarInt(Block::Air as i32)]),
+            palette: LengthVec::from(vec![VarInt(Block::Air.id() as i32)]),
             data: BitArray::<LengthVec<u64>>::new(
                 Self::CAPACITY * Self::INDIRECT_MIN_BITS_PER_BLOCK as usize / 64,
                 Self::INDIRECT_MIN_BITS_PER_BLOCK as usize,
@@ -212,15 +346,20 @@ impl ChunkSection {
         }
     }
 
+    /// Block ids stored in sections always come from valid blocks.
+    fn palette_block(id: u16) -> Block {
+        Block::from_id(id).expect("invalid block id in chunk section")
+    }
+
     pub fn get(&self, x: u8, y: u8, z: u8) -> Block {
         let palette_index = self
             .data
             .get(y as usize * Self::WIDTH * Self::LENGTH + z as usize * Self::WIDTH + x as usize);
 
         if self.bits_per_block == Self::DIRECT_BITS_PER_BLOCK {
-            palette_index.into()
+            Self::palette_block(palette_index)
         } else {
-            (*self.palette[palette_index as usize] as u16).into()
+            Self::palette_block(*self.palette[palette_index as usize] as u16)
         }
     }
 
@@ -271,7 +410,7 @@ impl ChunkSection {
             // We are on a direct palette. Add block count and return its direct value.
             if self.bits_per_block == Self::DIRECT_BITS_PER_BLOCK {
                 self.mapping.insert(block, (1, 0));
-                return block as usize;
+                return block.id() as usize;
             }
 
             let palette_index = self.available.pop().unwrap();
@@ -280,11 +419,11 @@ impl ChunkSection {
             // Increase palette size if it's the first time that we reach that size.
             if palette_index >= self.palette.len() {
                 self.palette
-                    .resize(palette_index + 1, VarInt(Block::Air as i32));
+                    .resize(palette_index + 1, VarInt(Block::Air.id() as i32));
             }
 
             // Add block to the palette.
-            self.palette[palette_index] = VarInt(block as i32);
+            self.palette[palette_index] = VarInt(block.id() as i32);
             palette_index
         }
     }
@@ -360,7 +499,7 @@ impl ChunkSection {
         let mut new_palette = LengthVec::from(Vec::with_capacity(self.mapping.len()));
         for (new_index, (block, (_count, palette_index))) in self.mapping.iter_mut().enumerate() {
             *palette_index = new_index;
-            new_palette.push(VarInt(*block as i32));
+            new_palette.push(VarInt(block.id() as i32));
         }
 
         let mut new_data = BitArray::<LengthVec<u64>>::new(
@@ -370,14 +509,18 @@ impl ChunkSection {
 
         if self.bits_per_block == Self::DIRECT_BITS_PER_BLOCK {
             for i in 0..Self::CAPACITY {
-                new_data.set(i, self.mapping[&self.data.get(i).into()].1 as u16);
+                new_data.set(
+                    i,
+                    self.mapping[&Self::palette_block(self.data.get(i))].1 as u16,
+                );
             }
         } else {
             for i in 0..Self::CAPACITY {
                 new_data.set(
                     i,
-                    self.mapping[&(*self.palette[self.data.get(i) as usize] as u16).into()].1
-                        as u16,
+                    self.mapping
+                        [&Self::palette_block(*self.palette[self.data.get(i) as usize] as u16)]
+                        .1 as u16,
                 );
             }
         }
@@ -435,6 +578,19 @@ impl Biomes {
     fn new(biome: Biome) -> Self {
         Self([biome; 1024])
     }
+
+    /// Biomes are stored by cells of 4x4x4 blocks.
+    fn index(x: u8, y: u16, z: u8) -> usize {
+        (y as usize / 4) << 4 | (z as usize / 4) << 2 | (x as usize / 4)
+    }
+
+    fn get(&self, x: u8, y: u16, z: u8) -> Biome {
+        self.0[Self::index(x, y, z)]
+    }
+
+    fn set(&mut self, x: u8, y: u16, z: u8, biome: Biome) {
+        self.0[Self::index(x, y, z)] = biome;
+    }
 }
 
 impl Debug for Biomes {
@@ -459,19 +615,10 @@ impl types::Send for Biomes {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-#[repr(i32)]
-pub enum Biome {
-    Ocean = 0,
-    Plains = 1,
-    Void = 127,
-}
-impl_size!(Biome, 4);
-impl_send!(Biome as i32);
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures_await_test::async_test;
     use rand::seq::SliceRandom;
 
     const ALL_4_HEIGHTMAP: [u64; 36] = [
@@ -555,6 +702,75 @@ mod tests {
         assert_eq!(heightmap.0.as_slice(), &ALL_4_HEIGHTMAP[..]);
     }
 
+    #[test]
+    fn test_biomes() {
+        let mut chunk = Chunk::new(0, 0);
+        assert_eq!(chunk.biome(0, 0, 0), Biome::Plains);
+
+        chunk.set_biome(5, 9, 6, Biome::Desert);
+        assert_eq!(chunk.biome(4, 8, 4), Biome::Desert);
+        assert_eq!(chunk.biome(7, 11, 7), Biome::Desert);
+        assert_eq!(chunk.biome(8, 8, 4), Biome::Plains);
+        assert_eq!(chunk.biome(4, 12, 4), Biome::Plains);
+        assert_eq!(chunk.biome(4, 7, 4), Biome::Plains);
+
+        chunk.set_column_biome(15, 15, Biome::Forest);
+        assert_eq!(chunk.biome(12, 0, 12), Biome::Forest);
+        assert_eq!(chunk.biome(15, 255, 15), Biome::Forest);
+        assert_eq!(chunk.biome(11, 255, 15), Biome::Plains);
+
+        chunk.fill_biome(Biome::Ocean);
+        assert_eq!(chunk.biome(4, 8, 4), Biome::Ocean);
+        assert_eq!(chunk.biome(15, 255, 15), Biome::Ocean);
+    }
+
+    #[test]
+    fn test_block_entities() {
+        let mut chunk = Chunk::new(0, 0);
+        chunk.set_block(1, 2, 3, Block::Chest);
+        assert_eq!(chunk.block_entity(1, 2, 3).unwrap().id(), "minecraft:chest");
+        chunk.set_block(1, 2, 3, Block::TrappedChest);
+        assert_eq!(
+            chunk.block_entity(1, 2, 3).unwrap().id(),
+            "minecraft:trapped_chest"
+        );
+
+        chunk.set_block(4, 5, 6, Block::OakSign);
+        if let Some(BlockEntity::Sign(sign)) = chunk.block_entity_mut(4, 5, 6) {
+            sign.set_lines(["Hello", "", "", ""]);
+        }
+        // A rotated sign keeps its text.
+        chunk.set_block(4, 5, 6, Block::OakWallSign);
+        match chunk.block_entity(4, 5, 6) {
+            Some(BlockEntity::Sign(sign)) => assert_eq!(sign.lines()[0], r#"{"text":"Hello"}"#),
+            entity => panic!("unexpected block entity: {:?}", entity),
+        }
+        assert_eq!(chunk.block_entities().count(), 2);
+
+        chunk.set_block(1, 2, 3, Block::Air);
+        assert!(chunk.block_entity(1, 2, 3).is_none());
+        chunk.set_block_entity(7, 7, 7, BlockEntity::new(Block::Chest).unwrap());
+        assert!(chunk.block_entity(7, 7, 7).is_none());
+        assert_eq!(chunk.block_entities().count(), 1);
+    }
+
+    #[async_test]
+    async fn test_send_packet() -> Result<()> {
+        let mut chunk = Chunk::new(2, -1);
+        chunk.set_block(1, 2, 3, Block::Stone);
+        chunk.set_block(4, 5, 6, Block::OakSign);
+
+        let mut buffer = Vec::new();
+        chunk.send_packet(&mut buffer).await?;
+        let size = Chunk::PACKET_ID.size() + chunk.size();
+        assert_eq!(buffer.len(), (size.size() + size).0 as usize);
+        let mut prefix = Vec::new();
+        size.send(&mut prefix).await?;
+        Chunk::PACKET_ID.send(&mut prefix).await?;
+        assert!(buffer.starts_with(&prefix));
+        Ok(())
+    }
+
     #[test]
     fn test_chunk_section() {
         let section = ChunkSection::new();
@@ -702,7 +918,7 @@ mod tests {
                 (i % 16) as u8,
                 (i / 256) as u8,
                 (i / 16) as u8,
-                Block::from(i),
+                Block::from_id(i).unwrap(),
             );
         }
     }
@@ -717,7 +933,7 @@ mod tests {
         for i in (start)..(start + n) {
             assert_eq!(
                 section.get((i % 16) as u8, (i / 256) as u8, (i / 16) as u8),
-                Block::from(i)
+                Block::from_id(i).unwrap()
             );
         }
     }
@@ -0,0 +1,53 @@
+use crate::packets::play::SkinParts;
+use crate::types::{self, Receive, TAsyncRead, VarInt};
+use anyhow::Result;
+
+/// Sent by the client after joining and when its options change.
+#[derive(Debug)]
+pub struct ClientSettings {
+    pub locale: String,
+    pub view_distance: i8,
+    pub chat_mode: VarInt,
+    pub chat_colors: bool,
+    pub skin_parts: SkinParts,
+    /// 0 for the left hand, 1 for the right hand.
+    pub main_hand: VarInt,
+}
+
+impl ClientSettings {
+    pub const PACKET_ID: VarInt = VarInt(0x05);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for ClientSettings {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let locale: types::String = reader.receive().await?;
+        Ok(Self {
+            locale: locale.to_string(),
+            view_distance: reader.receive().await?,
+            chat_mode: reader.receive().await?,
+            chat_colors: reader.receive().await?,
+            skin_parts: SkinParts(reader.receive().await?),
+            main_hand: reader.receive().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn read_client_settings() -> Result<()> {
+        let mut data = vec![5];
+        data.extend(b"en_us");
+        data.extend(&[12, 0, 1, 0x7F, 1]);
+        let settings: ClientSettings = Cursor::new(data).receive().await?;
+        assert_eq!(settings.locale, "en_us");
+        assert_eq!(settings.view_distance, 12);
+        assert_eq!(settings.skin_parts, SkinParts::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::types::{self, Receive, TAsyncRead, VarInt};
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    StartSneaking,
+    StopSneaking,
+    LeaveBed,
+    StartSprinting,
+    StopSprinting,
+    StartHorseJump,
+    StopHorseJump,
+    OpenHorseInventory,
+    StartFlyingWithElytra,
+}
+
+/// Sent by the client when the player starts or stops sneaking or sprinting.
+#[derive(Debug)]
+pub struct EntityAction {
+    pub id: VarInt,
+    pub action: Action,
+    /// The strength of a horse jump, from 0 to 100.
+    pub jump_boost: VarInt,
+}
+
+impl EntityAction {
+    pub const PACKET_ID: VarInt = VarInt(0x1B);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for EntityAction {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let id = reader.receive().await?;
+        let action: VarInt = reader.receive().await?;
+        let action = match *action {
+            0 => Action::StartSneaking,
+            1 => Action::StopSneaking,
+            2 => Action::LeaveBed,
+            3 => Action::StartSprinting,
+            4 => Action::StopSprinting,
+            5 => Action::StartHorseJump,
+            6 => Action::StopHorseJump,
+            7 => Action::OpenHorseInventory,
+            8 => Action::StartFlyingWithElytra,
+            action => return Err(anyhow!("invalid entity action: {}", action)),
+        };
+        let jump_boost = reader.receive().await?;
+        Ok(Self {
+            id,
+            action,
+            jump_boost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn read_entity_action() -> Result<()> {
+        let action: EntityAction = Cursor::new(vec![5, 3, 0]).receive().await?;
+        assert_eq!(*action.id, 5);
+        assert_eq!(action.action, Action::StartSprinting);
+
+        let invalid: Result<EntityAction> = Cursor::new(vec![5, 9, 0]).receive().await;
+        assert!(invalid.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use crate::packets::play::Hand;
+use crate::types::{self, Receive, TAsyncRead, VarInt};
+use anyhow::Result;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Animation {
+    SwingMainArm = 0,
+    TakeDamage,
+    LeaveBed,
+    SwingOffhand,
+    CriticalEffect,
+    MagicCriticalEffect,
+}
+
+impl From<Hand> for Animation {
+    fn from(hand: Hand) -> Self {
+        match hand {
+            Hand::Main => Animation::SwingMainArm,
+            Hand::Off => Animation::SwingOffhand,
+        }
+    }
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct EntityAnimation {
+    id: VarInt,
+    animation: u8,
+}
+crate::impl_packet!(EntityAnimation, 0x06);
+
+impl EntityAnimation {
+    pub fn new(id: VarInt, animation: Animation) -> Self {
+        Self {
+            id,
+            animation: animation as u8,
+        }
+    }
+}
+
+/// Sent by the client when the player swings an arm.
+#[derive(Debug)]
+pub struct InAnimation(pub Hand);
+
+impl InAnimation {
+    pub const PACKET_ID: VarInt = VarInt(0x2A);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for InAnimation {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(Self(reader.receive().await?))
+    }
+}
+
+/// The events of living entities the client plays, e.g. their hurt animation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Status {
+    Hurt = 2,
+    Death = 3,
+    ShieldBlock = 29,
+    ShieldBreak = 30,
+    TotemOfUndying = 35,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct EntityStatus {
+    id: i32,
+    status: i8,
+}
+crate::impl_packet!(EntityStatus, 0x1C);
+
+impl EntityStatus {
+    pub fn new(id: VarInt, status: Status) -> Self {
+        Self {
+            id: *id,
+            status: status as i8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Send, Size};
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_animation() -> Result<()> {
+        let InAnimation(hand) = Cursor::new(vec![1]).receive().await?;
+        let animation = EntityAnimation::new(VarInt(4), hand.into());
+        let mut buffer = Vec::new();
+        animation.send(&mut buffer).await?;
+        assert_eq!(buffer, [4, 3]);
+
+        let status = EntityStatus::new(VarInt(4), Status::Hurt);
+        let mut buffer = Vec::new();
+        status.send(&mut buffer).await?;
+        assert_eq!(buffer, [0, 0, 0, 4, 2]);
+        assert_eq!(*status.size(), 5);
+        Ok(())
+    }
+}
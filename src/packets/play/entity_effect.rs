@@ -0,0 +1,110 @@
+use crate::types::VarInt;
+
+/// The status effects, e.g. given by potions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Effect {
+    Speed = 1,
+    Slowness,
+    Haste,
+    MiningFatigue,
+    Strength,
+    InstantHealth,
+    InstantDamage,
+    JumpBoost,
+    Nausea,
+    Regeneration,
+    Resistance,
+    FireResistance,
+    WaterBreathing,
+    Invisibility,
+    Blindness,
+    NightVision,
+    Hunger,
+    Weakness,
+    Poison,
+    Wither,
+    HealthBoost,
+    Absorption,
+    Saturation,
+    Glowing,
+    Levitation,
+    Luck,
+    BadLuck,
+    SlowFalling,
+    ConduitPower,
+    DolphinsGrace,
+    BadOmen,
+    HeroOfTheVillage,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct EntityEffect {
+    id: VarInt,
+    effect: i8,
+    amplifier: i8,
+    duration: VarInt,
+    flags: i8,
+}
+crate::impl_packet!(EntityEffect, 0x5A);
+
+impl EntityEffect {
+    /// Effects given by beacons and conduits.
+    pub const AMBIENT: i8 = 0x01;
+    pub const SHOW_PARTICLES: i8 = 0x02;
+    pub const SHOW_ICON: i8 = 0x04;
+
+    /// An effect showing its particles and icon, `amplifier` is the level of the effect minus
+    /// one and `ticks` its duration.
+    pub fn new(id: VarInt, effect: Effect, amplifier: u8, ticks: u32) -> Self {
+        Self {
+            id,
+            effect: effect as i8,
+            amplifier: amplifier as i8,
+            duration: VarInt(ticks.min(i32::MAX as u32) as i32),
+            flags: Self::SHOW_PARTICLES | Self::SHOW_ICON,
+        }
+    }
+
+    pub fn set_flags(&mut self, flags: i8) {
+        self.flags = flags;
+    }
+
+    pub fn with_flags(mut self, flags: i8) -> Self {
+        self.set_flags(flags);
+        self
+    }
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct RemoveEntityEffect {
+    id: VarInt,
+    effect: i8,
+}
+crate::impl_packet!(RemoveEntityEffect, 0x39);
+
+impl RemoveEntityEffect {
+    pub fn new(id: VarInt, effect: Effect) -> Self {
+        Self {
+            id,
+            effect: effect as i8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Send;
+    use anyhow::Result;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_entity_effect() -> Result<()> {
+        let effect = EntityEffect::new(VarInt(1), Effect::HeroOfTheVillage, 1, 200)
+            .with_flags(EntityEffect::AMBIENT);
+        let mut buffer = Vec::new();
+        effect.send(&mut buffer).await?;
+        assert_eq!(buffer, [1, 32, 1, 0xC8, 0x01, 0x01]);
+        Ok(())
+    }
+}
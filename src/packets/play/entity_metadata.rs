@@ -0,0 +1,358 @@
+use crate::packets::play::{Block, Face, ItemStack};
+use crate::types::chat::Chat;
+use crate::types::{self, BlockPosition, Send, Size, TAsyncWrite, VarInt};
+use anyhow::Result;
+use nbt::Blob;
+use uuid::Uuid;
+
+/// A value of the entity metadata, sent with its type id.
+#[derive(Debug)]
+pub enum Metadata {
+    Byte(i8),
+    VarInt(VarInt),
+    Float(f32),
+    String(types::String),
+    Chat(Chat),
+    OptChat(Option<Chat>),
+    Slot(Option<ItemStack>),
+    Boolean(bool),
+    /// Pitch, yaw and roll, in degrees.
+    Rotation(f32, f32, f32),
+    Position(BlockPosition),
+    OptPosition(Option<BlockPosition>),
+    Direction(Face),
+    OptUuid(Option<Uuid>),
+    OptBlock(Option<Block>),
+    /// A NBT compound, already encoded.
+    Nbt(Vec<u8>),
+    /// The id of a particle without extra data.
+    Particle(VarInt),
+    VillagerData {
+        kind: VarInt,
+        profession: VarInt,
+        level: VarInt,
+    },
+    OptVarInt(Option<VarInt>),
+    Pose(Pose),
+}
+
+/// The pose of an entity, which changes its hitbox.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Pose {
+    Standing = 0,
+    FallFlying,
+    Sleeping,
+    Swimming,
+    SpinAttack,
+    Sneaking,
+    Dying,
+}
+
+impl Metadata {
+    fn type_id(&self) -> VarInt {
+        VarInt(match self {
+            Metadata::Byte(_) => 0,
+            Metadata::VarInt(_) => 1,
+            Metadata::Float(_) => 2,
+            Metadata::String(_) => 3,
+            Metadata::Chat(_) => 4,
+            Metadata::OptChat(_) => 5,
+            Metadata::Slot(_) => 6,
+            Metadata::Boolean(_) => 7,
+            Metadata::Rotation(..) => 8,
+            Metadata::Position(_) => 9,
+            Metadata::OptPosition(_) => 10,
+            Metadata::Direction(_) => 11,
+            Metadata::OptUuid(_) => 12,
+            Metadata::OptBlock(_) => 13,
+            Metadata::Nbt(_) => 14,
+            Metadata::Particle(_) => 15,
+            Metadata::VillagerData { .. } => 16,
+            Metadata::OptVarInt(_) => 17,
+            Metadata::Pose(_) => 18,
+        })
+    }
+
+    pub fn nbt(nbt: &Blob) -> Result<Self> {
+        let mut data = Vec::new();
+        nbt.to_writer(&mut data)?;
+        Ok(Metadata::Nbt(data))
+    }
+
+    /// Absent blocks, ids and positions are sent as 0 or as a false boolean prefix.
+    fn opt_block(block: &Option<Block>) -> VarInt {
+        VarInt(block.map_or(0, |block| block.id() as i32))
+    }
+
+    fn opt_var_int(value: &Option<VarInt>) -> VarInt {
+        VarInt(value.map_or(0, |value| *value + 1))
+    }
+}
+
+impl Size for Metadata {
+    fn size(&self) -> VarInt {
+        match self {
+            Metadata::Byte(value) => value.size(),
+            Metadata::VarInt(value) | Metadata::Particle(value) => value.size(),
+            Metadata::Float(value) => value.size(),
+            Metadata::String(value) => value.size(),
+            Metadata::Chat(chat) => chat.size(),
+            Metadata::OptChat(chat) => true.size() + chat.size(),
+            Metadata::Slot(item) => true.size() + item.size(),
+            Metadata::Boolean(value) => value.size(),
+            Metadata::Rotation(..) => VarInt(12),
+            Metadata::Position(position) => position.size(),
+            Metadata::OptPosition(position) => true.size() + position.size(),
+            Metadata::Direction(face) => VarInt(*face as i32).size(),
+            Metadata::OptUuid(uuid) => true.size() + uuid.size(),
+            Metadata::OptBlock(block) => Self::opt_block(block).size(),
+            Metadata::Nbt(data) => data.size(),
+            Metadata::VillagerData {
+                kind,
+                profession,
+                level,
+            } => kind.size() + profession.size() + level.size(),
+            Metadata::OptVarInt(value) => Self::opt_var_int(value).size(),
+            Metadata::Pose(pose) => VarInt(*pose as i32).size(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Send for Metadata {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            Metadata::Byte(value) => value.send(writer).await,
+            Metadata::VarInt(value) | Metadata::Particle(value) => value.send(writer).await,
+            Metadata::Float(value) => value.send(writer).await,
+            Metadata::String(value) => value.send(writer).await,
+            Metadata::Chat(chat) => chat.send(writer).await,
+            Metadata::OptChat(chat) => {
+                chat.is_some().send(writer).await?;
+                chat.send(writer).await
+            }
+            Metadata::Slot(item) => {
+                item.is_some().send(writer).await?;
+                item.send(writer).await
+            }
+            Metadata::Boolean(value) => value.send(writer).await,
+            Metadata::Rotation(pitch, yaw, roll) => {
+                pitch.send(writer).await?;
+                yaw.send(writer).await?;
+                roll.send(writer).await
+            }
+            Metadata::Position(position) => position.send(writer).await,
+            Metadata::OptPosition(position) => {
+                position.is_some().send(writer).await?;
+                position.send(writer).await
+            }
+            Metadata::Direction(face) => VarInt(*face as i32).send(writer).await,
+            Metadata::OptUuid(uuid) => {
+                uuid.is_some().send(writer).await?;
+                uuid.send(writer).await
+            }
+            Metadata::OptBlock(block) => Self::opt_block(block).send(writer).await,
+            Metadata::Nbt(data) => data.send(writer).await,
+            Metadata::VillagerData {
+                kind,
+                profession,
+                level,
+            } => {
+                kind.send(writer).await?;
+                profession.send(writer).await?;
+                level.send(writer).await
+            }
+            Metadata::OptVarInt(value) => Self::opt_var_int(value).send(writer).await,
+            Metadata::Pose(pose) => VarInt(*pose as i32).send(writer).await,
+        }
+    }
+}
+
+/// The bit mask of the states shared by all entities, at the index 0 of their metadata.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct EntityFlags(u8);
+
+impl EntityFlags {
+    pub const ON_FIRE: u8 = 0x01;
+    pub const SNEAKING: u8 = 0x02;
+    pub const SPRINTING: u8 = 0x08;
+    pub const SWIMMING: u8 = 0x10;
+    pub const INVISIBLE: u8 = 0x20;
+    pub const GLOWING: u8 = 0x40;
+    pub const FLYING_WITH_ELYTRA: u8 = 0x80;
+
+    pub fn contains(self, flag: u8) -> bool {
+        self.0 & flag != 0
+    }
+
+    pub fn set(&mut self, flag: u8, value: bool) {
+        if value {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+}
+
+/// The parts of a player skin which are displayed, the second layers of the skin.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SkinParts(pub u8);
+
+impl SkinParts {
+    pub const CAPE: u8 = 0x01;
+    pub const JACKET: u8 = 0x02;
+    pub const LEFT_SLEEVE: u8 = 0x04;
+    pub const RIGHT_SLEEVE: u8 = 0x08;
+    pub const LEFT_PANTS_LEG: u8 = 0x10;
+    pub const RIGHT_PANTS_LEG: u8 = 0x20;
+    pub const HAT: u8 = 0x40;
+}
+
+impl Default for SkinParts {
+    /// Clients display every part by default.
+    fn default() -> Self {
+        Self(0x7F)
+    }
+}
+
+#[derive(Debug)]
+pub struct EntityMetadata {
+    id: VarInt,
+    entries: Vec<(u8, Metadata)>,
+}
+
+impl EntityMetadata {
+    const FLAGS: u8 = 0;
+    const CUSTOM_NAME: u8 = 2;
+    const CUSTOM_NAME_VISIBLE: u8 = 3;
+    const NO_GRAVITY: u8 = 5;
+    const POSE: u8 = 6;
+    const ITEM: u8 = 7;
+    const HEALTH: u8 = 8;
+    const SKIN_PARTS: u8 = 16;
+
+    pub fn new(id: VarInt) -> Self {
+        Self {
+            id,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set the value at `index`, see the metadata indexes of each entity type.
+    pub fn with(mut self, index: u8, value: Metadata) -> Self {
+        self.entries.push((index, value));
+        self
+    }
+
+    pub fn with_flags(self, flags: EntityFlags) -> Self {
+        self.with(Self::FLAGS, Metadata::Byte(flags.0 as i8))
+    }
+
+    /// The name displayed above the entity, always visible if `visible` is set, otherwise
+    /// only when looking at the entity.
+    pub fn with_custom_name(self, name: Option<Chat>, visible: bool) -> Self {
+        self.with(Self::CUSTOM_NAME, Metadata::OptChat(name))
+            .with(Self::CUSTOM_NAME_VISIBLE, Metadata::Boolean(visible))
+    }
+
+    pub fn with_no_gravity(self, no_gravity: bool) -> Self {
+        self.with(Self::NO_GRAVITY, Metadata::Boolean(no_gravity))
+    }
+
+    pub fn with_pose(self, pose: Pose) -> Self {
+        self.with(Self::POSE, Metadata::Pose(pose))
+    }
+
+    /// The health of a living entity, the client plays the death animation at 0.
+    pub fn with_health(self, health: f32) -> Self {
+        self.with(Self::HEALTH, Metadata::Float(health))
+    }
+
+    /// The displayed parts of the skin of a player.
+    pub fn with_skin_parts(self, parts: SkinParts) -> Self {
+        self.with(Self::SKIN_PARTS, Metadata::Byte(parts.0 as i8))
+    }
+
+    /// The metadata of a dropped item entity.
+    pub fn item(id: VarInt, item: ItemStack) -> Self {
+        Self::new(id).with(Self::ITEM, Metadata::Slot(Some(item)))
+    }
+}
+crate::impl_packet!(EntityMetadata, 0x44);
+
+impl Size for EntityMetadata {
+    fn size(&self) -> VarInt {
+        let mut size = self.id.size() + VarInt(1);
+        for (_, value) in &self.entries {
+            size = size + VarInt(1) + value.type_id().size() + value.size();
+        }
+        size
+    }
+}
+
+#[async_trait::async_trait]
+impl Send for EntityMetadata {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        self.id.send(writer).await?;
+        for (index, value) in &self.entries {
+            index.send(writer).await?;
+            value.type_id().send(writer).await?;
+            value.send(writer).await?;
+        }
+        0xFFu8.send(writer).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Item;
+    use futures_await_test::async_test;
+
+    async fn encode(metadata: &EntityMetadata) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        metadata.send(&mut buffer).await?;
+        assert_eq!(*metadata.size() as usize, buffer.len());
+        Ok(buffer)
+    }
+
+    #[async_test]
+    async fn test_item_metadata() -> Result<()> {
+        let metadata = EntityMetadata::item(VarInt(3), ItemStack::new(Item::Stone, 2));
+        assert_eq!(encode(&metadata).await?, [3, 7, 6, 1, 1, 2, 0, 0xFF]);
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_player_metadata() -> Result<()> {
+        let mut flags = EntityFlags::default();
+        flags.set(EntityFlags::SNEAKING, true);
+        flags.set(EntityFlags::SPRINTING, true);
+        flags.set(EntityFlags::SPRINTING, false);
+        assert!(flags.contains(EntityFlags::SNEAKING));
+        let metadata = EntityMetadata::new(VarInt(1))
+            .with_flags(flags)
+            .with_pose(Pose::Sneaking)
+            .with_skin_parts(SkinParts(SkinParts::HAT | SkinParts::CAPE));
+        assert_eq!(
+            encode(&metadata).await?,
+            [1, 0, 0, 0x02, 6, 18, 5, 16, 0, 0x41, 0xFF]
+        );
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_optional_values() -> Result<()> {
+        let metadata = EntityMetadata::new(VarInt(1))
+            .with_custom_name(None, false)
+            .with(12, Metadata::OptUuid(None))
+            .with(13, Metadata::OptBlock(Some(Block::Stone)))
+            .with(14, Metadata::OptVarInt(Some(VarInt(0))))
+            .with(15, Metadata::Rotation(0., 0., 0.));
+        let mut expected = vec![1, 2, 5, 0, 3, 7, 0, 12, 12, 0, 13, 13, 1, 14, 17, 1, 15, 8];
+        expected.extend(&[0; 12]);
+        expected.push(0xFF);
+        assert_eq!(encode(&metadata).await?, expected);
+        Ok(())
+    }
+}
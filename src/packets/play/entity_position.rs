@@ -1,5 +1,5 @@
 use crate::game::player::Player;
-use crate::types::{PositionDelta, VarInt};
+use crate::types::{EntityPosition, PositionDelta, VarInt};
 
 #[derive(Debug, macro_derive::Size, macro_derive::Send)]
 pub struct OutPosition {
@@ -12,9 +12,9 @@ pub struct OutPosition {
 crate::impl_packet!(OutPosition, 0x29);
 
 impl OutPosition {
-    pub fn from(player: &Player, delta: &PositionDelta, on_ground: bool) -> Self {
+    pub fn new(id: VarInt, delta: &PositionDelta, on_ground: bool) -> Self {
         Self {
-            id: player.id(),
+            id,
             delta_x: delta.x,
             delta_y: delta.y,
             delta_z: delta.z,
@@ -87,3 +87,30 @@ impl OutEntityHeadLook {
         }
     }
 }
+
+/// Move an entity to an absolute position, when it moved too far for a relative move.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct EntityTeleport {
+    id: VarInt,
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: u8,
+    pitch: u8,
+    on_ground: bool,
+}
+crate::impl_packet!(EntityTeleport, 0x57);
+
+impl EntityTeleport {
+    pub fn new(id: VarInt, position: &EntityPosition, on_ground: bool) -> Self {
+        Self {
+            id,
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            yaw: position.x_angle,
+            pitch: position.z_angle,
+            on_ground,
+        }
+    }
+}
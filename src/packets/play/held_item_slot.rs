@@ -1,4 +1,5 @@
 use crate::impl_packet;
+use crate::types::{self, Receive, TAsyncRead, VarInt};
 use anyhow::{ensure, Result};
 
 #[derive(Debug, Default, macro_derive::Size, macro_derive::Send)]
@@ -11,3 +12,20 @@ impl HeldItemSlot {
     }
 }
 impl_packet!(HeldItemSlot, 0x40);
+
+/// Sent by the client when the selected hotbar slot changes.
+#[derive(Debug)]
+pub struct InHeldItemSlot(pub u8);
+
+impl InHeldItemSlot {
+    pub const PACKET_ID: VarInt = VarInt(0x23);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for InHeldItemSlot {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let slot: i16 = reader.receive().await?;
+        ensure!((0..=8).contains(&slot), "invalid slot index");
+        Ok(Self(slot as u8))
+    }
+}
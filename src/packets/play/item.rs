@@ -0,0 +1,208 @@
+use crate::packets::play::{Block, BlockKind};
+use crate::types::{self, Receive, TAsyncRead, VarInt};
+use anyhow::{anyhow, Error, Result};
+use futures::AsyncReadExt;
+use nbt::{Blob, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+include!(concat!(env!("OUT_DIR"), "/items.rs"));
+
+struct ItemData {
+    item: Item,
+    name: &'static str,
+    max_stack_size: u8,
+}
+
+/// Items placing a block which doesn't have the same name.
+const PLACED_BLOCKS: [(Item, BlockKind); 10] = [
+    (Item::Redstone, BlockKind::RedstoneWire),
+    (Item::String, BlockKind::Tripwire),
+    (Item::WheatSeeds, BlockKind::Wheat),
+    (Item::BeetrootSeeds, BlockKind::Beetroots),
+    (Item::Carrot, BlockKind::Carrots),
+    (Item::Potato, BlockKind::Potatoes),
+    (Item::MelonSeeds, BlockKind::MelonStem),
+    (Item::PumpkinSeeds, BlockKind::PumpkinStem),
+    (Item::CocoaBeans, BlockKind::Cocoa),
+    (Item::SweetBerries, BlockKind::SweetBerryBush),
+];
+
+impl Item {
+    fn data(self) -> &'static ItemData {
+        &ITEMS[self as usize]
+    }
+
+    /// Find an item from its protocol id.
+    pub fn from_id(id: u16) -> Option<Self> {
+        ITEMS.get(id as usize).map(|data| data.item)
+    }
+
+    /// The protocol id of the item.
+    pub fn id(self) -> u16 {
+        self as u16
+    }
+
+    pub fn name(self) -> &'static str {
+        self.data().name
+    }
+
+    pub fn max_stack_size(self) -> u8 {
+        self.data().max_stack_size
+    }
+
+    /// The type of block placed by this item, if any.
+    pub fn block(self) -> Option<BlockKind> {
+        if self == Item::Air {
+            return None;
+        }
+        PLACED_BLOCKS
+            .iter()
+            .find(|(item, _)| *item == self)
+            .map(|(_, kind)| *kind)
+            .or_else(|| BlockKind::from_name(self.name()))
+    }
+}
+
+impl TryFrom<u16> for Item {
+    type Error = Error;
+
+    fn try_from(id: u16) -> Result<Self> {
+        Self::from_id(id).ok_or_else(|| anyhow!("invalid item id: {}", id))
+    }
+}
+
+impl BlockKind {
+    /// The item placing this type of block, if any.
+    pub fn item(self) -> Option<Item> {
+        PLACED_BLOCKS
+            .iter()
+            .find(|(_, kind)| *kind == self)
+            .map(|(item, _)| *item)
+            .or_else(|| Item::from_name(self.name()))
+    }
+}
+
+/// A stack of items, as found in inventory slots.
+#[derive(Debug, Clone, Eq, PartialEq, macro_derive::Size, macro_derive::Send)]
+pub struct ItemStack {
+    id: VarInt,
+    count: i8,
+    nbt: Vec<u8>,
+}
+
+impl ItemStack {
+    /// A stack without NBT data.
+    pub fn new(item: Item, count: i8) -> Self {
+        Self {
+            id: VarInt(item.id() as i32),
+            count,
+            // A lone TAG_End: no NBT data.
+            nbt: vec![0],
+        }
+    }
+
+    pub fn item(&self) -> Item {
+        Item::from_id(*self.id as u16).unwrap()
+    }
+
+    pub fn count(&self) -> i8 {
+        self.count
+    }
+
+    pub fn set_count(&mut self, count: i8) {
+        self.count = count;
+    }
+
+    /// Whether the items are the same and can be in the same stack.
+    pub fn stacks_with(&self, other: &ItemStack) -> bool {
+        self.id == other.id && self.nbt == other.nbt
+    }
+
+    /// Set the NBT data of the stack, e.g. its display name or enchantments.
+    pub fn with_nbt(mut self, nbt: &Blob) -> Result<Self> {
+        self.nbt.clear();
+        nbt.to_writer(&mut self.nbt)?;
+        Ok(self)
+    }
+
+    /// The NBT data of the stack, None if it has none.
+    pub fn nbt(&self) -> Result<Option<HashMap<String, Value>>> {
+        match self.nbt.first() {
+            Some(0x00) => return Ok(None),
+            Some(0x0a) if self.nbt.len() >= 3 => {}
+            _ => return Err(anyhow!("missing NBT root compound")),
+        }
+        // Skip the name of the root compound.
+        let name_length = u16::from_be_bytes([self.nbt[1], self.nbt[2]]) as usize;
+        let mut reader = self
+            .nbt
+            .get(3 + name_length..)
+            .ok_or_else(|| anyhow!("invalid NBT root compound"))?;
+        match Value::from_reader(0x0a, &mut reader)? {
+            Value::Compound(compound) => Ok(Some(compound)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The placed block, in its default state.
+    pub fn block(&self) -> Option<Block> {
+        self.item().block().map(Block::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for ItemStack {
+    /// Slots are always the last field of the packets sent by the client, the NBT data is kept
+    /// as raw bytes until the end of the packet.
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let id: VarInt = reader.receive().await?;
+        Item::try_from(u16::try_from(*id)?)?;
+        let count = reader.receive().await?;
+        let mut nbt = Vec::new();
+        reader.read_to_end(&mut nbt).await?;
+        Ok(Self { id, count, nbt })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(Item::Air.id(), 0);
+        assert_eq!(Item::Stone.id(), 1);
+        assert_eq!(Item::from_id(1), Some(Item::Stone));
+        assert_eq!(Item::from_name("minecraft:diamond"), Some(Item::Diamond));
+        assert!(Item::try_from(u16::MAX).is_err());
+        assert_eq!(Item::Stone.max_stack_size(), 64);
+        assert_eq!(Item::EnderPearl.max_stack_size(), 16);
+        assert_eq!(Item::DiamondSword.max_stack_size(), 1);
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(Item::Stone.block(), Some(BlockKind::Stone));
+        assert_eq!(Item::OakStairs.block(), Some(BlockKind::OakStairs));
+        assert_eq!(Item::Redstone.block(), Some(BlockKind::RedstoneWire));
+        assert_eq!(Item::Air.block(), None);
+        assert_eq!(Item::Stick.block(), None);
+        assert_eq!(BlockKind::Wheat.item(), Some(Item::WheatSeeds));
+        assert_eq!(BlockKind::Water.item(), None);
+    }
+
+    #[test]
+    fn test_nbt() -> Result<()> {
+        let stone = ItemStack::new(Item::Stone, 1);
+        assert_eq!(stone.nbt()?, None);
+
+        let mut blob = Blob::new();
+        blob.insert("Damage", 3)?;
+        let damaged = ItemStack::new(Item::Stone, 1).with_nbt(&blob)?;
+        assert_eq!(damaged.nbt()?.unwrap()["Damage"], Value::Int(3));
+        assert!(!damaged.stacks_with(&stone));
+        assert!(stone.stacks_with(&ItemStack::new(Item::Stone, 5)));
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
 use crate::types::{self, Size, TAsyncWrite, VarInt};
 use crate::{impl_packet, impl_send, impl_size};
-use anyhow::Result;
+use anyhow::{anyhow, Error, Result};
+use sha2::{Digest, Sha256};
+use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Display, Formatter};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(macro_derive::Size, macro_derive::Send, Debug)]
 pub struct JoinGame {
@@ -21,10 +22,7 @@ impl_packet!(JoinGame, 0x26);
 impl Default for JoinGame {
     fn default() -> Self {
         JoinGame {
-            id: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i32,
+            id: 0,
             game_mode: GameMode::Creative,
             dimension: Dimension::Overworld,
             hash_seed: 0,
@@ -37,8 +35,17 @@ impl Default for JoinGame {
     }
 }
 
+impl JoinGame {
+    /// The world seed as sent to the client: the first 8 bytes of its SHA-256 hash, used
+    /// client-side for biome noise.
+    pub fn hash_seed(seed: i64) -> i64 {
+        let hash = Sha256::digest(&seed.to_le_bytes());
+        i64::from_le_bytes(hash[..8].try_into().unwrap())
+    }
+}
+
 // TODO: Move to own file.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u8)]
 pub enum GameMode {
     Survival = 0,
@@ -49,6 +56,20 @@ pub enum GameMode {
 impl_size!(GameMode, 1);
 impl_send!(GameMode as u8);
 
+impl TryFrom<u8> for GameMode {
+    type Error = Error;
+
+    fn try_from(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(GameMode::Survival),
+            1 => Ok(GameMode::Creative),
+            2 => Ok(GameMode::Adventure),
+            3 => Ok(GameMode::Spectator),
+            _ => Err(anyhow!("invalid game mode: {}", id)),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(i32)]
 pub enum Dimension {
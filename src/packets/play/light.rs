@@ -0,0 +1,514 @@
+use crate::packets::play::{Block, Chunk};
+use crate::types::{self, Size, TAsyncWrite, VarInt};
+use anyhow::Result;
+use futures::AsyncWriteExt;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::DerefMut;
+
+const MAX_LEVEL: u8 = 15;
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+const SECTION_BYTES: usize = SECTION_VOLUME / 2;
+const WORLD_HEIGHT: usize = 256;
+
+/// Sky and block light of a chunk, stored as one nibble per block.
+///
+/// It covers 18 sections: the 16 sections of the chunk plus one below and one above the world,
+/// as expected by the client.
+/// Light is computed per chunk, then spread across chunk borders with `spread`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkLight {
+    sky: Vec<LightSection>,
+    block: Vec<LightSection>,
+}
+
+impl ChunkLight {
+    pub const SECTIONS: usize = 18;
+
+    /// The light of an empty chunk: full sky light and no block light.
+    pub fn new() -> Self {
+        let mut sky = vec![LightSection::Uniform(MAX_LEVEL); Self::SECTIONS];
+        sky[0] = LightSection::Uniform(0);
+        Self {
+            sky,
+            block: vec![LightSection::Uniform(0); Self::SECTIONS],
+        }
+    }
+
+    /// Compute the light of a chunk.
+    ///
+    /// Sky light goes straight down from the sky to the highest block of the heightmap, then
+    /// both sky and block light (from emitting blocks) are propagated to neighbour blocks,
+    /// losing at least one level per block.
+    pub fn compute(chunk: &Chunk) -> Self {
+        let mut max_height = 0;
+        let mut sky = vec![0; 16 * 16 * WORLD_HEIGHT];
+        for z in 0..16 {
+            for x in 0..16 {
+                let height = match chunk.height(x, z) {
+                    0 if chunk.get_block(x, 0, z) == Block::Air => {
+                        for y in 0..WORLD_HEIGHT {
+                            sky[index(x, y, z)] = MAX_LEVEL;
+                        }
+                        continue;
+                    }
+                    height => height as usize,
+                };
+                max_height = max(max_height, height);
+
+                for y in height + 1..WORLD_HEIGHT {
+                    sky[index(x, y, z)] = MAX_LEVEL;
+                }
+                let mut level = MAX_LEVEL;
+                for y in (0..=height).rev() {
+                    level = level.saturating_sub(chunk.get_block(x, y as u16, z).light_filter());
+                    sky[index(x, y, z)] = level;
+                }
+            }
+        }
+
+        // Everything above the highest block is already at full light, only propagate below.
+        let mut queue = VecDeque::new();
+        for y in 0..=min(max_height + 1, WORLD_HEIGHT - 1) {
+            for z in 0..16 {
+                for x in 0..16 {
+                    if sky[index(x, y, z)] > 1 {
+                        queue.push_back((x, y, z));
+                    }
+                }
+            }
+        }
+        propagate(chunk, &mut sky, queue);
+
+        let mut block = vec![0; 16 * 16 * WORLD_HEIGHT];
+        let mut queue = VecDeque::new();
+        for section in 0..16 {
+            if !chunk.section_contains(section, |b| b.light_emission() > 0) {
+                continue;
+            }
+            for y in section * 16..(section + 1) * 16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let emission = chunk.get_block(x, y as u16, z).light_emission();
+                        if emission > 0 {
+                            block[index(x, y, z)] = emission;
+                            queue.push_back((x, y, z));
+                        }
+                    }
+                }
+            }
+        }
+        propagate(chunk, &mut block, queue);
+
+        Self {
+            sky: LightSection::from_levels(&sky, MAX_LEVEL),
+            block: LightSection::from_levels(&block, 0),
+        }
+    }
+
+    pub fn sky_light(&self, x: u8, y: u16, z: u8) -> u8 {
+        self.sky[y as usize / 16 + 1].get(x, (y % 16) as u8, z)
+    }
+
+    pub fn block_light(&self, x: u8, y: u16, z: u8) -> u8 {
+        self.block[y as usize / 16 + 1].get(x, (y % 16) as u8, z)
+    }
+
+    fn sky_mut(&mut self) -> &mut [LightSection] {
+        &mut self.sky
+    }
+
+    fn block_mut(&mut self) -> &mut [LightSection] {
+        &mut self.block
+    }
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn index(x: u8, y: usize, z: u8) -> usize {
+    y * 256 + z as usize * 16 + x as usize
+}
+
+fn propagate(chunk: &Chunk, levels: &mut [u8], mut queue: VecDeque<(u8, usize, u8)>) {
+    while let Some((x, y, z)) = queue.pop_front() {
+        let level = levels[index(x, y, z)];
+        if level <= 1 {
+            continue;
+        }
+
+        let mut neighbours = Vec::with_capacity(6);
+        if x > 0 {
+            neighbours.push((x - 1, y, z));
+        }
+        if x < 15 {
+            neighbours.push((x + 1, y, z));
+        }
+        if z > 0 {
+            neighbours.push((x, y, z - 1));
+        }
+        if z < 15 {
+            neighbours.push((x, y, z + 1));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1, z));
+        }
+        if y < WORLD_HEIGHT - 1 {
+            neighbours.push((x, y + 1, z));
+        }
+
+        for (n_x, n_y, n_z) in neighbours {
+            let filter = max(1, chunk.get_block(n_x, n_y as u16, n_z).light_filter());
+            let new_level = level.saturating_sub(filter);
+            let neighbour = &mut levels[index(n_x, n_y, n_z)];
+            if new_level > *neighbour {
+                *neighbour = new_level;
+                queue.push_back((n_x, n_y, n_z));
+            }
+        }
+    }
+}
+
+/// Spread the light of the `relit` chunks into their neighbours in `chunks` and the light of
+/// the neighbours into them, across the chunk borders.
+///
+/// Light only increases: the `relit` chunks are expected to be lit on their own (see
+/// `ChunkLight::compute`), and their neighbours not to hold light which came from them anymore.
+/// Returns the chunks whose light changed.
+pub fn spread<C: DerefMut<Target = Chunk>>(
+    chunks: &mut BTreeMap<(i32, i32), C>,
+    relit: &[(i32, i32)],
+) -> BTreeSet<(i32, i32)> {
+    let mut changed = spread_levels(chunks, relit, ChunkLight::sky_mut);
+    changed.extend(spread_levels(chunks, relit, ChunkLight::block_mut));
+    changed
+}
+
+/// Spread one type of light across the borders of the `relit` chunks, with world coordinates.
+fn spread_levels<C: DerefMut<Target = Chunk>>(
+    chunks: &mut BTreeMap<(i32, i32), C>,
+    relit: &[(i32, i32)],
+    sections: fn(&mut ChunkLight) -> &mut [LightSection],
+) -> BTreeSet<(i32, i32)> {
+    let level = |chunks: &mut BTreeMap<(i32, i32), C>, x: i32, y: usize, z: i32| {
+        let chunk = chunks.get_mut(&(x.div_euclid(16), z.div_euclid(16)))?;
+        let (x, z) = (x.rem_euclid(16) as u8, z.rem_euclid(16) as u8);
+        Some(sections(chunk.light_mut())[y / 16 + 1].get(x, (y % 16) as u8, z))
+    };
+
+    // Both sides of the borders between the relit chunks and their neighbours.
+    let mut queue = VecDeque::new();
+    for &(c_x, c_z) in relit {
+        for &(d_x, d_z) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            if !chunks.contains_key(&(c_x + d_x, c_z + d_z)) {
+                continue;
+            }
+            for i in 0..16 {
+                let (x, z) = match (d_x, d_z) {
+                    (-1, 0) => (c_x * 16, c_z * 16 + i),
+                    (1, 0) => (c_x * 16 + 15, c_z * 16 + i),
+                    (0, -1) => (c_x * 16 + i, c_z * 16),
+                    _ => (c_x * 16 + i, c_z * 16 + 15),
+                };
+                for y in 0..WORLD_HEIGHT {
+                    queue.push_back((x, y, z));
+                    queue.push_back((x + d_x, y, z + d_z));
+                }
+            }
+        }
+    }
+
+    let mut changed = BTreeSet::new();
+    while let Some((x, y, z)) = queue.pop_front() {
+        let level = match level(chunks, x, y, z) {
+            Some(level) if level > 1 => level,
+            _ => continue,
+        };
+
+        let mut neighbours = vec![(x - 1, y, z), (x + 1, y, z), (x, y, z - 1), (x, y, z + 1)];
+        if y > 0 {
+            neighbours.push((x, y - 1, z));
+        }
+        if y < WORLD_HEIGHT - 1 {
+            neighbours.push((x, y + 1, z));
+        }
+
+        for (n_x, n_y, n_z) in neighbours {
+            let position = (n_x.div_euclid(16), n_z.div_euclid(16));
+            let chunk = match chunks.get_mut(&position) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let (l_x, l_z) = (n_x.rem_euclid(16) as u8, n_z.rem_euclid(16) as u8);
+            let filter = max(1, chunk.get_block(l_x, n_y as u16, l_z).light_filter());
+            let new_level = level.saturating_sub(filter);
+            let section = &mut sections(chunk.light_mut())[n_y / 16 + 1];
+            if new_level > section.get(l_x, (n_y % 16) as u8, l_z) {
+                section.set(l_x, (n_y % 16) as u8, l_z, new_level);
+                changed.insert(position);
+                queue.push_back((n_x, n_y, n_z));
+            }
+        }
+    }
+    changed
+}
+
+#[derive(Debug, Clone)]
+enum LightSection {
+    Uniform(u8),
+    Nibbles(Vec<u8>),
+}
+
+impl LightSection {
+    /// Build the 18 sections from the levels of the 256 blocks high world. `outside` is used for
+    /// the sections below and above the world.
+    fn from_levels(levels: &[u8], outside: u8) -> Vec<Self> {
+        let mut sections = Vec::with_capacity(ChunkLight::SECTIONS);
+        sections.push(LightSection::Uniform(0));
+        for section in levels.chunks(SECTION_VOLUME) {
+            if section.iter().all(|level| *level == section[0]) {
+                sections.push(LightSection::Uniform(section[0]));
+                continue;
+            }
+
+            let nibbles = section
+                .chunks(2)
+                .map(|pair| pair[0] | (pair[1] << 4))
+                .collect();
+            sections.push(LightSection::Nibbles(nibbles));
+        }
+        sections.push(LightSection::Uniform(outside));
+        sections
+    }
+
+    fn get(&self, x: u8, y: u8, z: u8) -> u8 {
+        match self {
+            LightSection::Uniform(level) => *level,
+            LightSection::Nibbles(nibbles) => {
+                let index = index(x, y as usize, z);
+                (nibbles[index / 2] >> (4 * (index % 2))) & 0x0F
+            }
+        }
+    }
+
+    fn set(&mut self, x: u8, y: u8, z: u8, level: u8) {
+        if let LightSection::Uniform(current) = *self {
+            if current == level {
+                return;
+            }
+            *self = LightSection::Nibbles(vec![current | (current << 4); SECTION_BYTES]);
+        }
+        if let LightSection::Nibbles(nibbles) = self {
+            let index = index(x, y as usize, z);
+            let shift = 4 * (index % 2);
+            nibbles[index / 2] = (nibbles[index / 2] & !(0x0F << shift)) | (level << shift);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, LightSection::Uniform(0))
+    }
+}
+
+/// Sections are equal if they have the same levels, uniform or not.
+impl PartialEq for LightSection {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LightSection::Uniform(a), LightSection::Uniform(b)) => a == b,
+            (LightSection::Nibbles(a), LightSection::Nibbles(b)) => a == b,
+            (LightSection::Uniform(level), LightSection::Nibbles(nibbles))
+            | (LightSection::Nibbles(nibbles), LightSection::Uniform(level)) => {
+                nibbles.iter().all(|pair| *pair == level | (level << 4))
+            }
+        }
+    }
+}
+
+impl Size for LightSection {
+    fn size(&self) -> VarInt {
+        VarInt::new(SECTION_BYTES as i32).size() + VarInt::new(SECTION_BYTES as i32)
+    }
+}
+
+#[async_trait::async_trait]
+impl types::Send for LightSection {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        VarInt::new(SECTION_BYTES as i32).send(writer).await?;
+        match self {
+            LightSection::Uniform(level) => {
+                writer
+                    .write_all(&[level | (level << 4); SECTION_BYTES])
+                    .await?
+            }
+            LightSection::Nibbles(nibbles) => writer.write_all(nibbles).await?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct UpdateLight<'a> {
+    x: VarInt,
+    z: VarInt,
+    light: &'a ChunkLight,
+}
+crate::impl_packet!(UpdateLight<'_>, 0x25);
+
+impl<'a> UpdateLight<'a> {
+    pub fn new(chunk: &'a Chunk) -> Self {
+        Self::with_light(chunk.x, chunk.z, chunk.light())
+    }
+
+    /// The light of the chunk at `x` and `z`, e.g. a copy of its light.
+    pub fn with_light(x: i32, z: i32, light: &'a ChunkLight) -> Self {
+        Self {
+            x: VarInt::new(x),
+            z: VarInt::new(z),
+            light,
+        }
+    }
+
+    /// Bitmasks of the sections to send and of the sections which are completely dark.
+    fn masks(sections: &[LightSection]) -> (VarInt, VarInt) {
+        let (mut mask, mut empty_mask) = (0, 0);
+        for (i, section) in sections.iter().enumerate() {
+            if section.is_empty() {
+                empty_mask |= 1 << i;
+            } else {
+                mask |= 1 << i;
+            }
+        }
+        (VarInt::new(mask), VarInt::new(empty_mask))
+    }
+}
+
+impl Size for UpdateLight<'_> {
+    fn size(&self) -> VarInt {
+        let (sky_mask, empty_sky_mask) = Self::masks(&self.light.sky);
+        let (block_mask, empty_block_mask) = Self::masks(&self.light.block);
+
+        self.light
+            .sky
+            .iter()
+            .chain(self.light.block.iter())
+            .filter(|section| !section.is_empty())
+            .map(Size::size)
+            .fold(
+                self.x.size()
+                    + self.z.size()
+                    + sky_mask.size()
+                    + block_mask.size()
+                    + empty_sky_mask.size()
+                    + empty_block_mask.size(),
+                std::ops::Add::add,
+            )
+    }
+}
+
+#[async_trait::async_trait]
+impl types::Send for UpdateLight<'_> {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        let (sky_mask, empty_sky_mask) = Self::masks(&self.light.sky);
+        let (block_mask, empty_block_mask) = Self::masks(&self.light.block);
+
+        self.x.send(writer).await?;
+        self.z.send(writer).await?;
+        sky_mask.send(writer).await?;
+        block_mask.send(writer).await?;
+        empty_sky_mask.send(writer).await?;
+        empty_block_mask.send(writer).await?;
+        for section in self.light.sky.iter().chain(self.light.block.iter()) {
+            if !section.is_empty() {
+                section.send(writer).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sky_light() {
+        let mut chunk = Chunk::new(0, 0);
+        for z in 0..16 {
+            for x in 0..16 {
+                chunk.set_block(x, 0, z, Block::Bedrock);
+                chunk.set_block(x, 10, z, Block::Dirt);
+            }
+        }
+        // A hole in the roof.
+        chunk.set_block(8, 10, 8, Block::Air);
+        let light = ChunkLight::compute(&chunk);
+
+        assert_eq!(light.sky_light(0, 11, 0), 15);
+        assert_eq!(light.sky_light(0, 10, 0), 0);
+        assert_eq!(light.sky_light(0, 0, 0), 0);
+        assert_eq!(light.sky_light(8, 10, 8), 15);
+        assert_eq!(light.sky_light(8, 1, 8), 15);
+        assert_eq!(light.sky_light(9, 5, 8), 14);
+        assert_eq!(light.sky_light(10, 5, 10), 11);
+        assert_eq!(light.block_light(8, 5, 8), 0);
+    }
+
+    #[test]
+    fn test_block_light() {
+        let mut chunk = Chunk::new(0, 0);
+        chunk.set_block(4, 20, 4, Block::Lava);
+        chunk.set_block(5, 20, 4, Block::Dirt);
+        let light = ChunkLight::compute(&chunk);
+
+        assert_eq!(light.block_light(4, 20, 4), 15);
+        assert_eq!(light.block_light(4, 21, 4), 14);
+        assert_eq!(light.block_light(4, 17, 4), 12);
+        assert_eq!(light.block_light(5, 20, 4), 0);
+        assert_eq!(light.block_light(6, 20, 4), 11);
+        assert_eq!(light.block_light(4, 20, 15), 4);
+        assert_eq!(light.block_light(15, 20, 15), 0);
+    }
+
+    #[test]
+    fn test_spread() {
+        let mut lit = Chunk::new(0, 0);
+        lit.set_block(15, 20, 4, Block::Lava);
+        lit.update_light();
+        // A dark chunk under a roof.
+        let mut roofed = Chunk::new(1, 0);
+        for z in 0..16 {
+            for x in 0..16 {
+                roofed.set_block(x, 10, z, Block::Dirt);
+            }
+        }
+        roofed.update_light();
+        assert_eq!(roofed.light().sky_light(0, 5, 4), 0);
+
+        let mut chunks = BTreeMap::new();
+        chunks.insert((0, 0), &mut lit);
+        chunks.insert((1, 0), &mut roofed);
+        let changed = spread(&mut chunks, &[(0, 0)]);
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), [(1, 0)]);
+
+        assert_eq!(roofed.light().block_light(0, 20, 4), 14);
+        assert_eq!(roofed.light().block_light(3, 20, 4), 11);
+        assert_eq!(roofed.light().sky_light(0, 5, 4), 14);
+        assert_eq!(roofed.light().sky_light(2, 5, 4), 12);
+        assert_eq!(lit.light().block_light(15, 20, 4), 15);
+    }
+
+    #[test]
+    fn test_empty_masks() {
+        let chunk = Chunk::new(0, 0);
+        let (mask, empty_mask) = UpdateLight::masks(&chunk.light().sky);
+        assert_eq!(*mask, 0b11_1111_1111_1111_1110);
+        assert_eq!(*empty_mask, 1);
+
+        let (mask, empty_mask) = UpdateLight::masks(&chunk.light().block);
+        assert_eq!(*mask, 0);
+        assert_eq!(*empty_mask, 0b11_1111_1111_1111_1111);
+    }
+}
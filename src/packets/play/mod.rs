@@ -1,29 +1,68 @@
+pub mod biome;
 pub mod block;
+pub mod block_break_animation;
 pub mod block_change;
+pub mod block_entity;
+pub mod block_placement;
+pub mod change_game_state;
 pub mod chat_message;
 pub mod chunk;
+pub mod client_settings;
 pub mod destroy_entity;
+pub mod entity_action;
+pub mod entity_animation;
+pub mod entity_effect;
+pub mod entity_metadata;
 pub mod entity_position;
 pub mod held_item_slot;
+pub mod item;
 pub mod join_game;
 pub mod keep_alive;
+pub mod light;
+pub mod multi_block_change;
 pub mod player_digging;
 pub mod player_info;
 pub mod player_position;
 pub mod recipes;
 pub mod slot;
+pub mod spawn_entity;
+pub mod spawn_living_entity;
 pub mod spawn_player;
+pub mod spawn_position;
+pub mod time_update;
+pub mod tool;
+pub mod window;
+pub mod world_border;
 
+pub use biome::*;
 pub use block::*;
+pub use block_break_animation::*;
 pub use block_change::*;
+pub use block_entity::*;
+pub use block_placement::*;
+pub use change_game_state::*;
 pub use chunk::*;
+pub use client_settings::*;
 pub use destroy_entity::*;
+pub use entity_animation::*;
+pub use entity_effect::*;
+pub use entity_metadata::*;
 pub use held_item_slot::*;
+pub use item::*;
 pub use join_game::*;
 pub use keep_alive::*;
+pub use light::*;
+pub use multi_block_change::*;
 pub use player_digging::*;
 pub use player_info::*;
 pub use player_position::*;
 pub use recipes::*;
 pub use slot::*;
+pub use spawn_entity::*;
+pub use spawn_living_entity::*;
 pub use spawn_player::*;
+pub use spawn_position::*;
+pub use time_update::*;
+pub use tool::*;
+pub use window::*;
+pub use world_border::*;
@@ -0,0 +1,60 @@
+use crate::packets::play::Block;
+use crate::types::{LengthVec, VarInt};
+
+/// Multiple block changes in the same chunk.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct MultiBlockChange {
+    x: i32,
+    z: i32,
+    records: LengthVec<Record>,
+}
+crate::impl_packet!(MultiBlockChange, 0x10);
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+struct Record {
+    horizontal_position: u8,
+    y: u8,
+    block: VarInt,
+}
+
+impl MultiBlockChange {
+    /// `blocks` are the changed blocks with their coordinates relative to the chunk.
+    pub fn new(x: i32, z: i32, blocks: &[(u8, u16, u8, Block)]) -> Self {
+        let records = blocks
+            .iter()
+            .map(|(x, y, z, block)| Record {
+                horizontal_position: x << 4 | z,
+                y: *y as u8,
+                block: VarInt(block.id() as i32),
+            })
+            .collect();
+        Self {
+            x,
+            z,
+            records: LengthVec::from(records),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Send, Size};
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_encoding() {
+        let packet = MultiBlockChange::new(
+            -1,
+            2,
+            &[(15, 3, 1, Block::Stone), (0, 255, 0, Block::Water)],
+        );
+        let mut buffer = Vec::new();
+        packet.send(&mut buffer).await.unwrap();
+        assert_eq!(
+            buffer,
+            vec![0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 2, 2, 0xF1, 3, 1, 0x00, 255, 34]
+        );
+        assert_eq!(*packet.size() as usize, buffer.len());
+    }
+}
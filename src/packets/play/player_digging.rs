@@ -1,3 +1,4 @@
+use crate::packets::play::Block;
 use crate::types::{self, BlockPosition, Receive, TAsyncRead, VarInt};
 use anyhow::{ensure, Result};
 
@@ -42,7 +43,41 @@ impl types::FromReader for PlayerDigging {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DiggingStatus {
+    Started = 0,
+    Cancelled,
+    Finished,
+}
+
+/// Confirm or deny a digging action to the client.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct AcknowledgePlayerDigging {
+    position: BlockPosition,
+    block: VarInt,
+    status: VarInt,
+    successful: bool,
+}
+
+impl AcknowledgePlayerDigging {
+    /// `block` is the block at `position` once the action has been applied or refused.
+    pub fn new(
+        position: BlockPosition,
+        block: Block,
+        status: DiggingStatus,
+        successful: bool,
+    ) -> Self {
+        Self {
+            position,
+            block: VarInt(block.id() as i32),
+            status: VarInt(status as i32),
+            successful,
+        }
+    }
+}
+crate::impl_packet!(AcknowledgePlayerDigging, 0x08);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Face {
     Bottom = 0,
@@ -58,3 +93,18 @@ impl From<u8> for Face {
         unsafe { std::mem::transmute(n) }
     }
 }
+
+impl Face {
+    /// The position of the block touching this face of the block at `position`.
+    pub fn offset(self, position: &BlockPosition) -> Option<BlockPosition> {
+        let BlockPosition { x, y, z } = *position;
+        Some(match self {
+            Face::Bottom => BlockPosition::new(x, y.checked_sub(1)?, z),
+            Face::Top => BlockPosition::new(x, y + 1, z),
+            Face::North => BlockPosition::new(x, y, z - 1),
+            Face::South => BlockPosition::new(x, y, z + 1),
+            Face::West => BlockPosition::new(x - 1, y, z),
+            Face::East => BlockPosition::new(x + 1, y, z),
+        })
+    }
+}
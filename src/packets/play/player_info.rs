@@ -25,7 +25,10 @@ impl<'a> Size for PlayerInfo<'a> {
         self.action.size()
             + match self.action {
                 Action::Add => self.info.size(),
-                Action::UpdateGameMode => unimplemented!(),
+                Action::UpdateGameMode => {
+                    let length = self.info.len() as i32;
+                    VarInt(length).size() + VarInt(length * 17)
+                }
                 Action::UpdateLatency => unimplemented!(),
                 Action::UpdateDisplayName => unimplemented!(),
                 Action::Remove => {
@@ -42,7 +45,13 @@ impl Send for PlayerInfo<'_> {
         self.action.send(writer).await?;
         match self.action {
             Action::Add => self.info.send(writer).await?,
-            Action::UpdateGameMode => unimplemented!(),
+            Action::UpdateGameMode => {
+                VarInt(self.info.len() as i32).send(writer).await?;
+                for info in self.info.iter() {
+                    info.uuid().send(writer).await?;
+                    VarInt(info.game_mode() as i32).send(writer).await?;
+                }
+            }
             Action::UpdateLatency => unimplemented!(),
             Action::UpdateDisplayName => unimplemented!(),
             Action::Remove => {
@@ -15,6 +15,11 @@ pub struct OutPlayerPositionLook {
 }
 impl_packet!(OutPlayerPositionLook, 0x36);
 
+impl OutPlayerPositionLook {
+    /// The flags making the angles relative, to keep the rotation of the client.
+    pub const RELATIVE_ROTATION: i8 = 0x08 | 0x10;
+}
+
 impl From<&EntityPosition> for OutPlayerPositionLook {
     fn from(position: &EntityPosition) -> Self {
         let rotation = position.rotation();
@@ -1,9 +1,440 @@
 use crate::impl_packet;
-use crate::types::LengthVec;
+use crate::packets::play::{Item, ItemStack};
+use crate::types::{self, BoolOption, LengthVec, Send, Size, TAsyncWrite, VarInt};
+use anyhow::Result;
+use std::ops::Add;
 
+include!(concat!(env!("OUT_DIR"), "/recipes.rs"));
+
+/// The items accepted in a slot, none for a slot which must stay empty.
+pub type Ingredient = &'static [Item];
+
+/// A recipe of the vanilla data, see `build.rs`.
+#[derive(Debug)]
+pub struct Recipe {
+    name: &'static str,
+    group: &'static str,
+    kind: RecipeKind,
+    result: Item,
+    count: u8,
+}
+
+#[derive(Debug)]
+pub enum RecipeKind {
+    /// Ingredients row by row, the pattern can also be mirrored.
+    Shaped {
+        width: u8,
+        height: u8,
+        ingredients: &'static [Ingredient],
+    },
+    /// Ingredients in any order.
+    Shapeless(&'static [Ingredient]),
+    /// Cooking time in ticks.
+    Smelting {
+        ingredient: Ingredient,
+        experience: f32,
+        cooking_time: u16,
+    },
+}
+
+impl Recipe {
+    pub fn all() -> &'static [Recipe] {
+        &RECIPES
+    }
+
+    /// Find a recipe from its name, e.g. `minecraft:chest`.
+    pub fn from_name(name: &str) -> Option<&'static Recipe> {
+        // The recipes are sorted by name.
+        RECIPES
+            .binary_search_by_key(&name, |recipe| recipe.name)
+            .ok()
+            .map(|index| &RECIPES[index])
+    }
+
+    /// The crafting recipe matching the items of a crafting grid of `width` columns.
+    pub fn crafted(grid: &[Option<ItemStack>], width: usize) -> Option<&'static Recipe> {
+        RECIPES.iter().find(|recipe| recipe.matches(grid, width))
+    }
+
+    /// The smelting recipe of an item.
+    pub fn smelted(item: Item) -> Option<&'static Recipe> {
+        RECIPES.iter().find(|recipe| match recipe.kind {
+            RecipeKind::Smelting { ingredient, .. } => ingredient.contains(&item),
+            _ => false,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn kind(&self) -> &RecipeKind {
+        &self.kind
+    }
+
+    pub fn result(&self) -> ItemStack {
+        ItemStack::new(self.result, self.count as i8)
+    }
+
+    fn matches(&self, grid: &[Option<ItemStack>], width: usize) -> bool {
+        let accepts = |ingredient: Ingredient, slot: &Option<ItemStack>| match slot {
+            Some(item) => ingredient.contains(&item.item()),
+            None => ingredient.is_empty(),
+        };
+        match self.kind {
+            RecipeKind::Shaped {
+                width: recipe_width,
+                height,
+                ingredients,
+            } => {
+                // The smallest rectangle holding all the items of the grid.
+                let filled = grid
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, slot)| slot.is_some())
+                    .map(|(index, _)| (index % width, index / width));
+                let (mut left, mut top, mut right, mut bottom) = (width, usize::MAX, 0, 0);
+                for (x, y) in filled {
+                    left = left.min(x);
+                    right = right.max(x + 1);
+                    top = top.min(y);
+                    bottom = bottom.max(y + 1);
+                }
+                let (recipe_width, height) = (recipe_width as usize, height as usize);
+                if right <= left || right - left != recipe_width || bottom - top != height {
+                    return false;
+                }
+                let matches = |mirrored: bool| {
+                    (0..height).all(|y| {
+                        (0..recipe_width).all(|x| {
+                            let column = if mirrored { recipe_width - 1 - x } else { x };
+                            let slot = &grid[(top + y) * width + left + x];
+                            accepts(ingredients[y * recipe_width + column], slot)
+                        })
+                    })
+                };
+                matches(false) || matches(true)
+            }
+            RecipeKind::Shapeless(ingredients) => {
+                let items = grid
+                    .iter()
+                    .filter(|slot| slot.is_some())
+                    .collect::<Vec<_>>();
+                items.len() == ingredients.len()
+                    && assign(
+                        &items,
+                        ingredients,
+                        &mut vec![false; ingredients.len()],
+                        &accepts,
+                    )
+            }
+            RecipeKind::Smelting { .. } => false,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.kind {
+            RecipeKind::Shaped { .. } => "minecraft:crafting_shaped",
+            RecipeKind::Shapeless(_) => "minecraft:crafting_shapeless",
+            RecipeKind::Smelting { .. } => "minecraft:smelting",
+        }
+    }
+}
+
+/// Whether each item can be used by a different ingredient.
+fn assign(
+    items: &[&Option<ItemStack>],
+    ingredients: &[Ingredient],
+    used: &mut [bool],
+    accepts: &impl Fn(Ingredient, &Option<ItemStack>) -> bool,
+) -> bool {
+    let (item, rest) = match items.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for (index, ingredient) in ingredients.iter().enumerate() {
+        if !used[index] && accepts(ingredient, item) {
+            used[index] = true;
+            if assign(rest, ingredients, used, accepts) {
+                return true;
+            }
+            used[index] = false;
+        }
+    }
+    false
+}
+
+impl Item {
+    /// The item left in the crafting grid when this item is used, e.g. an empty bucket.
+    pub fn crafting_remainder(self) -> Option<Item> {
+        match self {
+            Item::WaterBucket | Item::LavaBucket | Item::MilkBucket => Some(Item::Bucket),
+            Item::HoneyBottle | Item::DragonBreath => Some(Item::GlassBottle),
+            _ => None,
+        }
+    }
+}
+
+impl ItemStack {
+    /// The stack left in a slot of a crafting grid once one of its items is used.
+    pub fn used_in_recipe(mut self) -> Option<ItemStack> {
+        if let Some(remainder) = self.item().crafting_remainder() {
+            return Some(ItemStack::new(remainder, 1));
+        }
+        self.set_count(self.count() - 1);
+        Some(self).filter(|stack| stack.count() > 0)
+    }
+}
+
+/// The items of an ingredient, as slots.
+fn slots(ingredient: Ingredient) -> LengthVec<BoolOption<ItemStack>> {
+    LengthVec::from(
+        ingredient
+            .iter()
+            .map(|item| BoolOption(Some(ItemStack::new(*item, 1))))
+            .collect(),
+    )
+}
+
+impl Size for Recipe {
+    fn size(&self) -> VarInt {
+        let header = types::String::new(self.type_name()).size()
+            + types::String::new(self.name).size()
+            + types::String::new(self.group).size();
+        let result = BoolOption(Some(self.result())).size();
+        let ingredients = |ingredients: &[Ingredient]| {
+            ingredients
+                .iter()
+                .map(|ingredient| slots(ingredient).size())
+                .fold(VarInt(0), Add::add)
+        };
+        header
+            + result
+            + match self.kind {
+                RecipeKind::Shaped {
+                    width,
+                    height,
+                    ingredients: shape,
+                } => {
+                    VarInt(width as i32).size() + VarInt(height as i32).size() + ingredients(shape)
+                }
+                RecipeKind::Shapeless(shape) => {
+                    VarInt(shape.len() as i32).size() + ingredients(shape)
+                }
+                RecipeKind::Smelting {
+                    ingredient,
+                    cooking_time,
+                    ..
+                } => slots(ingredient).size() + VarInt(4) + VarInt(cooking_time as i32).size(),
+            }
+    }
+}
+
+#[async_trait::async_trait]
+impl Send for Recipe {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        types::String::new(self.type_name()).send(writer).await?;
+        types::String::new(self.name).send(writer).await?;
+        let group = types::String::new(self.group);
+        let result = BoolOption(Some(self.result()));
+        match self.kind {
+            RecipeKind::Shaped {
+                width,
+                height,
+                ingredients,
+            } => {
+                VarInt(width as i32).send(writer).await?;
+                VarInt(height as i32).send(writer).await?;
+                group.send(writer).await?;
+                for ingredient in ingredients {
+                    slots(ingredient).send(writer).await?;
+                }
+                result.send(writer).await
+            }
+            RecipeKind::Shapeless(ingredients) => {
+                group.send(writer).await?;
+                VarInt(ingredients.len() as i32).send(writer).await?;
+                for ingredient in ingredients {
+                    slots(ingredient).send(writer).await?;
+                }
+                result.send(writer).await
+            }
+            RecipeKind::Smelting {
+                ingredient,
+                experience,
+                cooking_time,
+            } => {
+                group.send(writer).await?;
+                slots(ingredient).send(writer).await?;
+                result.send(writer).await?;
+                experience.send(writer).await?;
+                VarInt(cooking_time as i32).send(writer).await
+            }
+        }
+    }
+}
+
+impl Size for &Recipe {
+    fn size(&self) -> VarInt {
+        (**self).size()
+    }
+}
+
+#[async_trait::async_trait]
+impl Send for &Recipe {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        (**self).send(writer).await
+    }
+}
+
+/// Declare Recipes: the recipes known by the client, shown in its recipe book.
 #[derive(Debug, macro_derive::Size, macro_derive::Send)]
-pub struct Recipes(LengthVec<Recipe>);
+pub struct Recipes(LengthVec<&'static Recipe>);
 impl_packet!(Recipes, 0x5B);
 
-#[derive(Debug, macro_derive::Size, macro_derive::Send)]
-pub struct Recipe {}
+impl Recipes {
+    pub fn new(recipes: impl IntoIterator<Item = &'static Recipe>) -> Self {
+        Self(LengthVec::from(recipes.into_iter().collect()))
+    }
+
+    /// All the recipes of the game.
+    pub fn all() -> Self {
+        Self::new(Recipe::all())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_await_test::async_test;
+
+    fn grid(items: &[Option<Item>]) -> Vec<Option<ItemStack>> {
+        items
+            .iter()
+            .map(|item| item.map(|item| ItemStack::new(item, 1)))
+            .collect()
+    }
+
+    #[test]
+    fn test_shaped() {
+        let planks = Some(Item::OakPlanks);
+        let sticks = grid(&[None, None, planks, None, None, planks]);
+        let recipe = Recipe::crafted(&sticks, 3).unwrap();
+        assert_eq!(recipe.name(), "minecraft:stick");
+        assert_eq!(recipe.result(), ItemStack::new(Item::Stick, 4));
+
+        // Planks of different woods can be mixed, the pattern must keep its shape.
+        let mixed = grid(&[Some(Item::BirchPlanks), None, planks, None]);
+        assert_eq!(
+            Recipe::crafted(&mixed, 2).unwrap().name(),
+            "minecraft:stick"
+        );
+        let plate = Recipe::crafted(&grid(&[planks, planks, None, None]), 2).unwrap();
+        assert_eq!(plate.result().item(), Item::OakPressurePlate);
+        assert!(Recipe::crafted(&grid(&[planks, None, None, planks]), 2).is_none());
+
+        let (stick, iron) = (Some(Item::Stick), Some(Item::IronIngot));
+        let axe = grid(&[iron, iron, None, stick, iron, None, stick, None, None]);
+        let mirrored = grid(&[iron, iron, None, iron, stick, None, None, stick, None]);
+        assert_eq!(
+            Recipe::crafted(&axe, 3).unwrap().result().item(),
+            Item::IronAxe
+        );
+        assert_eq!(
+            Recipe::crafted(&mirrored, 3).unwrap().result().item(),
+            Item::IronAxe
+        );
+    }
+
+    #[test]
+    fn test_shapeless() {
+        let log = grid(&[None, Some(Item::StrippedOakLog), None, None]);
+        assert_eq!(
+            Recipe::crafted(&log, 2).unwrap().result(),
+            ItemStack::new(Item::OakPlanks, 4)
+        );
+        let book = grid(&[
+            Some(Item::Leather),
+            Some(Item::Paper),
+            None,
+            Some(Item::Paper),
+            Some(Item::Paper),
+        ]);
+        assert_eq!(
+            Recipe::crafted(&book, 3).unwrap().result().item(),
+            Item::Book
+        );
+        assert!(Recipe::crafted(&book[..4], 2).is_none());
+        assert!(Recipe::crafted(&grid(&[None; 9]), 3).is_none());
+    }
+
+    #[test]
+    fn test_smelting() {
+        let recipe = Recipe::smelted(Item::IronOre).unwrap();
+        assert_eq!(recipe.result(), ItemStack::new(Item::IronIngot, 1));
+        assert!(Recipe::smelted(Item::Stick).is_none());
+        assert_eq!(
+            Recipe::from_name(recipe.name()).unwrap().name(),
+            recipe.name()
+        );
+        assert!(Recipe::from_name("minecraft:unknown").is_none());
+    }
+
+    #[test]
+    fn test_remainder() {
+        let bucket = ItemStack::new(Item::MilkBucket, 1).used_in_recipe();
+        assert_eq!(bucket, Some(ItemStack::new(Item::Bucket, 1)));
+        assert_eq!(ItemStack::new(Item::Sugar, 1).used_in_recipe(), None);
+    }
+
+    #[async_test]
+    async fn test_declare_recipes() -> Result<()> {
+        let packet = Recipes::new(vec![
+            Recipe::from_name("minecraft:stick").unwrap(),
+            Recipe::smelted(Item::Cobblestone).unwrap(),
+        ]);
+        let mut buffer = Vec::new();
+        packet.send(&mut buffer).await?;
+        assert_eq!(*packet.size() as usize, buffer.len());
+
+        let mut expected = vec![2];
+        expected.push(25);
+        expected.extend(b"minecraft:crafting_shaped");
+        expected.push(15);
+        expected.extend(b"minecraft:stick");
+        // 1x2, no group, the 6 types of planks in each slot.
+        expected.extend(&[1, 2, 0]);
+        for _ in 0..2 {
+            expected.push(6);
+            for planks in &[Item::OakPlanks, Item::SprucePlanks, Item::BirchPlanks] {
+                expected.extend(&[1, planks.id() as u8, 1, 0]);
+            }
+            for planks in &[Item::JunglePlanks, Item::AcaciaPlanks, Item::DarkOakPlanks] {
+                expected.extend(&[1, planks.id() as u8, 1, 0]);
+            }
+        }
+        expected.push(1);
+        VarInt(Item::Stick.id() as i32).send(&mut expected).await?;
+        expected.extend(&[4, 0]);
+        assert_eq!(&buffer[..expected.len()], &expected[..]);
+
+        let smelting = &buffer[expected.len()..];
+        assert_eq!(&smelting[1..19], b"minecraft:smelting");
+        assert_eq!(
+            &smelting[smelting.len() - 10..],
+            &[
+                1,
+                Item::Stone.id() as u8,
+                1,
+                0,
+                0x3D,
+                0xCC,
+                0xCC,
+                0xCD,
+                0xC8,
+                0x01
+            ]
+        );
+        Ok(())
+    }
+}
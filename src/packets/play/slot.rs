@@ -1,35 +1,145 @@
-use crate::types::{self, BoolOption};
-use crate::{impl_packet, impl_send, impl_size};
+use crate::packets::play::ItemStack;
+use crate::types::{self, BoolOption, Receive, TAsyncRead, TAsyncWrite, VarInt};
+use crate::{impl_packet, impl_size};
+use anyhow::{ensure, Result};
 
+/// Set the content of a single slot of a window.
 #[derive(Debug, macro_derive::Size, macro_derive::Send)]
-pub struct Slot {
+pub struct SetSlot {
     window: Window,
-    index: u16,
-    item: BoolOption<Item>,
+    index: i16,
+    item: BoolOption<ItemStack>,
 }
 
-impl Slot {
-    pub fn empty(window: Window, index: u16) -> Self {
+impl SetSlot {
+    pub fn new(window: Window, index: u16, item: Option<ItemStack>) -> Self {
         Self {
             window,
-            index,
-            item: BoolOption(None),
+            index: index as i16,
+            item: BoolOption(item),
+        }
+    }
+
+    pub fn empty(window: Window, index: u16) -> Self {
+        Self::new(window, index, None)
+    }
+
+    /// Set the item held by the cursor.
+    pub fn cursor(item: Option<ItemStack>) -> Self {
+        Self {
+            window: Window::Cursor,
+            index: -1,
+            item: BoolOption(item),
         }
     }
 }
-impl_packet!(Slot, 0x17);
+impl_packet!(SetSlot, 0x17);
 
-#[derive(Debug, Copy, Clone)]
-#[repr(i8)]
+/// Set the content of all the slots of a window.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct WindowItems {
+    window: u8,
+    count: i16,
+    items: Vec<BoolOption<ItemStack>>,
+}
+
+impl WindowItems {
+    pub fn new(window: Window, items: &[Option<ItemStack>]) -> Self {
+        Self {
+            window: window.id() as u8,
+            count: items.len() as i16,
+            items: items.iter().cloned().map(BoolOption).collect(),
+        }
+    }
+}
+impl_packet!(WindowItems, 0x15);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Window {
-    Inventory = 0,
+    Cursor,
+    Inventory,
+    /// A window opened by the server, with an id from 1 to 100.
+    Container(u8),
+}
+
+impl Window {
+    pub fn id(self) -> i8 {
+        match self {
+            Window::Cursor => -1,
+            Window::Inventory => 0,
+            Window::Container(id) => id as i8,
+        }
+    }
 }
 impl_size!(Window, 1);
-impl_send!(Window as i8);
 
-#[derive(Debug, macro_derive::Size, macro_derive::Send)]
-pub struct Item {
-    id: types::VarInt,
-    count: i8,
-    nbt: Vec<u8>,
+#[async_trait::async_trait]
+impl types::Send for Window {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        self.id().send(writer).await
+    }
+}
+
+/// Sent by creative players to set the content of a slot of their inventory, or to drop an item
+/// with the index `DROP`.
+#[derive(Debug)]
+pub struct CreativeInventoryAction {
+    pub index: i16,
+    pub item: Option<ItemStack>,
+}
+
+impl CreativeInventoryAction {
+    pub const PACKET_ID: VarInt = VarInt(0x26);
+    /// The index sent when an item is dropped out of the creative inventory.
+    pub const DROP: i16 = -1;
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for CreativeInventoryAction {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let index: i16 = reader.receive().await?;
+        ensure!(index >= CreativeInventoryAction::DROP, "invalid slot index");
+        let item: BoolOption<ItemStack> = reader.receive().await?;
+        Ok(Self {
+            index,
+            item: item.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Item;
+    use crate::types::{Receive, Send, Size};
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_window_items() -> Result<()> {
+        let items = [None, Some(ItemStack::new(Item::Stone, 2))];
+        let packet = WindowItems::new(Window::Inventory, &items);
+        let mut buffer = Vec::new();
+        packet.send(&mut buffer).await?;
+        assert_eq!(buffer, [0, 0, 2, 0, 1, 1, 2, 0]);
+        assert_eq!(*packet.size() as usize, buffer.len());
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_creative_inventory_action() -> Result<()> {
+        let mut buffer = Vec::new();
+        (-1i16).send(&mut buffer).await?;
+        BoolOption(Some(ItemStack::new(Item::Stone, 3)))
+            .send(&mut buffer)
+            .await?;
+        let action: CreativeInventoryAction = Cursor::new(buffer).receive().await?;
+        assert_eq!(action.index, CreativeInventoryAction::DROP);
+        assert_eq!(action.item, Some(ItemStack::new(Item::Stone, 3)));
+
+        let buffer = vec![0xFF, 0xFE, 0];
+        let result: Result<CreativeInventoryAction> = Cursor::new(buffer).receive().await;
+        assert!(result.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,53 @@
+use crate::impl_packet;
+use crate::types::VarInt;
+use uuid::Uuid;
+
+/// The types of non-living entities the server can spawn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EntityType {
+    Item = 35,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct SpawnEntity {
+    id: VarInt,
+    uuid: Uuid,
+    kind: VarInt,
+    x: f64,
+    y: f64,
+    z: f64,
+    pitch: i8,
+    yaw: i8,
+    data: i32,
+    velocity_x: i16,
+    velocity_y: i16,
+    velocity_z: i16,
+}
+impl_packet!(SpawnEntity, 0x00);
+
+impl SpawnEntity {
+    /// `velocity` is in blocks per tick.
+    pub fn new(
+        id: VarInt,
+        uuid: Uuid,
+        kind: EntityType,
+        (x, y, z): (f64, f64, f64),
+        (velocity_x, velocity_y, velocity_z): (f64, f64, f64),
+    ) -> Self {
+        let scale = |velocity: f64| (velocity.clamp(-3.9, 3.9) * 8000.) as i16;
+        Self {
+            id,
+            uuid,
+            kind: VarInt(kind as i32),
+            x,
+            y,
+            z,
+            pitch: 0,
+            yaw: 0,
+            data: 1,
+            velocity_x: scale(velocity_x),
+            velocity_y: scale(velocity_y),
+            velocity_z: scale(velocity_z),
+        }
+    }
+}
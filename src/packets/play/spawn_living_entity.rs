@@ -0,0 +1,75 @@
+use crate::impl_packet;
+use crate::types::{EntityPosition, VarInt};
+use uuid::Uuid;
+
+/// The types of living entities the server can spawn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LivingType {
+    ArmorStand = 1,
+    Chicken = 9,
+    Cow = 11,
+    Creeper = 12,
+    Pig = 55,
+    Sheep = 62,
+    Skeleton = 66,
+    Villager = 85,
+    Zombie = 95,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct SpawnLivingEntity {
+    id: VarInt,
+    uuid: Uuid,
+    kind: VarInt,
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: u8,
+    pitch: u8,
+    head_pitch: u8,
+    velocity_x: i16,
+    velocity_y: i16,
+    velocity_z: i16,
+}
+impl_packet!(SpawnLivingEntity, 0x03);
+
+impl SpawnLivingEntity {
+    /// A motionless entity, looking in the direction of its position angles.
+    pub fn new(id: VarInt, uuid: Uuid, kind: LivingType, position: &EntityPosition) -> Self {
+        Self {
+            id,
+            uuid,
+            kind: VarInt(kind as i32),
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            yaw: position.x_angle,
+            pitch: position.z_angle,
+            head_pitch: position.z_angle,
+            velocity_x: 0,
+            velocity_y: 0,
+            velocity_z: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Send, Size};
+    use anyhow::Result;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_spawn_living_entity() -> Result<()> {
+        let position = EntityPosition::new(0., 64., 0., 64, 0);
+        let spawn = SpawnLivingEntity::new(VarInt(2), Uuid::nil(), LivingType::Zombie, &position);
+        let mut buffer = Vec::new();
+        spawn.send(&mut buffer).await?;
+        assert_eq!(*spawn.size() as usize, buffer.len());
+        assert_eq!(buffer[0], 2);
+        assert_eq!(buffer[17], 95);
+        assert_eq!(&buffer[42..45], &[64, 0, 0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+use crate::types::BlockPosition;
+
+/// The world spawn, where the compass points to.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct SpawnPosition(pub BlockPosition);
+crate::impl_packet!(SpawnPosition, 0x4E);
@@ -0,0 +1,45 @@
+use crate::impl_packet;
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct TimeUpdate {
+    age: i64,
+    time: i64,
+}
+impl_packet!(TimeUpdate, 0x4F);
+
+impl TimeUpdate {
+    /// The length of a day, in ticks.
+    pub const DAY: u64 = 24000;
+
+    /// `age` is the number of ticks since the world was created and `time` the time of day, in
+    /// ticks, counting the previous days for the moon phases. The clients stop advancing the
+    /// time if the daylight cycle is disabled.
+    pub fn new(age: u64, time: u64, daylight_cycle: bool) -> Self {
+        let time = time as i64;
+        Self {
+            age: age as i64,
+            // A null time would still be advanced.
+            time: if daylight_cycle { time } else { -time.max(1) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Send;
+    use anyhow::Result;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_time_update() -> Result<()> {
+        let update = TimeUpdate::new(1, 24001, false);
+        let mut buffer = Vec::new();
+        update.send(&mut buffer).await?;
+        assert_eq!(buffer[..8], [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(buffer[8..], (-24001i64).to_be_bytes());
+        assert_eq!(TimeUpdate::new(0, 0, false).time, -1);
+        assert_eq!(TimeUpdate::new(0, 6000, true).time, 6000);
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::packets::play::{Block, Item, ItemStack};
+use rand::Rng;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ToolKind {
+    Pickaxe,
+    Axe,
+    Shovel,
+    Hoe,
+    Sword,
+    Shears,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ToolMaterial {
+    Wood,
+    Stone,
+    Iron,
+    Diamond,
+    Gold,
+}
+
+impl ToolMaterial {
+    /// The blocks requiring a tool need a tool with a high enough harvest level to drop.
+    pub fn harvest_level(self) -> u8 {
+        match self {
+            ToolMaterial::Wood | ToolMaterial::Gold => 0,
+            ToolMaterial::Stone => 1,
+            ToolMaterial::Iron => 2,
+            ToolMaterial::Diamond => 3,
+        }
+    }
+
+    /// The mining speed multiplier against blocks of the right type.
+    pub fn speed(self) -> f32 {
+        match self {
+            ToolMaterial::Wood => 2.,
+            ToolMaterial::Stone => 4.,
+            ToolMaterial::Iron => 6.,
+            ToolMaterial::Diamond => 8.,
+            ToolMaterial::Gold => 12.,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Tool {
+    pub kind: ToolKind,
+    /// None for shears.
+    pub material: Option<ToolMaterial>,
+}
+
+impl Tool {
+    fn harvest_level(self) -> u8 {
+        self.material.map_or(0, ToolMaterial::harvest_level)
+    }
+
+    /// The mining speed multiplier against a block.
+    pub fn speed(self, block: Block) -> f32 {
+        if block.tool() != Some(self.kind) {
+            return 1.;
+        }
+        match (self.kind, self.material) {
+            (ToolKind::Shears, _) if block.name().ends_with("_wool") => 5.,
+            (ToolKind::Shears, _) | (ToolKind::Sword, _) => 15.,
+            (_, material) => material.map_or(1., ToolMaterial::speed),
+        }
+    }
+}
+
+/// The item dropped by a block when it is harvested.
+#[derive(Debug)]
+pub struct Drop {
+    pub item: Item,
+    /// Inclusive range of the dropped count.
+    pub count: (u8, u8),
+    pub chance: f32,
+}
+
+impl Item {
+    /// The tool this item is, e.g. an iron pickaxe.
+    pub fn tool(self) -> Option<Tool> {
+        let name = self.name().trim_start_matches("minecraft:");
+        if name == "shears" {
+            return Some(Tool {
+                kind: ToolKind::Shears,
+                material: None,
+            });
+        }
+
+        let mut split = name.splitn(2, '_');
+        let material = match split.next()? {
+            "wooden" => ToolMaterial::Wood,
+            "stone" => ToolMaterial::Stone,
+            "iron" => ToolMaterial::Iron,
+            "diamond" => ToolMaterial::Diamond,
+            "golden" => ToolMaterial::Gold,
+            _ => return None,
+        };
+        let kind = match split.next()? {
+            "pickaxe" => ToolKind::Pickaxe,
+            "axe" => ToolKind::Axe,
+            "shovel" => ToolKind::Shovel,
+            "hoe" => ToolKind::Hoe,
+            "sword" => ToolKind::Sword,
+            _ => return None,
+        };
+        Some(Tool {
+            kind,
+            material: Some(material),
+        })
+    }
+}
+
+impl Block {
+    /// Whether breaking this block with the tool (None for the bare hand) drops something.
+    pub fn can_harvest(self, tool: Option<Tool>) -> bool {
+        match (self.harvest_tool(), tool) {
+            (None, _) => true,
+            (Some((kind, level)), Some(tool)) => tool.kind == kind && tool.harvest_level() >= level,
+            (Some(_), None) => false,
+        }
+    }
+
+    /// The number of ticks needed to break this block with the tool, None if it can't be
+    /// broken and 0 if it breaks instantly.
+    pub fn break_ticks(self, tool: Option<Tool>) -> Option<u32> {
+        let hardness = self.hardness()?;
+        if hardness == 0. {
+            return Some(0);
+        }
+
+        let speed = tool.map_or(1., |tool| tool.speed(self));
+        let penalty = if self.can_harvest(tool) { 30. } else { 100. };
+        let damage = speed / hardness / penalty;
+        if damage >= 1. {
+            Some(0)
+        } else {
+            Some((1. / damage).ceil() as u32)
+        }
+    }
+
+    /// Roll what this block drops when broken with the tool.
+    pub fn drops(self, tool: Option<Tool>, random: &mut impl Rng) -> Option<ItemStack> {
+        let drop = self.drop().filter(|_| self.can_harvest(tool))?;
+        if random.gen::<f32>() >= drop.chance {
+            return None;
+        }
+
+        let mut count = random.gen_range(drop.count.0, drop.count.1 + 1);
+        if self.property("type") == Some("double") {
+            count *= 2;
+        }
+        Some(ItemStack::new(drop.item, count as i8)).filter(|_| count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn test_tools() {
+        let pickaxe = Item::IronPickaxe.tool().unwrap();
+        assert_eq!(pickaxe.kind, ToolKind::Pickaxe);
+        assert_eq!(pickaxe.material, Some(ToolMaterial::Iron));
+        assert_eq!(Item::Shears.tool().unwrap().material, None);
+        assert_eq!(Item::Stone.tool(), None);
+        assert_eq!(Item::IronIngot.tool(), None);
+    }
+
+    #[test]
+    fn test_break_ticks() {
+        let wooden = Item::WoodenPickaxe.tool();
+        let diamond = Item::DiamondPickaxe.tool();
+        assert_eq!(Block::Stone.break_ticks(None), Some(150));
+        assert_eq!(Block::Stone.break_ticks(wooden), Some(23));
+        assert_eq!(Block::Dirt.break_ticks(None), Some(15));
+        assert_eq!(Block::Torch.break_ticks(None), Some(0));
+        assert_eq!(Block::Obsidian.break_ticks(diamond), Some(188));
+        assert_eq!(Block::Bedrock.break_ticks(diamond), None);
+    }
+
+    #[test]
+    fn test_drops() {
+        let random = &mut StepRng::new(0, 0);
+        let wooden = Item::WoodenPickaxe.tool();
+        let iron = Item::IronPickaxe.tool();
+        assert_eq!(Block::Stone.drops(None, random), None);
+        assert_eq!(
+            Block::Stone.drops(wooden, random),
+            Some(ItemStack::new(Item::Cobblestone, 1))
+        );
+        assert_eq!(Block::DiamondOre.drops(wooden, random), None);
+        assert_eq!(
+            Block::DiamondOre
+                .drops(iron, random)
+                .map(|drop| drop.item()),
+            Some(Item::Diamond)
+        );
+        assert_eq!(Block::Glass.drops(None, random), None);
+        let double = "oak_slab[type=double]".parse::<Block>().unwrap();
+        assert_eq!(double.drops(None, random).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::packets::play::ItemStack;
+use crate::types::{self, chat::Chat, BoolOption, Receive, TAsyncRead, VarInt};
+use anyhow::{bail, Result};
+
+/// The types of windows the client can open.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WindowType {
+    Generic9x3 = 2,
+    Crafting = 11,
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct OpenWindow {
+    id: VarInt,
+    kind: VarInt,
+    title: Chat,
+}
+crate::impl_packet!(OpenWindow, 0x2F);
+
+impl OpenWindow {
+    pub fn new(id: u8, kind: WindowType, title: Chat) -> Self {
+        Self {
+            id: VarInt(id as i32),
+            kind: VarInt(kind as i32),
+            title,
+        }
+    }
+}
+
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct CloseWindow(pub u8);
+crate::impl_packet!(CloseWindow, 0x14);
+
+/// Sent by the client when it closes a window.
+#[derive(Debug)]
+pub struct InCloseWindow(pub u8);
+
+impl InCloseWindow {
+    pub const PACKET_ID: VarInt = VarInt(0x0A);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for InCloseWindow {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(Self(reader.receive().await?))
+    }
+}
+
+/// What the player does with a click, with the mouse button or key used.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ClickMode {
+    /// Left (0) or right (1) click.
+    Click(u8),
+    ShiftClick(u8),
+    /// Swap with the hotbar slot of the pressed number key, from 0 to 8.
+    NumberKey(u8),
+    MiddleClick,
+    /// Drop one item (0) or the whole stack (1).
+    Drop(u8),
+    /// Start (0 left, 4 right, 8 middle), add a slot (1, 5, 9) or end (2, 6, 10) a drag.
+    Drag(u8),
+    DoubleClick,
+}
+
+#[derive(Debug)]
+pub struct ClickWindow {
+    pub window: u8,
+    /// -999 when clicking outside of the window.
+    pub slot: i16,
+    pub action: i16,
+    pub mode: ClickMode,
+    /// The content of the clicked slot before the click, as seen by the client.
+    pub item: Option<ItemStack>,
+}
+
+impl ClickWindow {
+    pub const PACKET_ID: VarInt = VarInt(0x09);
+    pub const OUTSIDE: i16 = -999;
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for ClickWindow {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        let window = reader.receive().await?;
+        let slot = reader.receive().await?;
+        let button: u8 = reader.receive().await?;
+        let action = reader.receive().await?;
+        let mode = match reader.receive::<u8>().await? {
+            0 if button <= 1 => ClickMode::Click(button),
+            1 if button <= 1 => ClickMode::ShiftClick(button),
+            2 if button <= 8 => ClickMode::NumberKey(button),
+            3 => ClickMode::MiddleClick,
+            4 if button <= 1 => ClickMode::Drop(button),
+            5 if button <= 10 => ClickMode::Drag(button),
+            6 => ClickMode::DoubleClick,
+            mode => bail!("invalid click mode {} with button {}", mode, button),
+        };
+        let item: BoolOption<ItemStack> = reader.receive().await?;
+        Ok(Self {
+            window,
+            slot,
+            action,
+            mode,
+            item: item.0,
+        })
+    }
+}
+
+/// Accept or refuse a click, the client sends it back when it is refused.
+#[derive(Debug, macro_derive::Size, macro_derive::Send)]
+pub struct ConfirmTransaction {
+    window: i8,
+    action: i16,
+    accepted: bool,
+}
+crate::impl_packet!(ConfirmTransaction, 0x13);
+
+impl ConfirmTransaction {
+    pub fn new(window: u8, action: i16, accepted: bool) -> Self {
+        Self {
+            window: window as i8,
+            action,
+            accepted,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InConfirmTransaction {
+    pub window: u8,
+    pub action: i16,
+    pub accepted: bool,
+}
+
+impl InConfirmTransaction {
+    pub const PACKET_ID: VarInt = VarInt(0x07);
+}
+
+#[async_trait::async_trait]
+impl types::FromReader for InConfirmTransaction {
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            window: reader.receive::<i8>().await? as u8,
+            action: reader.receive().await?,
+            accepted: reader.receive().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::play::Item;
+    use futures::io::Cursor;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn read_click_window() -> Result<()> {
+        let data = vec![1, 0, 3, 1, 0, 7, 0, 1, 1, 2, 0];
+        let click: ClickWindow = Cursor::new(data).receive().await?;
+        assert_eq!((click.window, click.slot, click.action), (1, 3, 7));
+        assert_eq!(click.mode, ClickMode::Click(1));
+        assert_eq!(click.item, Some(ItemStack::new(Item::Stone, 2)));
+
+        let data = vec![0, 0xFC, 0x19, 11, 0, 1, 5, 0];
+        assert!(Cursor::new(data).receive::<ClickWindow>().await.is_err());
+        Ok(())
+    }
+}
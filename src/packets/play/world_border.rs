@@ -0,0 +1,196 @@
+use crate::types::{Send, Size, TAsyncWrite, VarInt, VarLong};
+use anyhow::Result;
+
+#[derive(Debug, Clone)]
+enum BorderAction {
+    SetSize(f64),
+    LerpSize {
+        old: f64,
+        new: f64,
+        millis: i64,
+    },
+    SetCenter(f64, f64),
+    Initialize {
+        center: (f64, f64),
+        old: f64,
+        new: f64,
+        millis: i64,
+        warning_time: i32,
+        warning_blocks: i32,
+    },
+    SetWarningTime(i32),
+    SetWarningBlocks(i32),
+}
+
+impl BorderAction {
+    fn id(&self) -> i32 {
+        match self {
+            BorderAction::SetSize(_) => 0,
+            BorderAction::LerpSize { .. } => 1,
+            BorderAction::SetCenter(..) => 2,
+            BorderAction::Initialize { .. } => 3,
+            BorderAction::SetWarningTime(_) => 4,
+            BorderAction::SetWarningBlocks(_) => 5,
+        }
+    }
+}
+
+/// An update of the world border, the sizes are diameters in blocks.
+#[derive(Debug, Clone)]
+pub struct WorldBorder {
+    action: BorderAction,
+}
+crate::impl_packet!(WorldBorder, 0x3E);
+
+impl WorldBorder {
+    /// The distance from the center of the world beyond which portals don't teleport, as in
+    /// vanilla.
+    const PORTAL_BOUNDARY: i32 = 29_999_984;
+
+    pub fn set_size(diameter: f64) -> Self {
+        Self {
+            action: BorderAction::SetSize(diameter),
+        }
+    }
+
+    /// Grow or shrink the border from `old` to `new` in `millis` milliseconds.
+    pub fn lerp_size(old: f64, new: f64, millis: i64) -> Self {
+        Self {
+            action: BorderAction::LerpSize { old, new, millis },
+        }
+    }
+
+    pub fn set_center(x: f64, z: f64) -> Self {
+        Self {
+            action: BorderAction::SetCenter(x, z),
+        }
+    }
+
+    /// The whole state of the border, for a player who just joined.
+    pub fn initialize(
+        center: (f64, f64),
+        old: f64,
+        new: f64,
+        millis: i64,
+        warning_time: i32,
+        warning_blocks: i32,
+    ) -> Self {
+        Self {
+            action: BorderAction::Initialize {
+                center,
+                old,
+                new,
+                millis,
+                warning_time,
+                warning_blocks,
+            },
+        }
+    }
+
+    /// The screen turns red this number of seconds before a shrinking border reaches the player.
+    pub fn set_warning_time(seconds: i32) -> Self {
+        Self {
+            action: BorderAction::SetWarningTime(seconds),
+        }
+    }
+
+    /// The screen turns red under this distance to the border.
+    pub fn set_warning_blocks(blocks: i32) -> Self {
+        Self {
+            action: BorderAction::SetWarningBlocks(blocks),
+        }
+    }
+}
+
+impl Size for WorldBorder {
+    fn size(&self) -> VarInt {
+        VarInt(self.action.id()).size()
+            + match &self.action {
+                BorderAction::SetSize(_) => VarInt(8),
+                BorderAction::LerpSize { millis, .. } => VarInt(16) + VarLong(*millis).size(),
+                BorderAction::SetCenter(..) => VarInt(16),
+                BorderAction::Initialize {
+                    millis,
+                    warning_time,
+                    warning_blocks,
+                    ..
+                } => {
+                    VarInt(32)
+                        + VarLong(*millis).size()
+                        + VarInt(Self::PORTAL_BOUNDARY).size()
+                        + VarInt(*warning_time).size()
+                        + VarInt(*warning_blocks).size()
+                }
+                BorderAction::SetWarningTime(value) | BorderAction::SetWarningBlocks(value) => {
+                    VarInt(*value).size()
+                }
+            }
+    }
+}
+
+#[async_trait::async_trait]
+impl Send for WorldBorder {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        VarInt(self.action.id()).send(writer).await?;
+        match &self.action {
+            BorderAction::SetSize(diameter) => diameter.send(writer).await,
+            BorderAction::LerpSize { old, new, millis } => {
+                old.send(writer).await?;
+                new.send(writer).await?;
+                VarLong(*millis).send(writer).await
+            }
+            BorderAction::SetCenter(x, z) => {
+                x.send(writer).await?;
+                z.send(writer).await
+            }
+            BorderAction::Initialize {
+                center,
+                old,
+                new,
+                millis,
+                warning_time,
+                warning_blocks,
+            } => {
+                center.0.send(writer).await?;
+                center.1.send(writer).await?;
+                old.send(writer).await?;
+                new.send(writer).await?;
+                VarLong(*millis).send(writer).await?;
+                VarInt(Self::PORTAL_BOUNDARY).send(writer).await?;
+                VarInt(*warning_time).send(writer).await?;
+                VarInt(*warning_blocks).send(writer).await
+            }
+            BorderAction::SetWarningTime(value) | BorderAction::SetWarningBlocks(value) => {
+                VarInt(*value).send(writer).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_await_test::async_test;
+
+    #[async_test]
+    async fn test_world_border() -> Result<()> {
+        let packets = vec![
+            WorldBorder::set_size(100.),
+            WorldBorder::lerp_size(100., 50., 60_000),
+            WorldBorder::set_center(0.5, -0.5),
+            WorldBorder::initialize((0., 0.), 100., 100., 0, 15, 5),
+            WorldBorder::set_warning_blocks(300),
+        ];
+        for packet in packets {
+            let mut buffer = Vec::new();
+            packet.send(&mut buffer).await?;
+            assert_eq!(buffer.len() as i32, *packet.size());
+        }
+
+        let mut buffer = Vec::new();
+        WorldBorder::set_center(1., 2.).send(&mut buffer).await?;
+        assert_eq!(buffer[0], 2);
+        assert_eq!(buffer[1..9], 1f64.to_be_bytes());
+        Ok(())
+    }
+}
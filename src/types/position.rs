@@ -56,20 +56,45 @@ impl EntityPosition {
         (x, y / 16, z)
     }
 
-    pub fn update_position(&mut self, from: &dyn PlayerPositionPacket) -> PositionDelta {
-        let before_subchunk = self.subchunk();
-
-        let mut delta = PositionDelta {
-            x: ((from.x() * 32. - self.x * 32.) * 128.) as i16,
-            y: ((from.y() * 32. - self.y * 32.) * 128.) as i16,
-            z: ((from.z() * 32. - self.z * 32.) * 128.) as i16,
-            subchunk_changed: false,
+    /// The movement to another position, in 1/4096 of block as sent in relative move packets.
+    pub fn delta(&self, to: &EntityPosition) -> PositionDelta {
+        let encode = |from: f64, to: f64| {
+            let delta = (to * 32. - from * 32.) * 128.;
+            if delta >= i16::MIN as f64 && delta <= i16::MAX as f64 {
+                Some(delta as i16)
+            } else {
+                None
+            }
         };
-        self.x = from.x();
-        self.y = from.y();
-        self.z = from.z();
+        match (
+            encode(self.x, to.x),
+            encode(self.y, to.y),
+            encode(self.z, to.z),
+        ) {
+            (Some(x), Some(y), Some(z)) => PositionDelta {
+                x,
+                y,
+                z,
+                subchunk_changed: self.subchunk() != to.subchunk(),
+                teleport: false,
+            },
+            _ => PositionDelta {
+                x: 0,
+                y: 0,
+                z: 0,
+                subchunk_changed: self.subchunk() != to.subchunk(),
+                teleport: true,
+            },
+        }
+    }
 
-        delta.subchunk_changed = before_subchunk != self.subchunk();
+    pub fn update_position(&mut self, from: &dyn PlayerPositionPacket) -> PositionDelta {
+        let mut to = self.clone();
+        to.x = from.x();
+        to.y = from.y();
+        to.z = from.z();
+        let delta = self.delta(&to);
+        *self = to;
         delta
     }
 
@@ -85,9 +110,11 @@ pub struct PositionDelta {
     pub y: i16,
     pub z: i16,
     pub subchunk_changed: bool,
+    /// The move is too long for a relative move packet, the entity must be teleported.
+    pub teleport: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct BlockPosition {
     pub x: i32,
     pub y: u16,
@@ -127,3 +154,21 @@ impl From<u64> for BlockPosition {
         Self::from_u64(n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta() {
+        let from = EntityPosition::new(0., 64., 0., 0, 0);
+        let delta = from.delta(&EntityPosition::new(1.5, 63., -7.9, 0, 0));
+        assert_eq!((delta.x, delta.y, delta.z), (6144, -4096, -32358));
+        assert!(!delta.teleport);
+        assert!(delta.subchunk_changed);
+
+        let delta = from.delta(&EntityPosition::new(8., 64., 0., 0, 0));
+        assert!(delta.teleport);
+        assert!(!delta.subchunk_changed);
+    }
+}
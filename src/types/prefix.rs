@@ -1,4 +1,4 @@
-use crate::types::{self, Send, Size, TAsyncWrite};
+use crate::types::{self, FromReader, Receive, Send, Size, TAsyncRead, TAsyncWrite};
 use anyhow::Result;
 use std::ops::{Index, IndexMut};
 
@@ -116,3 +116,17 @@ where
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl<T> FromReader for BoolOption<T>
+where
+    T: FromReader + std::marker::Send,
+{
+    async fn from_reader<R: TAsyncRead>(reader: &mut R) -> Result<Self> {
+        Ok(if reader.receive::<bool>().await? {
+            Self(Some(reader.receive().await?))
+        } else {
+            Self(None)
+        })
+    }
+}
@@ -85,6 +85,42 @@ impl Display for VarInt {
     }
 }
 
+/// A 64 bits integer encoded like a VarInt, e.g. durations in milliseconds.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub struct VarLong(pub i64);
+
+impl VarLong {
+    const MAX_SIZE: i32 = 10;
+}
+
+impl Size for VarLong {
+    fn size(&self) -> VarInt {
+        VarInt::new(match self.0 {
+            std::i64::MIN..=-1 => Self::MAX_SIZE,
+            0 => 1,
+            n => (64 - n.leading_zeros() as i32 + 6) / 7,
+        })
+    }
+}
+
+#[async_trait]
+impl Send for VarLong {
+    async fn send<W: TAsyncWrite>(&self, writer: &mut W) -> Result<()> {
+        let mut n = self.0 as u64;
+        loop {
+            let tmp = n as u8 & 0b0111_1111;
+            n >>= 7;
+            if n == 0 {
+                tmp.send(writer).await?;
+                break;
+            } else {
+                (tmp | 0b1000_0000).send(writer).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +142,13 @@ mod tests {
         );
         assert_eq!(*VarInt::new(std::i32::MAX).size(), VarInt::MAX_SIZE);
     }
+
+    #[test]
+    fn var_long_size() {
+        assert_eq!(*VarLong(-1).size(), VarLong::MAX_SIZE);
+        assert_eq!(*VarLong(0).size(), 1);
+        assert_eq!(*VarLong(127).size(), 1);
+        assert_eq!(*VarLong(128).size(), 2);
+        assert_eq!(*VarLong(i64::MAX).size(), 9);
+    }
 }